@@ -2,9 +2,133 @@ fn main() {
     let root = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
         .join("../..");
 
-    cc::Build::new()
+    let mut build = cc::Build::new();
+    if std::env::var_os("CARGO_FEATURE_BUNDLED_SQLITE3").is_some() {
+        // Only the vendored headers, never whichever sqlite3.h the system or
+        // CPATH happens to have, which may be too old for sqlite-vec.c.
+        let vendor = root.join("vendor");
+        for header in ["sqlite3.h", "sqlite3ext.h"] {
+            let path = vendor.join(header);
+            if !path.exists() {
+                panic!(
+                    "the bundled-sqlite3 feature needs {}, run ./scripts/vendor.sh first",
+                    path.display()
+                );
+            }
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+        build.include(&vendor);
+    }
+    build
         .file(root.join("sqlite-vec.c"))
         .include(&root)
-        .include(root.join("vendor"))
-        .compile("sqlite_vec0");
+        .include(root.join("vendor"));
+
+    if std::env::var_os("CARGO_FEATURE_LOADABLE_EXTENSION").is_some() {
+        // A loadable extension gets SQLite from the sqlite3_api_routines table
+        // it's loaded with, so SQLITE_CORE stays off. The C entrypoint is
+        // renamed so lib.rs can export a `sqlite3_vec_init` of its own, as a
+        // cdylib only exports symbols defined in Rust.
+        build.define("sqlite3_vec_init", "sqlite3_vec_init_c");
+    } else if std::env::var_os("CARGO_FEATURE_RUSQLITE").is_some()
+        || std::env::var_os("CARGO_FEATURE_SQLX").is_some()
+    {
+        // With the rusqlite or sqlx features, libsqlite3-sys already links
+        // SQLite into the final binary, so call sqlite3_* symbols directly
+        // instead of through the sqlite3_api_routines table. This lets `load()` run the
+        // entrypoint on an existing connection without an API pointer.
+        build.define("SQLITE_CORE", None);
+    }
+
+    let feature = |name: &str| std::env::var_os(format!("CARGO_FEATURE_{name}")).is_some();
+    let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
+
+    if std::env::var("CARGO_CFG_TARGET_FAMILY").is_ok_and(|f| f.split(',').any(|f| f == "wasm")) {
+        // WebAssembly gets the scalar kernels and no worker threads, for
+        // emscripten, WASI and wasm32-unknown-unknown alike.
+        build
+            .define("SQLITE_VEC_OMIT_THREADS", None)
+            .define("SQLITE_VEC_OMIT_RUNTIME_DISPATCH", None);
+        if target_os == "unknown" {
+            build.define("SQLITE_VEC_OMIT_FS", None);
+        }
+        // WASI_SDK_PATH picks wasi-sdk's clang and sysroot.
+        // wasm32-unknown-unknown has no libc of its own, so it also links the
+        // sysroot's libc.a.
+        println!("cargo:rerun-if-env-changed=WASI_SDK_PATH");
+        if let Some(sdk) = std::env::var_os("WASI_SDK_PATH").map(std::path::PathBuf::from) {
+            if target_os == "wasi" || target_os == "unknown" {
+                let sysroot = sdk.join("share/wasi-sysroot");
+                build
+                    .compiler(sdk.join("bin/clang"))
+                    .archiver(sdk.join("bin/llvm-ar"))
+                    .flag(format!("--sysroot={}", sysroot.display()));
+                if target_os == "unknown" {
+                    println!(
+                        "cargo:rustc-link-search=native={}",
+                        sysroot.join("lib/wasm32-wasi").display()
+                    );
+                    println!("cargo:rustc-link-lib=static=c");
+                }
+            }
+        }
+    }
+    if feature("NO_SIMD") {
+        // Plain C distance functions only: no NEON on aarch64, and none of
+        // the AVX2/AVX-512/SVE kernels picked at runtime.
+        if feature("AVX") || feature("NEON") {
+            panic!("the no-simd feature can't be combined with avx or neon");
+        }
+        build
+            .define("SQLITE_VEC_OMIT_NEON", None)
+            .define("SQLITE_VEC_OMIT_RUNTIME_DISPATCH", None);
+    }
+    let msvc = build.get_compiler().is_like_msvc();
+    let x86 = target_arch == "x86_64" || target_arch == "x86";
+    if msvc {
+        // cl.exe deprecates fopen(), strncpy() and friends in favor of its
+        // _s variants
+        build.define("_CRT_SECURE_NO_WARNINGS", None);
+    }
+    if feature("AVX") && !x86 {
+        panic!("the avx feature needs an x86 target, not {target_arch}");
+    }
+    if msvc && x86 && !feature("NO_SIMD") {
+        // The AVX2/AVX-512 kernels picked at runtime need GCC or Clang, so
+        // MSVC only gets the SIMD it's told the CPU has: the avx feature, or
+        // the Rust target's own features, like with
+        // `-C target-feature=+avx2` or `-C target-cpu=native`.
+        let target_features = std::env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+        let has = |name: &str| target_features.split(',').any(|f| f == name);
+        let arch = if has("avx512f") {
+            Some("AVX512")
+        } else if has("avx2") {
+            Some("AVX2")
+        } else if has("avx") || feature("AVX") {
+            Some("AVX")
+        } else {
+            None
+        };
+        if let Some(arch) = arch {
+            build
+                .flag(format!("/arch:{arch}"))
+                .define("SQLITE_VEC_ENABLE_AVX", None);
+        }
+    } else if feature("AVX") {
+        build.flag("-mavx").define("SQLITE_VEC_ENABLE_AVX", None);
+    }
+    if feature("NEON") {
+        // The NEON kernels use AArch64-only intrinsics, so 32-bit ARM targets
+        // need no-simd or the default build instead.
+        if target_arch != "aarch64" {
+            panic!("the neon feature needs an aarch64 target, not {target_arch}");
+        }
+        build.define("SQLITE_VEC_ENABLE_NEON", None);
+    }
+    if feature("OMIT_JSON") {
+        build.define("SQLITE_VEC_OMIT_JSON", None);
+    }
+
+    build.compile("sqlite_vec0");
 }
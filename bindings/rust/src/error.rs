@@ -0,0 +1,396 @@
+//! Errors from `sqlite-vec`, as an [`enum@Error`] to match on, with the
+//! `rusqlite` feature.
+//!
+//! sqlite-vec reports errors as SQLite error messages. [`Error::from()`]
+//! reads the ones an application is likely to handle into variants, and
+//! keeps everything else as [`Error::Sqlite`]:
+//!
+//! ```no_run
+//! # fn run(conn: &rusqlite::Connection, query: &str) -> rusqlite::Result<()> {
+//! use sqlite_vec::Error;
+//!
+//! let result = conn.execute("select rowid from docs where embedding match ?", [query]);
+//! match result.map_err(Error::from) {
+//!     Ok(_) => {}
+//!     Err(Error::DimensionMismatch { expected, actual, .. }) => {
+//!         eprintln!("the embedding model changed, {actual} dimensions instead of {expected}");
+//!     }
+//!     Err(Error::MissingK) => eprintln!("add a LIMIT"),
+//!     Err(err) => return Err(err.into()),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+/// Why a statement using `sqlite-vec` failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A vector has a different number of dimensions than its column, or
+    /// than the other vectors of a function call. `column` is `None` for
+    /// function calls.
+    DimensionMismatch {
+        column: Option<String>,
+        expected: usize,
+        actual: usize,
+    },
+    /// A vector has a different element type than its column, or than the
+    /// other vectors of a function call, like `int8` and `float32`.
+    ElementTypeMismatch {
+        column: Option<String>,
+        expected: String,
+        actual: String,
+    },
+    /// A value isn't a vector: malformed JSON, a BLOB of the wrong length, an
+    /// INTEGER, etc. Holds the whole message.
+    InvalidVector(String),
+    /// A KNN query has neither a `k = ?` nor a `LIMIT` constraint, and its
+    /// table no `default_k`.
+    MissingK,
+    /// The `k`, or `LIMIT` plus `OFFSET`, of a query is above `limit`, 4096 or
+    /// the table's `max_k`.
+    KTooLarge { k: i64, limit: i64 },
+    /// A `distance_metric` that sqlite-vec doesn't know. Holds the whole
+    /// message.
+    UnknownDistanceMetric(String),
+    /// Any other error in a `vec0` table's `CREATE VIRTUAL TABLE` arguments.
+    /// Holds the message without its `vec0 constructor error: ` prefix.
+    InvalidTableDefinition(String),
+    /// Everything else, as rusqlite reported it.
+    Sqlite(rusqlite::Error),
+}
+
+const CONSTRUCTOR_ERROR: &str = "vec0 constructor error: ";
+
+const INVALID_VECTOR: &[&str] = &[
+    "JSON array parsing error",
+    "JSON parsing error",
+    "zero-length vectors are not supported",
+    "vector BLOB length",
+    "Input must have type BLOB (compact format) or TEXT (JSON)",
+    "Unknown subtype: ",
+];
+
+/// The text between `start` and the following `end`.
+fn between<'a>(message: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let rest = &message[message.find(start)? + start.len()..];
+    Some(&rest[..rest.find(end)?])
+}
+
+fn number<T: std::str::FromStr>(message: &str, start: &str, end: &str) -> Option<T> {
+    between(message, start, end)?.parse().ok()
+}
+
+/// `the "name" column` of vec0 messages.
+fn column(message: &str) -> Option<String> {
+    between(message, "for the \"", "\" column").map(str::to_string)
+}
+
+fn parse(message: &str) -> Option<Error> {
+    if message.starts_with("Dimension mismatch for ") {
+        return Some(Error::DimensionMismatch {
+            column: column(message),
+            expected: number(message, "Expected ", " dimensions")?,
+            actual: number(message, "but received ", ".")?,
+        });
+    }
+    if message.starts_with("Vector dimension mistmatch. ") {
+        return Some(Error::DimensionMismatch {
+            column: None,
+            expected: number(message, "First vector has ", " dimensions")?,
+            actual: number(message, "while the second has ", " dimensions")?,
+        });
+    }
+    if message.starts_with("Vector dimension mismatch. ") {
+        return Some(Error::DimensionMismatch {
+            column: None,
+            expected: number(message, "The first vector has ", " dimensions")?,
+            actual: number(message, "a vector with ", " dimensions")?,
+        });
+    }
+    if message.contains(" column is expected to be of type ") {
+        return Some(Error::ElementTypeMismatch {
+            column: column(message),
+            expected: between(message, "of type ", ", but")?.to_string(),
+            actual: between(message, "but a ", " vector")?.to_string(),
+        });
+    }
+    if message.starts_with("Vector type mistmatch. ")
+        || message.starts_with("Vector type mismatch. ")
+    {
+        let expected = between(message, "has type ", ",")?;
+        let actual = match between(message, "while ", ".") {
+            Some(rest) => &rest[rest.rfind("has type ")? + "has type ".len()..],
+            None => between(message, "but a ", " vector")?,
+        };
+        return Some(Error::ElementTypeMismatch {
+            column: None,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+    if message.contains(" column is invalid: ")
+        || INVALID_VECTOR.iter().any(|m| message.contains(m))
+    {
+        return Some(Error::InvalidVector(message.to_string()));
+    }
+    if message.starts_with("A LIMIT or 'k = ?' constraint is required") {
+        return Some(Error::MissingK);
+    }
+    if message.contains(" too large, provided ") && message.contains(" and the limit is ") {
+        let limit = &message[message.find(" and the limit is ")? + " and the limit is ".len()..];
+        return Some(Error::KTooLarge {
+            k: number(message, " too large, provided ", " and")?,
+            limit: limit.parse().ok()?,
+        });
+    }
+    if message.contains("Unknown distance_metric") || message.contains("Unknown distance metric") {
+        return Some(Error::UnknownDistanceMetric(message.to_string()));
+    }
+    message
+        .strip_prefix(CONSTRUCTOR_ERROR)
+        .map(|rest| Error::InvalidTableDefinition(rest.to_string()))
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        match &err {
+            rusqlite::Error::SqliteFailure(_, Some(message)) => {
+                parse(message).unwrap_or(Error::Sqlite(err))
+            }
+            _ => Error::Sqlite(err),
+        }
+    }
+}
+
+impl From<Error> for rusqlite::Error {
+    /// The error as rusqlite would report it. Only [`Error::Sqlite`] keeps
+    /// its original SQLite error code, the others are `SQLITE_ERROR`s.
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Sqlite(err) => err,
+            err => rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+                Some(err.to_string()),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DimensionMismatch {
+                column: Some(column),
+                expected,
+                actual,
+            } => write!(
+                f,
+                "expected {expected} dimensions for the \"{column}\" column, got {actual}"
+            ),
+            Error::DimensionMismatch {
+                column: None,
+                expected,
+                actual,
+            } => {
+                write!(f, "expected {expected} dimensions, got {actual}")
+            }
+            Error::ElementTypeMismatch {
+                column: Some(column),
+                expected,
+                actual,
+            } => write!(
+                f,
+                "expected a {expected} vector for the \"{column}\" column, got {actual}"
+            ),
+            Error::ElementTypeMismatch {
+                column: None,
+                expected,
+                actual,
+            } => {
+                write!(f, "expected a {expected} vector, got {actual}")
+            }
+            Error::MissingK => write!(f, "KNN queries need a LIMIT or 'k = ?' constraint"),
+            Error::KTooLarge { k, limit } => write!(f, "k of {k} is above the limit of {limit}"),
+            Error::InvalidTableDefinition(message) => write!(f, "{CONSTRUCTOR_ERROR}{message}"),
+            Error::InvalidVector(message) | Error::UnknownDistanceMetric(message) => {
+                f.write_str(message)
+            }
+            Error::Sqlite(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Sqlite(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn error(conn: &Connection, sql: &str) -> Error {
+        let err = conn.query_row(sql, [], |_| Ok(())).unwrap_err();
+        Error::from(err)
+    }
+
+    fn dimensions(err: Error) -> (Option<String>, usize, usize) {
+        match err {
+            Error::DimensionMismatch {
+                column,
+                expected,
+                actual,
+            } => (column, expected, actual),
+            err => panic!("{err:?}"),
+        }
+    }
+
+    fn element_type(err: Error) -> (Option<String>, (String, String)) {
+        match err {
+            Error::ElementTypeMismatch {
+                column,
+                expected,
+                actual,
+            } => (column, (expected, actual)),
+            err => panic!("{err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_error() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            "create virtual table v using vec0(a float[2], b int8[2]);
+             create virtual table d using vec0(a float[2], default_k=2, max_k=10);",
+        )
+        .unwrap();
+
+        let some = |column: &str| Some(column.to_string());
+        let types = |expected: &str, actual: &str| (expected.to_string(), actual.to_string());
+        assert_eq!(
+            dimensions(error(
+                &conn,
+                "insert into v(a, b) values ('[1, 2, 3]', vec_int8('[1, 2]'))"
+            )),
+            (some("a"), 2, 3)
+        );
+        assert_eq!(
+            dimensions(error(
+                &conn,
+                "select rowid from v where a match '[1]' and k = 1"
+            )),
+            (some("a"), 2, 1)
+        );
+        assert_eq!(
+            dimensions(error(&conn, "select vec_distance_l2('[1, 2]', '[1]')")),
+            (None, 2, 1)
+        );
+        assert_eq!(
+            dimensions(error(
+                &conn,
+                "select vec_avg(value) from (select '[1, 2]' as value union all select '[1]')"
+            )),
+            (None, 2, 1)
+        );
+
+        assert_eq!(
+            element_type(error(
+                &conn,
+                "insert into v(a, b) values ('[1, 2]', '[1, 2]')"
+            )),
+            (some("b"), types("int8", "float32"))
+        );
+        assert_eq!(
+            element_type(error(
+                &conn,
+                "select rowid from v where b match '[1, 2]' and k = 1"
+            )),
+            (some("b"), types("int8", "float32"))
+        );
+        assert_eq!(
+            element_type(error(
+                &conn,
+                "select vec_distance_l2(vec_int8('[1]'), '[1]')"
+            )),
+            (None, types("int8", "float32"))
+        );
+        assert_eq!(
+            element_type(error(&conn, "select vec_concat('[1]', vec_int8('[1]'))")),
+            (None, types("float32", "int8"))
+        );
+
+        assert!(matches!(
+            error(&conn, "select vec_length('x')"),
+            Error::InvalidVector(_)
+        ));
+        assert!(matches!(
+            error(&conn, "select vec_length(1)"),
+            Error::InvalidVector(_)
+        ));
+        assert!(matches!(
+            error(
+                &conn,
+                "insert into v(a, b) values (x'00', vec_int8('[1, 2]'))"
+            ),
+            Error::InvalidVector(_)
+        ));
+        assert!(matches!(
+            error(&conn, "select rowid from v where a match '[1, 2]'"),
+            Error::MissingK
+        ));
+        assert!(matches!(
+            error(
+                &conn,
+                "select rowid from v where a match '[1, 2]' and k = 5000"
+            ),
+            Error::KTooLarge {
+                k: 5000,
+                limit: 4096
+            }
+        ));
+        assert!(matches!(
+            error(
+                &conn,
+                "select rowid from d where a match '[1, 2]' and k = 11"
+            ),
+            Error::KTooLarge { k: 11, limit: 10 }
+        ));
+        assert!(matches!(
+            Error::from(
+                conn.execute_batch(
+                    "create virtual table m using vec0(a float[2] distance_metric=l3)"
+                )
+                .unwrap_err()
+            ),
+            Error::UnknownDistanceMetric(_)
+        ));
+        match Error::from(
+            conn.execute_batch("create virtual table c using vec0(a float[2], chunk_size=7)")
+                .unwrap_err(),
+        ) {
+            Error::InvalidTableDefinition(message) => {
+                assert_eq!(message, "chunk_size must be divisible by 8")
+            }
+            err => panic!("{err:?}"),
+        }
+
+        let err = error(&conn, "select * from missing");
+        assert!(matches!(err, Error::Sqlite(_)));
+        assert_eq!(err.to_string(), "no such table: missing");
+        let err: rusqlite::Error =
+            error(&conn, "select rowid from v where a match '[1]' and k = 1").into();
+        assert_eq!(
+            err.to_string(),
+            "expected 2 dimensions for the \"a\" column, got 1"
+        );
+    }
+}
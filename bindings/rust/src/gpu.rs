@@ -0,0 +1,237 @@
+//! Offloading the distances of `vec0` KNN queries, like to CUDA or Metal, with
+//! the `gpu` feature.
+//!
+//! sqlite-vec hands a [`DistanceBackend`] whole chunks of `float32` vectors
+//! during a scan, and the candidates of `quantize=binary` and
+//! `prefilter='hamming'` rescoring passes. A backend computes them on the GPU
+//! with whichever library it likes, or declines and they're computed on the
+//! CPU as usual:
+//!
+//! ```no_run
+//! # fn gpu_distances(_: &[f32], _: &[f32], _: usize, _: &mut [f32]) -> bool { false }
+//! # fn run(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+//! use sqlite_vec::gpu::{set_distance_backend, Batch, Metric};
+//!
+//! set_distance_backend(
+//!     conn,
+//!     Some(|batch: Batch<'_>, out: &mut [f32]| {
+//!         // small batches aren't worth the copy to the device
+//!         batch.metric == Metric::L2
+//!             && batch.k >= 1000
+//!             && gpu_distances(batch.query, batch.vectors, batch.dimensions, out)
+//!     }),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::search::Metric;
+use rusqlite::{ffi, Connection};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+type DistancesCallback = unsafe extern "C" fn(
+    *mut c_void,
+    *const c_char,
+    *const f32,
+    *const f32,
+    c_int,
+    c_int,
+    i64,
+    *mut f32,
+) -> c_int;
+
+#[link(name = "sqlite_vec0")]
+extern "C" {
+    fn vec0_set_distance_handler(
+        db: *mut ffi::sqlite3,
+        x_distances: Option<DistancesCallback>,
+        p_arg: *mut c_void,
+        x_destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+}
+
+/// One batch of distances to compute.
+#[derive(Debug, Clone, Copy)]
+pub struct Batch<'a> {
+    pub metric: Metric,
+    /// `dimensions` values.
+    pub query: &'a [f32],
+    /// `dimensions` values per vector, one vector after the other. Chunks
+    /// include their unused slots, whose distances are ignored.
+    pub vectors: &'a [f32],
+    pub dimensions: usize,
+    /// How many rows the query returns.
+    pub k: i64,
+}
+
+/// Computes batches of distances for sqlite-vec, see [`set_distance_backend()`].
+pub trait DistanceBackend: Send + Sync + 'static {
+    /// Writes the distance between `batch.query` and each vector of
+    /// `batch.vectors` to `out`, which has room for one per vector, and
+    /// returns `true`. `false` has sqlite-vec compute the batch on the CPU
+    /// instead.
+    fn distances(&self, batch: Batch<'_>, out: &mut [f32]) -> bool;
+}
+
+impl<F> DistanceBackend for F
+where
+    F: Fn(Batch<'_>, &mut [f32]) -> bool + Send + Sync + 'static,
+{
+    fn distances(&self, batch: Batch<'_>, out: &mut [f32]) -> bool {
+        self(batch, out)
+    }
+}
+
+/// Offers the distances of KNN queries on `conn`'s `float32` `vec0` columns
+/// to `backend`. Tables with `threads=N` may call it from several threads at
+/// once. `None` removes the backend.
+///
+/// `conn` must have `sqlite-vec` loaded already. A panic in `backend` counts
+/// as `false`, so the batch is computed on the CPU.
+pub fn set_distance_backend<B>(conn: &Connection, backend: Option<B>) -> rusqlite::Result<()>
+where
+    B: DistanceBackend,
+{
+    #[allow(clippy::too_many_arguments)]
+    unsafe extern "C" fn call<B: DistanceBackend>(
+        arg: *mut c_void,
+        metric: *const c_char,
+        query: *const f32,
+        vectors: *const f32,
+        n: c_int,
+        dimensions: c_int,
+        k: i64,
+        distances: *mut f32,
+    ) -> c_int {
+        // SAFETY: `arg` is the Box<B> from set_distance_backend(), metric is
+        // nul-terminated, query has `dimensions` values and vectors and
+        // distances room for `n` vectors and distances
+        let backend = &*(arg as *const B);
+        let Some(metric) = CStr::from_ptr(metric).to_str().ok().and_then(Metric::from_name) else {
+            return 1;
+        };
+        let (n, dimensions) = (n as usize, dimensions as usize);
+        let batch = Batch {
+            metric,
+            query: std::slice::from_raw_parts(query, dimensions),
+            vectors: std::slice::from_raw_parts(vectors, n * dimensions),
+            dimensions,
+            k,
+        };
+        let out = std::slice::from_raw_parts_mut(distances, n);
+        match catch_unwind(AssertUnwindSafe(|| backend.distances(batch, out))) {
+            Ok(true) => 0,
+            _ => 1,
+        }
+    }
+
+    unsafe extern "C" fn destroy<B>(arg: *mut c_void) {
+        drop(Box::from_raw(arg as *mut B));
+    }
+
+    let rc = match backend {
+        // SAFETY: sqlite-vec owns the box until it calls destroy(), and B is
+        // Sync for the worker threads of threads=N tables
+        Some(backend) => unsafe {
+            vec0_set_distance_handler(
+                conn.handle(),
+                Some(call::<B>),
+                Box::into_raw(Box::new(backend)).cast(),
+                Some(destroy::<B>),
+            )
+        },
+        None => unsafe {
+            vec0_set_distance_handler(conn.handle(), None, std::ptr::null_mut(), None)
+        },
+    };
+    if rc != ffi::SQLITE_OK {
+        return Err(crate::statement::error(conn, rc));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn l2(batch: Batch<'_>, out: &mut [f32]) {
+        for (vector, distance) in batch.vectors.chunks(batch.dimensions).zip(out) {
+            let sum: f32 = vector.iter().zip(batch.query).map(|(a, b)| (a - b) * (a - b)).sum();
+            *distance = sum.sqrt();
+        }
+    }
+
+    fn knn(conn: &Connection, table: &str) -> Vec<(i64, f64)> {
+        conn.prepare(&format!(
+            "select rowid, distance from {table} where a match '[4.2, 0, 1, 0, 0, 0, 0, 0]' and k = 5"
+        ))
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_set_distance_backend() {
+        let guard = crate::tests::auto_extensions();
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(set_distance_backend(&conn, Some(|_: Batch<'_>, _: &mut [f32]| false)).is_err());
+        drop(guard);
+
+        crate::load(&conn).unwrap();
+        for table in [
+            "v using vec0(a float[8], chunk_size=8)",
+            "t using vec0(a float[8], chunk_size=8, threads=2)",
+            "b using vec0(a float[8] quantize=binary(rescore=2), chunk_size=8)",
+        ] {
+            conn.execute_batch(&format!("create virtual table {table}")).unwrap();
+        }
+        for table in ["v", "t", "b"] {
+            for i in 1..=40 {
+                conn.execute(
+                    &format!("insert into {table}(rowid, a) values (?, ?)"),
+                    rusqlite::params![i, format!("[{i}, {}, 1, 0, 0, 0, 0, 0]", i % 3)],
+                )
+                .unwrap();
+            }
+        }
+        let expected: Vec<_> = ["v", "t", "b"].iter().map(|table| knn(&conn, table)).collect();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        set_distance_backend(
+            &conn,
+            Some(move |batch: Batch<'_>, out: &mut [f32]| {
+                assert_eq!((batch.metric, batch.dimensions, batch.k), (Metric::L2, 8, 5));
+                counted.fetch_add(1, Ordering::Relaxed);
+                l2(batch, out);
+                true
+            }),
+        )
+        .unwrap();
+        assert_eq!(knn(&conn, "v"), expected[0]);
+        // one call per chunk
+        assert_eq!(calls.swap(0, Ordering::Relaxed), 5);
+        assert_eq!(knn(&conn, "t"), expected[1]);
+        assert_eq!(calls.swap(0, Ordering::Relaxed), 5);
+        // only the rescoring of binary columns
+        assert_eq!(knn(&conn, "b"), expected[2]);
+        assert_eq!(calls.swap(0, Ordering::Relaxed), 1);
+
+        // declined batches and panics fall back to the CPU
+        set_distance_backend(&conn, Some(|_: Batch<'_>, _: &mut [f32]| false)).unwrap();
+        assert_eq!(Arc::strong_count(&calls), 1);
+        assert_eq!(knn(&conn, "v"), expected[0]);
+        set_distance_backend(&conn, Some(|_: Batch<'_>, _: &mut [f32]| -> bool { panic!() }))
+            .unwrap();
+        assert_eq!(knn(&conn, "t"), expected[1]);
+
+        set_distance_backend(&conn, None::<fn(Batch<'_>, &mut [f32]) -> bool>).unwrap();
+        assert_eq!(knn(&conn, "b"), expected[2]);
+    }
+}
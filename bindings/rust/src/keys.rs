@@ -0,0 +1,156 @@
+//! Keys of `encrypt='aes-gcm'` `vec0` tables, with the `rusqlite` feature.
+//!
+//! ```no_run
+//! # fn key_from_vault(table: &str) -> Option<[u8; 32]> { None }
+//! # fn run(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+//! sqlite_vec::keys::set_key_handler(
+//!     conn,
+//!     Some(|_schema: &str, table: &str| key_from_vault(table)),
+//! )?;
+//! conn.execute_batch(
+//!     "create virtual table faces using vec0(embedding float[512], encrypt='aes-gcm')",
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use rusqlite::{ffi, Connection};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_uchar, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Size of the AES-256 key of an encrypted table.
+pub const KEY_SIZE: usize = 32;
+
+type KeyCallback =
+    unsafe extern "C" fn(*mut c_void, *const c_char, *const c_char, *mut c_uchar) -> c_int;
+
+#[link(name = "sqlite_vec0")]
+extern "C" {
+    fn vec0_set_key_handler(
+        db: *mut ffi::sqlite3,
+        x_key: Option<KeyCallback>,
+        p_arg: *mut c_void,
+        x_destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+}
+
+/// Calls `handler` with the schema and name of an `encrypt='aes-gcm'` table
+/// of `conn` that `vec0_set_key()` has no key for, when it's created and the
+/// first time it reads or writes a vector. `None` from `handler` leaves the
+/// table without a key, so the statement fails. `None` removes the handler.
+///
+/// `conn` must have `sqlite-vec` loaded already. A panic in `handler` counts
+/// as `None`.
+pub fn set_key_handler<F>(conn: &Connection, handler: Option<F>) -> rusqlite::Result<()>
+where
+    F: FnMut(&str, &str) -> Option<[u8; KEY_SIZE]> + Send + 'static,
+{
+    unsafe extern "C" fn call<F>(
+        arg: *mut c_void,
+        schema: *const c_char,
+        table: *const c_char,
+        key: *mut c_uchar,
+    ) -> c_int
+    where
+        F: FnMut(&str, &str) -> Option<[u8; KEY_SIZE]>,
+    {
+        // SAFETY: `arg` is the Box<F> from set_key_handler(), both strings
+        // are nul-terminated and live for the duration of the call, and key
+        // has room for KEY_SIZE bytes
+        let handler = &mut *(arg as *mut F);
+        let schema = CStr::from_ptr(schema).to_str().unwrap_or_default();
+        let table = CStr::from_ptr(table).to_str().unwrap_or_default();
+        match catch_unwind(AssertUnwindSafe(|| handler(schema, table))) {
+            Ok(Some(found)) => {
+                std::ptr::copy_nonoverlapping(found.as_ptr(), key, KEY_SIZE);
+                1
+            }
+            _ => 0,
+        }
+    }
+
+    unsafe extern "C" fn destroy<F>(arg: *mut c_void) {
+        drop(Box::from_raw(arg as *mut F));
+    }
+
+    let rc = match handler {
+        // SAFETY: sqlite-vec owns the box until it calls destroy(), and only
+        // calls call() on this connection's thread
+        Some(handler) => unsafe {
+            vec0_set_key_handler(
+                conn.handle(),
+                Some(call::<F>),
+                Box::into_raw(Box::new(handler)).cast(),
+                Some(destroy::<F>),
+            )
+        },
+        None => unsafe {
+            vec0_set_key_handler(conn.handle(), None, std::ptr::null_mut(), None)
+        },
+    };
+    if rc != ffi::SQLITE_OK {
+        return Err(crate::statement::error(conn, rc));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_set_key_handler() {
+        let guard = crate::tests::auto_extensions();
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(set_key_handler(&conn, Some(|_: &str, _: &str| None)).is_err());
+        drop(guard);
+
+        crate::load(&conn).unwrap();
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let sink = asked.clone();
+        set_key_handler(
+            &conn,
+            Some(move |schema: &str, table: &str| {
+                sink.lock().unwrap().push(format!("{schema}.{table}"));
+                (table == "v").then_some([7u8; KEY_SIZE])
+            }),
+        )
+        .unwrap();
+        conn.execute_batch(
+            "create virtual table v using vec0(a float[2], encrypt='aes-gcm');
+             insert into v(rowid, a) values (1, '[1, 2]'), (2, '[3, 4]');",
+        )
+        .unwrap();
+        let nearest: i64 = conn
+            .query_row(
+                "select rowid from v where a match '[3, 4]' and k = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(nearest, 2);
+        // asked once, when the table was created
+        assert_eq!(*asked.lock().unwrap(), ["main.v"]);
+
+        let err = conn
+            .execute_batch("create virtual table w using vec0(a float[2], encrypt='aes-gcm')")
+            .unwrap_err();
+        assert!(err.to_string().contains("w is encrypt='aes-gcm' but has no key"), "{err}");
+
+        // keys from handlers are checked too, and vec0_set_key() comes first
+        set_key_handler(&conn, Some(|_: &str, _: &str| Some([8u8; KEY_SIZE]))).unwrap();
+        assert_eq!(Arc::strong_count(&asked), 1);
+        let err = conn
+            .query_row("select a from v where rowid = 1", [], |row| row.get::<_, Vec<u8>>(0))
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't the key it was created with"), "{err}");
+        conn.query_row("select vec0_set_key('v', ?)", [[7u8; KEY_SIZE].as_slice()], |_| Ok(()))
+            .unwrap();
+        conn.query_row("select a from v where rowid = 1", [], |row| row.get::<_, Vec<u8>>(0))
+            .unwrap();
+
+        set_key_handler(&conn, None::<fn(&str, &str) -> Option<[u8; KEY_SIZE]>>).unwrap();
+    }
+}
@@ -1,16 +1,171 @@
+#[cfg(not(feature = "loadable-extension"))]
 #[link(name = "sqlite_vec0")]
 extern "C" {
     pub fn sqlite3_vec_init();
 }
 
+#[cfg(all(feature = "loadable-extension", feature = "rusqlite"))]
+compile_error!(
+    "the loadable-extension and rusqlite features can't be combined, load() \
+     and auto() need SQLite linked into the same binary"
+);
+
+#[cfg(all(feature = "loadable-extension", feature = "sqlx"))]
+compile_error!(
+    "the loadable-extension and sqlx features can't be combined, \
+     sqlx::register() needs SQLite linked into the same binary"
+);
+
+#[cfg(feature = "loadable-extension")]
+#[link(name = "sqlite_vec0")]
+extern "C" {
+    // sqlite-vec.c's `sqlite3_vec_init`, renamed by build.rs
+    fn sqlite3_vec_init_c(
+        db: *mut std::os::raw::c_void,
+        pz_err_msg: *mut *mut std::os::raw::c_char,
+        p_api: *const std::os::raw::c_void,
+    ) -> std::os::raw::c_int;
+}
+
+/// The SQLite extension entrypoint, exported from the shared library built
+/// with `cargo rustc --features loadable-extension --crate-type cdylib`.
+///
+/// # Safety
+///
+/// Only meant to be called by SQLite when it loads the extension, with a valid
+/// connection and the `sqlite3_api_routines` table of that SQLite library.
+#[cfg(feature = "loadable-extension")]
+#[no_mangle]
+pub unsafe extern "C" fn sqlite3_vec_init(
+    db: *mut std::os::raw::c_void,
+    pz_err_msg: *mut *mut std::os::raw::c_char,
+    p_api: *const std::os::raw::c_void,
+) -> std::os::raw::c_int {
+    sqlite3_vec_init_c(db, pz_err_msg, p_api)
+}
+
+#[cfg(feature = "rusqlite")]
+pub mod error;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "rusqlite")]
+pub mod keys;
+#[cfg(feature = "rusqlite")]
+pub mod mirror;
+#[cfg(feature = "rusqlite")]
+pub mod progress;
+pub mod search;
+#[cfg(feature = "rusqlite")]
+pub mod statement;
+#[cfg(feature = "rusqlite")]
+pub mod stats;
+#[cfg(feature = "rusqlite")]
+pub mod table;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod vector;
+
+#[cfg(feature = "rusqlite")]
+pub use error::Error;
+#[cfg(feature = "rusqlite")]
+pub use mirror::Vec0Mirror;
+#[cfg(feature = "rusqlite")]
+pub use table::{Knn, Vec0Table};
+
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+
+#[cfg(feature = "rusqlite")]
+type EntryPoint = unsafe extern "C" fn(
+    *mut rusqlite::ffi::sqlite3,
+    *mut *mut std::os::raw::c_char,
+    *const rusqlite::ffi::sqlite3_api_routines,
+) -> std::os::raw::c_int;
+
+#[cfg(feature = "rusqlite")]
+fn entrypoint() -> EntryPoint {
+    // SAFETY: `sqlite3_vec_init` is declared without arguments above for
+    // backwards compatibility, but the C symbol has the standard SQLite
+    // extension entrypoint signature.
+    unsafe { std::mem::transmute::<*const (), EntryPoint>(sqlite3_vec_init as *const ()) }
+}
+
+/// Registers `sqlite-vec` with `sqlite3_auto_extension()`, so every
+/// connection opened afterwards in this process has the `vec0` module and
+/// all `vec_*` functions available.
+///
+/// Calling this more than once is harmless, SQLite ignores duplicate
+/// registrations of the same entrypoint.
+#[cfg(feature = "rusqlite")]
+pub fn auto() -> rusqlite::Result<()> {
+    let rc = unsafe { rusqlite::ffi::sqlite3_auto_extension(Some(entrypoint())) };
+    if rc != rusqlite::ffi::SQLITE_OK {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rc),
+            Some("could not register sqlite-vec as an auto extension".to_string()),
+        ));
+    }
+    Ok(())
+}
+
+/// Loads `sqlite-vec` into an already-open connection.
+///
+/// Unlike [`auto()`], this only affects `conn`, which is handy when other
+/// connections in the process shouldn't see the extension.
+#[cfg(feature = "rusqlite")]
+pub fn load(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let mut err: *mut std::os::raw::c_char = std::ptr::null_mut();
+    // SAFETY: the handle stays valid for the lifetime of `conn`, and the
+    // crate is compiled with SQLITE_CORE under this feature so the API
+    // routines pointer is never dereferenced.
+    let rc = unsafe { entrypoint()(conn.handle(), &mut err, std::ptr::null()) };
+    if rc != rusqlite::ffi::SQLITE_OK {
+        let message = if err.is_null() {
+            None
+        } else {
+            let message = unsafe { std::ffi::CStr::from_ptr(err) }
+                .to_string_lossy()
+                .into_owned();
+            unsafe { rusqlite::ffi::sqlite3_free(err.cast()) };
+            Some(message)
+        };
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rc),
+            message,
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use rusqlite::{ffi::sqlite3_auto_extension, Connection};
+    use std::sync::{Mutex, MutexGuard};
+
+    static AUTO_EXTENSIONS: Mutex<()> = Mutex::new(());
+
+    /// Held by tests that register auto extensions, which every connection
+    /// in the process then gets, and by tests that need a connection without
+    /// sqlite-vec. Dropping it unregisters them again.
+    pub(crate) struct AutoExtensionGuard(#[allow(dead_code)] MutexGuard<'static, ()>);
+
+    impl Drop for AutoExtensionGuard {
+        fn drop(&mut self) {
+            unsafe { rusqlite::ffi::sqlite3_reset_auto_extension() };
+        }
+    }
+
+    pub(crate) fn auto_extensions() -> AutoExtensionGuard {
+        let lock = AUTO_EXTENSIONS.lock();
+        AutoExtensionGuard(lock.unwrap_or_else(|err| err.into_inner()))
+    }
 
     #[test]
+    #[allow(clippy::missing_transmute_annotations)]
     fn test_rusqlite_auto_extension() {
+        let _guard = auto_extensions();
         unsafe {
             sqlite3_auto_extension(Some(std::mem::transmute(sqlite3_vec_init as *const ())));
         }
@@ -23,4 +178,28 @@ mod tests {
 
         assert!(result.starts_with("v"));
     }
+
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn test_auto() {
+        let _guard = auto_extensions();
+        auto().unwrap();
+        auto().unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        let result: String = conn
+            .query_row("select vec_version()", [], |x| x.get(0))
+            .unwrap();
+        assert!(result.starts_with("v"));
+    }
+
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn test_load() {
+        let conn = Connection::open_in_memory().unwrap();
+        load(&conn).unwrap();
+        let result: String = conn
+            .query_row("select vec_to_json(vec_f32('[1, 2]'))", [], |x| x.get(0))
+            .unwrap();
+        assert_eq!(result, "[1.000000,2.000000]");
+    }
 }
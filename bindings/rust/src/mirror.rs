@@ -0,0 +1,513 @@
+//! An in-process copy of a `vec0` table's vectors, for KNN queries that can't
+//! wait on SQLite, with the `rusqlite` feature.
+//!
+//! The table stays the source of truth: a [`Vec0Mirror`] reads every vector
+//! once, then catches up with the table's `changelog=on` changes on each
+//! [`Vec0Mirror::refresh()`]:
+//!
+//! ```no_run
+//! # fn run(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+//! use sqlite_vec::Vec0Mirror;
+//!
+//! let mut mirror = Vec0Mirror::load(conn, "docs")?;
+//! conn.execute("delete from docs where rowid = 42", [])?;
+//! mirror.refresh(conn)?;
+//! for result in mirror.knn(&[0.1, 0.2, 0.3], 10) {
+//!     println!("{} {}", result.rowid, result.distance);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::search::{Metric, SearchResult};
+use crate::table::{quote, Vec0Table};
+use crate::vector::Float32Vector;
+use rusqlite::types::ValueRef;
+use rusqlite::{ffi, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+fn error(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_ERROR), Some(message))
+}
+
+/// The `float32` vectors of one column of a `vec0` table, held in memory and
+/// searched by brute force.
+///
+/// A mirror doesn't hold on to the connection, so it can be shared between
+/// threads behind a `RwLock`, with one of them calling `refresh()`.
+#[derive(Debug, Clone)]
+pub struct Vec0Mirror {
+    table: String,
+    key: String,
+    column: String,
+    metric: Metric,
+    dimensions: usize,
+    // `dimensions` values per row, in the same order as `rowids`
+    vectors: Vec<f32>,
+    rowids: Vec<i64>,
+    positions: HashMap<i64, usize>,
+    change_id: i64,
+}
+
+impl Vec0Mirror {
+    /// Reads every vector of the `vec0` table `table` in the `main` schema,
+    /// which needs exactly one vector column, `changelog=on`, and integer
+    /// rowids or primary keys.
+    pub fn load(conn: &Connection, table: &str) -> rusqlite::Result<Self> {
+        Self::load_inner(conn, table, None)
+    }
+
+    /// Like [`Vec0Mirror::load()`], for the vector column `column` of a table
+    /// with more than one.
+    pub fn load_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<Self> {
+        Self::load_inner(conn, table, Some(column))
+    }
+
+    fn load_inner(conn: &Connection, table: &str, column: Option<&str>) -> rusqlite::Result<Self> {
+        let vec0 = Vec0Table::open(conn, table)?;
+        let column = vec0.vector_column(column)?.to_string();
+
+        let mut info = HashMap::new();
+        let mut stmt =
+            conn.prepare("select key, cast(value as text) from vec0_info(?) where name = ?")?;
+        let mut rows = stmt.query([table, column.as_str()])?;
+        while let Some(row) = rows.next()? {
+            info.insert(row.get::<_, String>(0)?, row.get::<_, String>(1)?);
+        }
+        let info = |key: &str| info.get(key).map(String::as_str).unwrap_or_default();
+        if info("element_type") != "float32" || info("quantize") != "none" {
+            return Err(error(format!(
+                "{table}.{column} can't be mirrored, only float32 columns without quantize can"
+            )));
+        }
+        let metric = Metric::from_name(info("distance_metric")).ok_or_else(|| {
+            error(format!(
+                "{table}.{column} can't be mirrored, its distance_metric isn't l2, l1, cosine or dot"
+            ))
+        })?;
+        let dimensions = info("dimensions").parse().unwrap_or_default();
+
+        let changelog: Option<i64> = conn
+            .query_row(
+                "select 1 from sqlite_master where type = 'table' and name = ?",
+                [format!("{table}_changelog")],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if changelog.is_none() {
+            return Err(error(format!(
+                "{table} can't be mirrored without the changelog=on table option"
+            )));
+        }
+        let mut mirror = Vec0Mirror {
+            table: table.to_string(),
+            key: vec0.key,
+            column,
+            metric,
+            dimensions,
+            vectors: Vec::new(),
+            rowids: Vec::new(),
+            positions: HashMap::new(),
+            change_id: 0,
+        };
+        mirror.reload(conn)?;
+        Ok(mirror)
+    }
+
+    /// Applies the changes made to the table since the mirror was loaded or
+    /// last refreshed, and returns how many rows of the mirror were added,
+    /// replaced or removed.
+    ///
+    /// When some of those changes were already deleted with
+    /// `'purge_changelog'`, the mirror can't tell which rows they were, so it
+    /// reads the whole table again and returns its number of rows.
+    pub fn refresh(&mut self, conn: &Connection) -> rusqlite::Result<usize> {
+        // one read transaction, so the rows match the last change_id read
+        let tx = if conn.is_autocommit() {
+            Some(conn.unchecked_transaction()?)
+        } else {
+            None
+        };
+        let change_id = self.last_change_id(conn)?;
+        let changes: i64 = conn.query_row(
+            &format!(
+                "select count(*) from {} where change_id > ?1 and change_id <= ?2",
+                quote(&format!("{}_changelog", self.table))
+            ),
+            [self.change_id, change_id],
+            |row| row.get(0),
+        )?;
+        // change_id is AUTOINCREMENT, so only a purge leaves gaps
+        if changes != change_id - self.change_id {
+            self.reload(conn)?;
+            if let Some(tx) = tx {
+                tx.commit()?;
+            }
+            return Ok(self.rowids.len());
+        }
+
+        let mut ids = conn.prepare(&format!(
+            "select id from {} where change_id > ?1 and change_id <= ?2 group by id",
+            quote(&format!("{}_changelog", self.table))
+        ))?;
+        let mut row = conn.prepare(&format!(
+            "select {} from {} where {} = ?",
+            quote(&self.column),
+            quote(&self.table),
+            quote(&self.key)
+        ))?;
+        let mut changed = 0;
+        let mut rows = ids.query([self.change_id, change_id])?;
+        while let Some(id) = rows.next()? {
+            let rowid = self.rowid(id.get_ref(0)?)?;
+            // a deleted row, or one whose vector is NULL now
+            let vector: Option<Float32Vector> = row
+                .query_row([rowid], |row| row.get(0))
+                .optional()?
+                .flatten();
+            match vector {
+                Some(vector) => self.upsert(rowid, &vector.0)?,
+                None => self.remove(rowid),
+            }
+            changed += 1;
+        }
+        self.change_id = change_id;
+        drop(rows);
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
+        Ok(changed)
+    }
+
+    /// The rows nearest to `query`, nearest first, with the same distances
+    /// as a KNN query on the table. Ties are broken by rowid.
+    ///
+    /// # Panics
+    ///
+    /// If `query` doesn't have [`dimensions()`](Vec0Mirror::dimensions)
+    /// values.
+    pub fn knn(&self, query: &[f32], k: usize) -> Vec<SearchResult> {
+        assert_eq!(
+            query.len(),
+            self.dimensions,
+            "query vector for {}.{} has the wrong number of dimensions",
+            self.table,
+            self.column
+        );
+        let mut distances: Vec<(f32, i64)> = self
+            .rowids
+            .iter()
+            .zip(self.vectors.chunks_exact(self.dimensions.max(1)))
+            .map(|(rowid, vector)| (distance(self.metric, query, vector), *rowid))
+            .collect();
+        let by_distance = |a: &(f32, i64), b: &(f32, i64)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+        if k < distances.len() {
+            distances.select_nth_unstable_by(k, by_distance);
+            distances.truncate(k);
+        }
+        distances.sort_unstable_by(by_distance);
+        distances
+            .into_iter()
+            .map(|(distance, rowid)| SearchResult {
+                rowid,
+                distance: distance as f64,
+                metadata: (),
+            })
+            .collect()
+    }
+
+    /// The vector of `rowid`, if the mirror has it.
+    pub fn get(&self, rowid: i64) -> Option<&[f32]> {
+        let position = *self.positions.get(&rowid)?;
+        Some(&self.vectors[position * self.dimensions..(position + 1) * self.dimensions])
+    }
+
+    /// Number of rows in the mirror.
+    pub fn len(&self) -> usize {
+        self.rowids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rowids.is_empty()
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The `change_id` of the table's last change the mirror has, 0 before
+    /// any.
+    pub fn change_id(&self) -> i64 {
+        self.change_id
+    }
+
+    /// The last `change_id` ever given out by the table's changelog, even if
+    /// it was purged since.
+    fn last_change_id(&self, conn: &Connection) -> rusqlite::Result<i64> {
+        Ok(conn
+            .query_row(
+                "select seq from sqlite_sequence where name = ?",
+                [format!("{}_changelog", self.table)],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0))
+    }
+
+    fn reload(&mut self, conn: &Connection) -> rusqlite::Result<()> {
+        let tx = if conn.is_autocommit() {
+            Some(conn.unchecked_transaction()?)
+        } else {
+            None
+        };
+        let change_id = self.last_change_id(conn)?;
+        self.vectors.clear();
+        self.rowids.clear();
+        self.positions.clear();
+        let mut stmt = conn.prepare(&format!(
+            "select {}, {} from {}",
+            quote(&self.key),
+            quote(&self.column),
+            quote(&self.table)
+        ))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            // rows with a NULL vector aren't mirrored
+            let vector: Option<Float32Vector> = row.get(1)?;
+            if let Some(vector) = vector {
+                self.upsert(self.rowid(row.get_ref(0)?)?, &vector.0)?;
+            }
+        }
+        self.change_id = change_id;
+        drop(rows);
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    fn rowid(&self, key: ValueRef<'_>) -> rusqlite::Result<i64> {
+        match key {
+            ValueRef::Integer(rowid) => Ok(rowid),
+            _ => Err(error(format!(
+                "{} can't be mirrored, its primary key isn't an integer",
+                self.table
+            ))),
+        }
+    }
+
+    fn upsert(&mut self, rowid: i64, vector: &[f32]) -> rusqlite::Result<()> {
+        if vector.len() != self.dimensions {
+            return Err(error(format!(
+                "{}.{} of row {rowid} has {} dimensions instead of {}",
+                self.table,
+                self.column,
+                vector.len(),
+                self.dimensions
+            )));
+        }
+        match self.positions.get(&rowid) {
+            Some(&position) => self.vectors
+                [position * self.dimensions..(position + 1) * self.dimensions]
+                .copy_from_slice(vector),
+            None => {
+                self.positions.insert(rowid, self.rowids.len());
+                self.rowids.push(rowid);
+                self.vectors.extend_from_slice(vector);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, rowid: i64) {
+        let Some(position) = self.positions.remove(&rowid) else {
+            return;
+        };
+        // the last row takes the removed row's place
+        let last = self.rowids.len() - 1;
+        self.rowids.swap_remove(position);
+        if position != last {
+            self.positions.insert(self.rowids[position], position);
+            let (head, tail) = self.vectors.split_at_mut(last * self.dimensions);
+            head[position * self.dimensions..(position + 1) * self.dimensions]
+                .copy_from_slice(tail);
+        }
+        self.vectors.truncate(last * self.dimensions);
+    }
+}
+
+fn distance(metric: Metric, a: &[f32], b: &[f32]) -> f32 {
+    let pairs = a.iter().zip(b);
+    match metric {
+        Metric::L2 => pairs.map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt(),
+        Metric::L1 => pairs.map(|(a, b)| (a - b).abs()).sum(),
+        Metric::Dot => -pairs.map(|(a, b)| a * b).sum::<f32>(),
+        Metric::Cosine => {
+            let (mut ab, mut aa, mut bb) = (0.0f32, 0.0f32, 0.0f32);
+            for (a, b) in pairs {
+                ab += a * b;
+                aa += a * a;
+                bb += b * b;
+            }
+            1.0 - ab / (aa.sqrt() * bb.sqrt())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Float32Vector;
+
+    fn docs() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            create virtual table docs using vec0(
+              embedding float[2] distance_metric=l1,
+              chunk_size=8,
+              changelog=on
+            );
+            insert into docs(rowid, embedding)
+              select value, json_array(value, -value) from json_each('[1, 2, 3, 4, 5, 6]');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn knn(conn: &Connection, query: &[f32], k: i64) -> Vec<SearchResult> {
+        let query = Float32Vector(query.to_vec());
+        conn.prepare("select rowid, distance from docs where embedding match ? and k = ?")
+            .unwrap()
+            .query_map(rusqlite::params![query, k], |row| {
+                Ok(SearchResult {
+                    rowid: row.get(0)?,
+                    distance: row.get(1)?,
+                    metadata: (),
+                })
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mirror_refresh() {
+        let conn = docs();
+        let mut mirror = Vec0Mirror::load(&conn, "docs").unwrap();
+        assert_eq!((mirror.len(), mirror.dimensions()), (6, 2));
+        assert_eq!(mirror.metric(), Metric::L1);
+        assert_eq!(mirror.change_id(), 6);
+        assert_eq!(mirror.knn(&[2.9, -2.9], 3), knn(&conn, &[2.9, -2.9], 3));
+        assert_eq!(mirror.get(4), Some(&[4.0, -4.0][..]));
+
+        conn.execute_batch(
+            r#"
+            delete from docs where rowid in (1, 3);
+            update docs set embedding = '[100, 100]' where rowid = 2;
+            insert into docs(rowid, embedding) values (7, '[3, -3]'), (8, '[0, 0]');
+            delete from docs where rowid = 8;
+            "#,
+        )
+        .unwrap();
+        assert_eq!(mirror.len(), 6);
+        assert_eq!(mirror.refresh(&conn).unwrap(), 5);
+        assert_eq!(mirror.refresh(&conn).unwrap(), 0);
+        assert_eq!(mirror.len(), 5);
+        assert_eq!(mirror.get(3), None);
+        assert_eq!(mirror.get(2), Some(&[100.0, 100.0][..]));
+        for query in [[2.9, -2.9], [99.0, 99.0], [0.0, 0.0]] {
+            assert_eq!(mirror.knn(&query, 10), knn(&conn, &query, 10));
+        }
+        assert!(mirror.knn(&[0.0, 0.0], 0).is_empty());
+
+        // purged changes can't be replayed, so the mirror reads the table again
+        conn.execute_batch(
+            r#"
+            delete from docs where rowid = 4;
+            insert into docs(docs) values ('purge_changelog');
+            "#,
+        )
+        .unwrap();
+        assert_eq!(mirror.refresh(&conn).unwrap(), 4);
+        assert_eq!(mirror.knn(&[0.0, 0.0], 10), knn(&conn, &[0.0, 0.0], 10));
+        assert_eq!(mirror.change_id(), 13);
+    }
+
+    #[test]
+    fn test_mirror_null_vectors() {
+        let conn = docs();
+        conn.execute_batch("insert into docs(rowid) values (7), (8)")
+            .unwrap();
+        // rows waiting for a vector aren't in the mirror until they get one
+        let mut mirror = Vec0Mirror::load(&conn, "docs").unwrap();
+        assert_eq!(mirror.len(), 6);
+        assert_eq!(mirror.get(7), None);
+
+        conn.execute_batch(
+            r#"
+            update docs set embedding = '[7, -7]' where rowid = 7;
+            insert into docs(rowid) values (9);
+            "#,
+        )
+        .unwrap();
+        assert_eq!(mirror.refresh(&conn).unwrap(), 2);
+        assert_eq!(mirror.len(), 7);
+        assert_eq!(mirror.get(7), Some(&[7.0, -7.0][..]));
+        assert_eq!(mirror.get(9), None);
+        assert_eq!(mirror.knn(&[8.0, -8.0], 10), knn(&conn, &[8.0, -8.0], 10));
+    }
+
+    #[test]
+    fn test_mirror_errors() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            create virtual table plain using vec0(embedding float[2]);
+            create virtual table bits using vec0(embedding bit[8], changelog=on);
+            create virtual table texts using vec0(
+              id text primary key,
+              embedding float[2],
+              changelog=on
+            );
+            insert into texts(id, embedding) values ('a', '[1, 1]');
+            create virtual table two using vec0(a float[2], b float[2] distance_metric=cosine, changelog=on);
+            "#,
+        )
+        .unwrap();
+        for (table, message) in [
+            (
+                "plain",
+                "plain can't be mirrored without the changelog=on table option",
+            ),
+            (
+                "bits",
+                "bits.embedding can't be mirrored, only float32 columns",
+            ),
+            (
+                "texts",
+                "texts can't be opened as a Vec0Table, its primary key isn't an integer",
+            ),
+            ("two", "two has 2 vector columns, pick one with column()"),
+        ] {
+            let err = Vec0Mirror::load(&conn, table).unwrap_err();
+            assert!(err.to_string().contains(message), "{err}");
+        }
+
+        conn.execute(
+            "insert into two(rowid, a, b) values (1, '[1, 0]', '[1, 1]')",
+            [],
+        )
+        .unwrap();
+        let mirror = Vec0Mirror::load_column(&conn, "two", "b").unwrap();
+        assert_eq!(mirror.metric(), Metric::Cosine);
+        assert_eq!(mirror.knn(&[1.0, 1.0], 1)[0].rowid, 1);
+    }
+}
@@ -0,0 +1,251 @@
+//! Progress reports for long `vec0` operations, with the `rusqlite` feature.
+//!
+//! ```no_run
+//! # fn run(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+//! sqlite_vec::progress::set_progress_handler(
+//!     conn,
+//!     Some(|progress: sqlite_vec::progress::Progress<'_>| {
+//!         println!(
+//!             "{} {}: {}/{}",
+//!             progress.operation, progress.table, progress.done, progress.total
+//!         );
+//!         false
+//!     }),
+//! )?;
+//! conn.execute("insert into docs(docs) values ('optimize')", [])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use rusqlite::{ffi, Connection};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+type ProgressCallback =
+    unsafe extern "C" fn(*mut c_void, *const c_char, *const c_char, i64, i64) -> c_int;
+
+#[link(name = "sqlite_vec0")]
+extern "C" {
+    fn vec0_set_progress_handler(
+        db: *mut ffi::sqlite3,
+        x_progress: Option<ProgressCallback>,
+        p_arg: *mut c_void,
+        x_destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+}
+
+/// One progress report of a long operation on a `vec0` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress<'a> {
+    /// `"optimize"`, `"rechunk"`, `"rebuild"`, `"train"`, `"batch"` or
+    /// `"migrate_from_vss"`.
+    pub operation: &'a str,
+    /// The name of the `vec0` table.
+    pub table: &'a str,
+    /// Units of work done so far, out of `total`. Units are rows for most
+    /// operations, but only their ratio is meant to be shown.
+    pub done: i64,
+    pub total: i64,
+}
+
+/// Calls `handler` with the progress of long operations on `conn`'s `vec0`
+/// tables, like `'optimize'`, `'rebuild'`, `'train'` and batch inserts.
+/// Returning `true` cancels the operation, which then fails with
+/// [`rusqlite::ErrorCode::OperationInterrupted`] and rolls back like any
+/// interrupted statement. `None` removes the handler.
+///
+/// `conn` must have `sqlite-vec` loaded already. A panic in `handler` also
+/// cancels the operation.
+pub fn set_progress_handler<F>(conn: &Connection, handler: Option<F>) -> rusqlite::Result<()>
+where
+    F: FnMut(Progress<'_>) -> bool + Send + 'static,
+{
+    unsafe extern "C" fn call<F>(
+        arg: *mut c_void,
+        operation: *const c_char,
+        table: *const c_char,
+        done: i64,
+        total: i64,
+    ) -> c_int
+    where
+        F: FnMut(Progress<'_>) -> bool,
+    {
+        // SAFETY: `arg` is the Box<F> from set_progress_handler(), and both
+        // strings are nul-terminated and live for the duration of the call
+        let handler = &mut *(arg as *mut F);
+        let progress = Progress {
+            operation: CStr::from_ptr(operation).to_str().unwrap_or_default(),
+            table: CStr::from_ptr(table).to_str().unwrap_or_default(),
+            done,
+            total,
+        };
+        match catch_unwind(AssertUnwindSafe(|| handler(progress))) {
+            Ok(false) => 0,
+            _ => 1,
+        }
+    }
+
+    unsafe extern "C" fn destroy<F>(arg: *mut c_void) {
+        drop(Box::from_raw(arg as *mut F));
+    }
+
+    let rc = match handler {
+        // SAFETY: sqlite-vec owns the box until it calls destroy(), and only
+        // calls call() on this connection's thread
+        Some(handler) => unsafe {
+            vec0_set_progress_handler(
+                conn.handle(),
+                Some(call::<F>),
+                Box::into_raw(Box::new(handler)).cast(),
+                Some(destroy::<F>),
+            )
+        },
+        None => unsafe {
+            vec0_set_progress_handler(conn.handle(), None, std::ptr::null_mut(), None)
+        },
+    };
+    if rc != ffi::SQLITE_OK {
+        return Err(crate::statement::error(conn, rc));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_set_progress_handler() {
+        let guard = crate::tests::auto_extensions();
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(set_progress_handler(&conn, Some(|_: Progress<'_>| false)).is_err());
+        drop(guard);
+
+        crate::load(&conn).unwrap();
+        conn.execute_batch("create virtual table v using vec0(a float[2], chunk_size=8)")
+            .unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        set_progress_handler(
+            &conn,
+            Some(move |progress: Progress<'_>| {
+                sink.lock().unwrap().push((
+                    progress.operation.to_string(),
+                    progress.table.to_string(),
+                    progress.done,
+                    progress.total,
+                ));
+                false
+            }),
+        )
+        .unwrap();
+
+        let vectors: Vec<u8> = (0..3000)
+            .flat_map(|i| [i as f32, 0.0])
+            .flat_map(f32::to_ne_bytes)
+            .collect();
+        conn.execute("insert into v(v, a) values ('batch', ?)", [&vectors])
+            .unwrap();
+        let report =
+            |operation: &str, done, total| (operation.to_string(), "v".to_string(), done, total);
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                report("batch", 0, 3000),
+                report("batch", 1024, 3000),
+                report("batch", 2048, 3000),
+                report("batch", 3000, 3000),
+            ]
+        );
+
+        reports.lock().unwrap().clear();
+        conn.execute("delete from v where rowid % 2 = 0", [])
+            .unwrap();
+        conn.execute("insert into v(v) values ('optimize')", [])
+            .unwrap();
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                report("optimize", 0, 1500),
+                report("optimize", 1024, 1500),
+                report("optimize", 1500, 1500)
+            ]
+        );
+
+        // cancelling rolls the operation back
+        set_progress_handler(&conn, Some(|progress: Progress<'_>| progress.done > 0)).unwrap();
+        assert_eq!(Arc::strong_count(&reports), 1);
+        let err = conn
+            .execute("insert into v(v) values ('rechunk=16')", [])
+            .unwrap_err();
+        assert_eq!(
+            err.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::OperationInterrupted),
+            "{err}"
+        );
+        let chunk_size: i64 = conn
+            .query_row(
+                "select value from vec0_info('v') where key = 'chunk_size'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(chunk_size, 8);
+
+        set_progress_handler(&conn, None::<fn(Progress<'_>) -> bool>).unwrap();
+        conn.execute("insert into v(v) values ('rechunk=16')", [])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_train_and_rebuild_progress() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            "create virtual table v using vec0(a float[2] index=ivf(nlist=4), \
+             b float[2] index=hnsw(m=4, ef_construction=20), chunk_size=8)",
+        )
+        .unwrap();
+        let vectors: Vec<u8> = (0..2000)
+            .flat_map(|i| [i as f32, 1.0])
+            .flat_map(f32::to_ne_bytes)
+            .collect();
+        conn.execute(
+            "insert into v(v, a, b) values ('batch', ?1, ?1)",
+            [&vectors],
+        )
+        .unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        set_progress_handler(
+            &conn,
+            Some(move |progress: Progress<'_>| {
+                assert!(0 <= progress.done && progress.done <= progress.total);
+                sink.lock().unwrap().push((
+                    progress.operation.to_string(),
+                    progress.done,
+                    progress.total,
+                ));
+                false
+            }),
+        )
+        .unwrap();
+        for (command, operation, total) in [("train", "train", 4000), ("rebuild", "rebuild", 2000)]
+        {
+            reports.lock().unwrap().clear();
+            conn.execute("insert into v(v) values (?)", [command])
+                .unwrap();
+            let reports = reports.lock().unwrap();
+            let ours: Vec<_> = reports.iter().filter(|r| r.0 == operation).collect();
+            assert!(ours.len() > 2, "{reports:?}");
+            assert!(ours.windows(2).all(|w| w[0].1 <= w[1].1), "{reports:?}");
+            assert_eq!(
+                *ours.last().unwrap(),
+                &(operation.to_string(), total, total)
+            );
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Rows returned by KNN queries on `vec0` tables, and the metrics they're
+//! ranked by.
+
+/// One row of a KNN query: the `rowid` and `distance` columns every `vec0`
+/// KNN query can return, and whatever other columns the caller selected,
+/// like auxiliary or metadata columns, in `metadata`.
+///
+/// With the `serde` feature it serializes as
+/// `{"rowid": 1, "distance": 0.25, "metadata": ...}`, so KNN results can be
+/// returned from an API as JSON directly.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult<M = ()> {
+    pub rowid: i64,
+    pub distance: f64,
+    pub metadata: M,
+}
+
+/// The `distance_metric=` of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Euclidean distance, not squared.
+    L2,
+    L1,
+    /// One minus the cosine similarity.
+    Cosine,
+    /// The negative inner product, so smaller is closer.
+    Dot,
+}
+
+impl Metric {
+    #[cfg(feature = "rusqlite")]
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "l2" => Some(Metric::L2),
+            "l1" => Some(Metric::L1),
+            "cosine" => Some(Metric::Cosine),
+            "dot" => Some(Metric::Dot),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Article {
+        title: String,
+    }
+
+    #[test]
+    fn test_serde() {
+        let result = SearchResult {
+            rowid: 3,
+            distance: 0.25,
+            metadata: Article {
+                title: "hello".to_string(),
+            },
+        };
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rowid":3,"distance":0.25,"metadata":{"title":"hello"}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<SearchResult<Article>>(&json).unwrap(),
+            result
+        );
+
+        let json = serde_json::to_string(&SearchResult::<()>::default()).unwrap();
+        assert_eq!(json, r#"{"rowid":0,"distance":0.0,"metadata":null}"#);
+    }
+}
@@ -0,0 +1,108 @@
+//! [`sqlx`](https://docs.rs/sqlx) support, with the `sqlx` feature.
+//!
+//! sqlx's SQLite driver links SQLite through libsqlite3-sys, so `sqlite-vec`
+//! is registered as an auto extension of that library. The vector types in
+//! [`crate::vector`] also implement sqlx's `Encode`/`Decode`, and bind as
+//! BLOBs like they do with rusqlite.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), ::sqlx::Error> {
+//! use sqlite_vec::vector::Float32Vector;
+//! use sqlx::sqlite::SqliteConnectOptions;
+//! use sqlx::{ConnectOptions, Row};
+//!
+//! let mut options = SqliteConnectOptions::new().filename("vectors.db");
+//! sqlite_vec::sqlx::register(&mut options)?;
+//! let mut conn = options.connect().await?;
+//! let row = sqlx::query("select vec_length(?)")
+//!     .bind(Float32Vector(vec![0.1, 0.2, 0.3]))
+//!     .fetch_one(&mut conn)
+//!     .await?;
+//! assert_eq!(row.get::<i64, _>(0), 3);
+//! # Ok(())
+//! # }
+//! ```
+
+use ::sqlx::sqlite::SqliteConnectOptions;
+
+type EntryPoint = unsafe extern "C" fn(
+    *mut libsqlite3_sys::sqlite3,
+    *mut *mut std::os::raw::c_char,
+    *const libsqlite3_sys::sqlite3_api_routines,
+) -> std::os::raw::c_int;
+
+/// Makes `sqlite-vec` available on connections opened with `options`.
+///
+/// sqlx has no hook to run code on each new connection, so this registers
+/// the extension with `sqlite3_auto_extension()`, and every connection opened
+/// afterwards in the process gets it, not only the ones from `options`.
+/// Calling it more than once is harmless.
+pub fn register(options: &mut SqliteConnectOptions) -> Result<(), ::sqlx::Error> {
+    let _ = options;
+    // SAFETY: `sqlite3_vec_init` is declared without arguments in lib.rs, but
+    // the C symbol has the standard SQLite extension entrypoint signature.
+    let entrypoint = unsafe {
+        std::mem::transmute::<*const (), EntryPoint>(crate::sqlite3_vec_init as *const ())
+    };
+    let rc = unsafe { libsqlite3_sys::sqlite3_auto_extension(Some(entrypoint)) };
+    if rc != libsqlite3_sys::SQLITE_OK {
+        return Err(::sqlx::Error::Configuration(
+            format!("could not register sqlite-vec as an auto extension: {rc}").into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::{BitVector, Float32Vector, Int8Vector};
+    use ::sqlx::{ConnectOptions, Row};
+
+    #[test]
+    fn test_register() {
+        let _guard = crate::tests::auto_extensions();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut options = SqliteConnectOptions::new().in_memory(true);
+            register(&mut options).unwrap();
+            register(&mut options).unwrap();
+            let mut conn = options.connect().await.unwrap();
+
+            ::sqlx::query("create virtual table v using vec0(a float[2], b int8[2], c bit[8])")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            ::sqlx::query("insert into v(rowid, a, b, c) values (1, ?, vec_int8(?), vec_bit(?))")
+                .bind(Float32Vector(vec![1.0, 2.0]))
+                .bind(Int8Vector(vec![-1, 1]))
+                .bind(BitVector(vec![0b1010_1010]))
+                .execute(&mut conn)
+                .await
+                .unwrap();
+
+            let row = ::sqlx::query("select a, b, c, distance from v where a match ? and k = 1")
+                .bind(Float32Vector(vec![1.0, 1.0]))
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+            assert_eq!(
+                row.get::<Float32Vector, _>(0),
+                Float32Vector(vec![1.0, 2.0])
+            );
+            assert_eq!(row.get::<Int8Vector, _>(1), Int8Vector(vec![-1, 1]));
+            assert_eq!(row.get::<BitVector, _>(2), BitVector(vec![0b1010_1010]));
+            assert_eq!(row.get::<f64, _>(3), 1.0);
+
+            let err = ::sqlx::query("select x'000000'")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap()
+                .try_get::<Float32Vector, _>(0)
+                .unwrap_err();
+            assert!(err.to_string().contains("must be divisible by 4"));
+        });
+    }
+}
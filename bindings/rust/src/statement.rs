@@ -0,0 +1,298 @@
+//! Zero-copy parameter binding, with the `rusqlite` feature.
+//!
+//! rusqlite copies every BLOB it binds, which adds up when inserting millions
+//! of 1536-dimension vectors. [`ZeroCopyStatement`] binds borrowed slices with
+//! `SQLITE_STATIC` instead, so SQLite reads the vector straight from the
+//! caller's memory.
+//!
+//! ```no_run
+//! # fn run(conn: &rusqlite::Connection, embeddings: &[Vec<f32>]) -> rusqlite::Result<()> {
+//! use sqlite_vec::statement::{Param, ZeroCopyStatement};
+//!
+//! let mut insert =
+//!     ZeroCopyStatement::prepare(conn, "insert into vec_items(rowid, embedding) values (?, ?)")?;
+//! for (i, embedding) in embeddings.iter().enumerate() {
+//!     insert.execute(&[Param::Integer(i as i64), Param::Float32(embedding)])?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::vector::{BitVector, Float32Vector, Int8Vector};
+use rusqlite::{ffi, Connection};
+use std::os::raw::{c_char, c_int};
+use std::ptr::NonNull;
+
+/// A parameter for [`ZeroCopyStatement::execute()`]. Borrowed variants are
+/// bound without copying.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param<'a> {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(&'a str),
+    Blob(&'a [u8]),
+    /// A `float32` vector, bound as its in-memory bytes, which are already in
+    /// `sqlite-vec`'s format.
+    Float32(&'a [f32]),
+    /// An `int8` vector. Like with any BLOB, wrap the parameter in
+    /// `vec_int8(?)` unless the column is `int8`.
+    Int8(&'a [i8]),
+}
+
+impl<'a> From<&'a Float32Vector> for Param<'a> {
+    fn from(vector: &'a Float32Vector) -> Self {
+        Param::Float32(&vector.0)
+    }
+}
+
+impl<'a> From<&'a Int8Vector> for Param<'a> {
+    fn from(vector: &'a Int8Vector) -> Self {
+        Param::Int8(&vector.0)
+    }
+}
+
+impl<'a> From<&'a BitVector> for Param<'a> {
+    fn from(vector: &'a BitVector) -> Self {
+        Param::Blob(&vector.0)
+    }
+}
+
+impl From<i64> for Param<'_> {
+    fn from(value: i64) -> Self {
+        Param::Integer(value)
+    }
+}
+
+impl From<f64> for Param<'_> {
+    fn from(value: f64) -> Self {
+        Param::Real(value)
+    }
+}
+
+impl<'a> From<&'a str> for Param<'a> {
+    fn from(value: &'a str) -> Self {
+        Param::Text(value)
+    }
+}
+
+/// A prepared statement that binds [`Param`]s without copying them.
+///
+/// SQLite only reads `SQLITE_STATIC` parameters until the statement is reset,
+/// so [`ZeroCopyStatement::execute()`] resets the statement and clears its
+/// bindings before returning, even on errors. The borrowed data only has to
+/// outlive that call, which the borrow checker enforces.
+pub struct ZeroCopyStatement<'conn> {
+    conn: &'conn Connection,
+    stmt: NonNull<ffi::sqlite3_stmt>,
+}
+
+impl<'conn> ZeroCopyStatement<'conn> {
+    /// Prepares a single SQL statement on `conn`.
+    pub fn prepare(conn: &'conn Connection, sql: &str) -> rusqlite::Result<Self> {
+        let length = c_int::try_from(sql.len()).map_err(|_| {
+            rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_TOOBIG), None)
+        })?;
+        let mut stmt = std::ptr::null_mut();
+        let mut tail: *const c_char = std::ptr::null();
+        // SAFETY: the handle stays valid for the lifetime of `conn`, and
+        // `length` is the exact length of `sql`, which needn't be
+        // nul-terminated.
+        let rc = unsafe {
+            ffi::sqlite3_prepare_v2(
+                conn.handle(),
+                sql.as_ptr().cast(),
+                length,
+                &mut stmt,
+                &mut tail,
+            )
+        };
+        if rc != ffi::SQLITE_OK {
+            return Err(error(conn, rc));
+        }
+        let Some(stmt) = NonNull::new(stmt) else {
+            // only whitespace or comments
+            return Err(rusqlite::Error::InvalidQuery);
+        };
+        let statement = ZeroCopyStatement { conn, stmt };
+        let rest = &sql[tail as usize - sql.as_ptr() as usize..];
+        if !rest.trim().is_empty() {
+            return Err(rusqlite::Error::MultipleStatement);
+        }
+        Ok(statement)
+    }
+
+    /// Runs the statement to completion with `params` bound in order, and
+    /// returns the number of rows it changed. Rows it returns are ignored.
+    pub fn execute(&mut self, params: &[Param<'_>]) -> rusqlite::Result<usize> {
+        let stmt = self.stmt.as_ptr();
+        let result = self.bind_and_step(params);
+        // SAFETY: `stmt` is a valid statement until drop. Clearing the
+        // bindings drops SQLite's pointers into `params` before they can
+        // dangle.
+        unsafe {
+            ffi::sqlite3_reset(stmt);
+            ffi::sqlite3_clear_bindings(stmt);
+        }
+        result
+    }
+
+    fn bind_and_step(&mut self, params: &[Param<'_>]) -> rusqlite::Result<usize> {
+        let stmt = self.stmt.as_ptr();
+        // SAFETY: `stmt` is a valid statement until drop
+        let expected = unsafe { ffi::sqlite3_bind_parameter_count(stmt) } as usize;
+        if params.len() != expected {
+            return Err(rusqlite::Error::InvalidParameterCount(
+                params.len(),
+                expected,
+            ));
+        }
+        for (i, param) in params.iter().enumerate() {
+            let index = i as c_int + 1;
+            // SAFETY: every pointer and length comes from a live slice, which
+            // SQLite reads until execute() clears the bindings.
+            let rc = unsafe {
+                match *param {
+                    Param::Null => ffi::sqlite3_bind_null(stmt, index),
+                    Param::Integer(value) => ffi::sqlite3_bind_int64(stmt, index, value),
+                    Param::Real(value) => ffi::sqlite3_bind_double(stmt, index, value),
+                    Param::Text(value) => ffi::sqlite3_bind_text64(
+                        stmt,
+                        index,
+                        value.as_ptr().cast(),
+                        value.len() as u64,
+                        ffi::SQLITE_STATIC(),
+                        ffi::SQLITE_UTF8 as u8,
+                    ),
+                    Param::Blob(value) => {
+                        bind_blob(stmt, index, value.as_ptr().cast(), value.len())
+                    }
+                    Param::Float32(value) => bind_blob(
+                        stmt,
+                        index,
+                        value.as_ptr().cast(),
+                        std::mem::size_of_val(value),
+                    ),
+                    Param::Int8(value) => {
+                        bind_blob(stmt, index, value.as_ptr().cast(), value.len())
+                    }
+                }
+            };
+            if rc != ffi::SQLITE_OK {
+                return Err(error(self.conn, rc));
+            }
+        }
+
+        loop {
+            // SAFETY: `stmt` is a valid statement with every parameter bound
+            match unsafe { ffi::sqlite3_step(stmt) } {
+                ffi::SQLITE_ROW => continue,
+                ffi::SQLITE_DONE => break,
+                rc => return Err(error(self.conn, rc)),
+            }
+        }
+        Ok(self.conn.changes() as usize)
+    }
+}
+
+impl Drop for ZeroCopyStatement<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the statement is finalized exactly once
+        unsafe { ffi::sqlite3_finalize(self.stmt.as_ptr()) };
+    }
+}
+
+unsafe fn bind_blob(
+    stmt: *mut ffi::sqlite3_stmt,
+    index: c_int,
+    data: *const std::os::raw::c_void,
+    length: usize,
+) -> c_int {
+    ffi::sqlite3_bind_blob64(stmt, index, data, length as u64, ffi::SQLITE_STATIC())
+}
+
+pub(crate) fn error(conn: &Connection, rc: c_int) -> rusqlite::Error {
+    // SAFETY: the handle is valid, and sqlite3_errmsg() always returns a
+    // nul-terminated string
+    let message = unsafe { std::ffi::CStr::from_ptr(ffi::sqlite3_errmsg(conn.handle())) }
+        .to_string_lossy()
+        .into_owned();
+    rusqlite::Error::SqliteFailure(ffi::Error::new(rc), Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            "create virtual table v using vec0(a float[3], b int8[2], c bit[8], +label text)",
+        )
+        .unwrap();
+
+        let mut insert = ZeroCopyStatement::prepare(
+            &conn,
+            "insert into v(rowid, a, b, c, label) values (?, ?, vec_int8(?), vec_bit(?), ?)",
+        )
+        .unwrap();
+        for i in 0..3 {
+            let a = [i as f32, 1.0, 2.0];
+            let b = Int8Vector(vec![-1, i as i8]);
+            let c = BitVector(vec![0b1111_0000]);
+            let label = format!("row {i}");
+            let changed = insert
+                .execute(&[
+                    Param::Integer(i + 1),
+                    Param::Float32(&a),
+                    (&b).into(),
+                    (&c).into(),
+                    label.as_str().into(),
+                ])
+                .unwrap();
+            assert_eq!(changed, 1);
+        }
+
+        let (a, b, label): (Float32Vector, Int8Vector, String) = conn
+            .query_row("select a, b, label from v where rowid = 3", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(a, Float32Vector(vec![2.0, 1.0, 2.0]));
+        assert_eq!(b, Int8Vector(vec![-1, 2]));
+        assert_eq!(label, "row 2");
+
+        // errors leave the statement usable
+        let err = insert
+            .execute(&[
+                Param::Integer(10),
+                Param::Float32(&[1.0]),
+                Param::Int8(&[0, 0]),
+                Param::Blob(&[0]),
+                Param::Null,
+            ])
+            .unwrap_err();
+        assert!(err.to_string().contains("Dimension mismatch"), "{err}");
+        assert!(matches!(
+            insert.execute(&[Param::Integer(10)]).unwrap_err(),
+            rusqlite::Error::InvalidParameterCount(1, 5)
+        ));
+        insert
+            .execute(&[
+                Param::Integer(10),
+                Param::Float32(&[1.0, 1.0, 1.0]),
+                Param::Int8(&[0, 0]),
+                Param::Blob(&[0]),
+                Param::Null,
+            ])
+            .unwrap();
+
+        assert!(matches!(
+            ZeroCopyStatement::prepare(&conn, "select 1; select 2").err(),
+            Some(rusqlite::Error::MultipleStatement)
+        ));
+        assert!(ZeroCopyStatement::prepare(&conn, "select nope").is_err());
+    }
+}
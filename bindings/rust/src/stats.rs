@@ -0,0 +1,245 @@
+//! Query counters and slow query reports for `vec0` tables, with the
+//! `rusqlite` feature.
+//!
+//! ```no_run
+//! # fn run(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+//! use std::time::Duration;
+//!
+//! sqlite_vec::stats::set_slow_query_handler(
+//!     conn,
+//!     Duration::from_millis(50),
+//!     Some(|query: sqlite_vec::stats::SlowQuery<'_>| {
+//!         eprintln!("{} took {:?}", query.plan, query.elapsed);
+//!     }),
+//! )?;
+//! let stats = sqlite_vec::stats::reset(conn)?;
+//! println!("{} KNN queries read {} bytes", stats.queries, stats.bytes_read);
+//! # Ok(())
+//! # }
+//! ```
+
+use rusqlite::{ffi, Connection};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Duration;
+
+type SlowQueryCallback = unsafe extern "C" fn(*mut c_void, *const c_char, *const c_char, i64);
+
+#[link(name = "sqlite_vec0")]
+extern "C" {
+    fn vec0_set_slow_query_handler(
+        db: *mut ffi::sqlite3,
+        threshold_ms: i64,
+        x_slow_query: Option<SlowQueryCallback>,
+        p_arg: *mut c_void,
+        x_destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+}
+
+/// The KNN query counters of a connection, from `vec0_stats()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// KNN queries on `vec0` tables. Point lookups and full scans aren't
+    /// counted.
+    pub queries: i64,
+    /// Chunks read by chunk scans, from the database or the chunk cache.
+    /// Chunks skipped by summaries aren't counted.
+    pub chunks_scanned: i64,
+    /// Rows of those chunks whose distance to the query was computed, after
+    /// rowid and metadata filters.
+    pub rows_compared: i64,
+    /// Bytes of vectors read from the database, not the chunk cache.
+    pub bytes_read: i64,
+}
+
+fn read(conn: &Connection, sql: &str) -> rusqlite::Result<Stats> {
+    let json: String = conn.query_row(sql, [], |row| row.get(0))?;
+    // a flat object of integers, so no JSON parser is needed
+    let field = |name: &str| {
+        let key = format!("\"{name}\":");
+        json.find(&key)
+            .map(|start| &json[start + key.len()..])
+            .and_then(|rest| rest.split([',', '}']).next())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    };
+    Ok(Stats {
+        queries: field("queries"),
+        chunks_scanned: field("chunks_scanned"),
+        rows_compared: field("rows_compared"),
+        bytes_read: field("bytes_read"),
+    })
+}
+
+/// The counters of `conn`'s KNN queries since it loaded `sqlite-vec` or since
+/// the last [`reset()`].
+pub fn stats(conn: &Connection) -> rusqlite::Result<Stats> {
+    read(conn, "select vec0_stats()")
+}
+
+/// Like [`stats()`], then zeroes the counters, for reporting them per
+/// interval.
+pub fn reset(conn: &Connection) -> rusqlite::Result<Stats> {
+    read(conn, "select vec0_stats('reset')")
+}
+
+/// A KNN query that took at least the threshold of
+/// [`set_slow_query_handler()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlowQuery<'a> {
+    /// The name of the `vec0` table.
+    pub table: &'a str,
+    /// The query plan, as `vec_debug_last_plan()` returns it.
+    pub plan: &'a str,
+    pub elapsed: Duration,
+}
+
+/// Calls `handler` after each KNN query on `conn`'s `vec0` tables that took
+/// at least `threshold`, or after every one with [`Duration::ZERO`].
+/// Durations have millisecond precision. `None` removes the handler.
+///
+/// `conn` must have `sqlite-vec` loaded already. Panics in `handler` are
+/// ignored.
+pub fn set_slow_query_handler<F>(
+    conn: &Connection,
+    threshold: Duration,
+    handler: Option<F>,
+) -> rusqlite::Result<()>
+where
+    F: FnMut(SlowQuery<'_>) + Send + 'static,
+{
+    unsafe extern "C" fn call<F>(
+        arg: *mut c_void,
+        table: *const c_char,
+        plan: *const c_char,
+        elapsed_ms: i64,
+    ) where
+        F: FnMut(SlowQuery<'_>),
+    {
+        // SAFETY: `arg` is the Box<F> from set_slow_query_handler(), and both
+        // strings are nul-terminated and live for the duration of the call
+        let handler = &mut *(arg as *mut F);
+        let query = SlowQuery {
+            table: CStr::from_ptr(table).to_str().unwrap_or_default(),
+            plan: CStr::from_ptr(plan).to_str().unwrap_or_default(),
+            elapsed: Duration::from_millis(elapsed_ms.max(0) as u64),
+        };
+        let _ = catch_unwind(AssertUnwindSafe(|| handler(query)));
+    }
+
+    unsafe extern "C" fn destroy<F>(arg: *mut c_void) {
+        drop(Box::from_raw(arg as *mut F));
+    }
+
+    let threshold_ms = i64::try_from(threshold.as_millis()).unwrap_or(i64::MAX);
+    let rc = match handler {
+        // SAFETY: sqlite-vec owns the box until it calls destroy(), and only
+        // calls call() on this connection's thread
+        Some(handler) => unsafe {
+            vec0_set_slow_query_handler(
+                conn.handle(),
+                threshold_ms,
+                Some(call::<F>),
+                Box::into_raw(Box::new(handler)).cast(),
+                Some(destroy::<F>),
+            )
+        },
+        None => unsafe {
+            vec0_set_slow_query_handler(conn.handle(), 0, None, std::ptr::null_mut(), None)
+        },
+    };
+    if rc != ffi::SQLITE_OK {
+        return Err(crate::statement::error(conn, rc));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_stats() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        assert_eq!(stats(&conn).unwrap(), Stats::default());
+
+        conn.execute_batch("create virtual table v using vec0(a float[4], chunk_size=8)")
+            .unwrap();
+        let vectors: Vec<u8> = (0..20)
+            .flat_map(|i| [i as f32; 4])
+            .flat_map(f32::to_ne_bytes)
+            .collect();
+        conn.execute("insert into v(v, a) values ('batch', ?)", [&vectors])
+            .unwrap();
+        let knn = "select rowid from v where a match '[1, 1, 1, 1]' and k = 2";
+        for _ in 0..2 {
+            conn.prepare(knn)
+                .unwrap()
+                .query([])
+                .unwrap()
+                .next()
+                .unwrap();
+        }
+        let expected = Stats {
+            queries: 2,
+            chunks_scanned: 6,
+            rows_compared: 40,
+            bytes_read: 768,
+        };
+        assert_eq!(reset(&conn).unwrap(), expected);
+        assert_eq!(stats(&conn).unwrap(), Stats::default());
+    }
+
+    #[test]
+    fn test_set_slow_query_handler() {
+        let guard = crate::tests::auto_extensions();
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(
+            set_slow_query_handler(&conn, Duration::ZERO, Some(|_: SlowQuery<'_>| {})).is_err()
+        );
+        drop(guard);
+
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            "create virtual table v using vec0(a float[2]); \
+             insert into v(rowid, a) values (1, '[1, 1]');",
+        )
+        .unwrap();
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let sink = queries.clone();
+        set_slow_query_handler(
+            &conn,
+            Duration::ZERO,
+            Some(move |query: SlowQuery<'_>| {
+                sink.lock()
+                    .unwrap()
+                    .push((query.table.to_string(), query.plan.to_string()));
+            }),
+        )
+        .unwrap();
+        let knn = "select rowid from v where a match '[1, 1]' and k = 1";
+        conn.query_row(knn, [], |_| Ok(())).unwrap();
+        conn.query_row("select rowid from v where rowid = 1", [], |_| Ok(()))
+            .unwrap();
+        assert_eq!(
+            *queries.lock().unwrap(),
+            [("v".to_string(), "knn on v.a via chunk scan".to_string())]
+        );
+
+        // replacing the handler drops the old one
+        set_slow_query_handler(
+            &conn,
+            Duration::from_secs(3600),
+            Some(|_: SlowQuery<'_>| panic!("not slow")),
+        )
+        .unwrap();
+        assert_eq!(Arc::strong_count(&queries), 1);
+        conn.query_row(knn, [], |_| Ok(())).unwrap();
+        set_slow_query_handler(&conn, Duration::ZERO, None::<fn(SlowQuery<'_>)>).unwrap();
+        conn.query_row(knn, [], |_| Ok(())).unwrap();
+        assert_eq!(queries.lock().unwrap().len(), 1);
+    }
+}
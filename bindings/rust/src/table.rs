@@ -0,0 +1,503 @@
+//! A typed KNN query builder for `vec0` tables, with the `rusqlite` feature.
+//!
+//! ```no_run
+//! # fn run(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+//! use sqlite_vec::vector::Float32Vector;
+//! use sqlite_vec::Vec0Table;
+//!
+//! let query = Float32Vector(vec![0.1, 0.2, 0.3]);
+//! let results = Vec0Table::open(conn, "docs")?
+//!     .knn(&query)
+//!     .k(10)
+//!     .filter("tenant_id", 42)
+//!     .execute()?;
+//! for result in results {
+//!     println!("{} {}", result.rowid, result.distance);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::search::SearchResult;
+use crate::statement::{Param, ZeroCopyStatement};
+use rusqlite::{ffi, Connection, Row, ToSql};
+
+/// Rows [`Vec0Table::upsert_batch()`] writes per transaction.
+const UPSERT_CHUNK_SIZE: usize = 10_000;
+
+pub(crate) fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A `vec0` table on a connection, and the columns KNN queries can use.
+#[derive(Debug)]
+pub struct Vec0Table<'conn> {
+    conn: &'conn Connection,
+    name: String,
+    pub(crate) key: String,
+    columns: Vec<String>,
+    vector_columns: Vec<String>,
+}
+
+impl<'conn> Vec0Table<'conn> {
+    /// Looks up the `vec0` table `name` in the `main` schema. Fails if there's
+    /// no such table, if it isn't a `vec0` table, or if its primary key is TEXT
+    /// or composite, since results and upserts are keyed by an `i64` rowid.
+    pub fn open(conn: &'conn Connection, name: &str) -> rusqlite::Result<Self> {
+        let vector_columns = conn
+            .prepare("select name from vec0_info(?) where key = 'element_type'")?
+            .query_map([name], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        // table_xinfo marks hidden columns like distance and k with hidden = 1
+        let mut key = "rowid".to_string();
+        let mut columns = Vec::new();
+        let mut stmt = conn.prepare("select name, pk, hidden from pragma_table_xinfo(?)")?;
+        let mut rows = stmt.query([name])?;
+        while let Some(row) = rows.next()? {
+            let column: String = row.get(0)?;
+            if row.get::<_, i64>(2)? != 0 {
+                continue;
+            }
+            if row.get::<_, i64>(1)? != 0 {
+                key = column.clone();
+            }
+            columns.push(column);
+        }
+
+        // composite primary keys are stored in keyNN columns of _rowids, and
+        // TEXT ones in its id column, while upsert_batch() and knn() only know
+        // about a single integer key
+        let (composite, text): (bool, bool) = conn.query_row(
+            "select count(*) filter (where name = 'key00') > 0,
+               count(*) filter (where name = 'id' and type = 'TEXT') > 0
+             from pragma_table_xinfo(?)",
+            [format!("{name}_rowids")],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let unsupported = if composite {
+            Some(format!(
+                "{name} has a composite primary key, which Vec0Table doesn't support"
+            ))
+        } else if text {
+            Some(format!(
+                "{name} can't be opened as a Vec0Table, its primary key isn't an integer"
+            ))
+        } else {
+            None
+        };
+        if let Some(message) = unsupported {
+            return Err(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_ERROR),
+                Some(message),
+            ));
+        }
+
+        Ok(Vec0Table {
+            conn,
+            name: name.to_string(),
+            key,
+            columns,
+            vector_columns,
+        })
+    }
+
+    /// The table's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Names of the table's vector columns, in declaration order.
+    pub fn vector_columns(&self) -> &[String] {
+        &self.vector_columns
+    }
+
+    /// Starts a KNN query for the rows nearest to `query`, which can be any
+    /// vector `sqlite-vec` accepts: a [`crate::vector::Float32Vector`], a JSON
+    /// string, or a BLOB.
+    pub fn knn<'a>(&'a self, query: &'a dyn ToSql) -> Knn<'a> {
+        Knn {
+            table: self,
+            query,
+            column: None,
+            k: 10,
+            filters: Vec::new(),
+            select: Vec::new(),
+        }
+    }
+
+    /// Inserts or replaces the vector of each `(rowid, vector)` in `rows`, and
+    /// returns how many rows were written. The table needs exactly one vector
+    /// column.
+    ///
+    /// Rows are written with two reused prepared statements, in transactions
+    /// of up to 10,000 rows each. If a row fails, its transaction is rolled
+    /// back, but earlier ones stay committed. Inside a transaction the caller
+    /// already opened, everything is written in that one instead.
+    pub fn upsert_batch<'v>(
+        &mut self,
+        rows: impl Iterator<Item = (i64, &'v [f32])>,
+    ) -> rusqlite::Result<usize> {
+        let column = quote(self.vector_column(None)?);
+        let (name, key) = (quote(&self.name), quote(&self.key));
+        // vec0 has no INSERT OR REPLACE, so rows that the UPDATE misses get
+        // inserted instead
+        let mut update = ZeroCopyStatement::prepare(
+            self.conn,
+            &format!("update {name} set {column} = ?2 where {key} = ?1"),
+        )?;
+        let mut insert = ZeroCopyStatement::prepare(
+            self.conn,
+            &format!("insert into {name}({key}, {column}) values (?1, ?2)"),
+        )?;
+
+        let chunk_size = if self.conn.is_autocommit() {
+            UPSERT_CHUNK_SIZE
+        } else {
+            usize::MAX
+        };
+        let mut rows = rows.peekable();
+        let mut written = 0;
+        while rows.peek().is_some() {
+            let tx = if self.conn.is_autocommit() {
+                Some(self.conn.unchecked_transaction()?)
+            } else {
+                None
+            };
+            for (rowid, vector) in rows.by_ref().take(chunk_size) {
+                let params = [Param::Integer(rowid), Param::Float32(vector)];
+                if update.execute(&params)? == 0 {
+                    insert.execute(&params)?;
+                }
+                written += 1;
+            }
+            if let Some(tx) = tx {
+                tx.commit()?;
+            }
+        }
+        Ok(written)
+    }
+
+    /// The vector column named `column`, or the only one if it's `None`.
+    pub(crate) fn vector_column<'s>(&'s self, column: Option<&'s str>) -> rusqlite::Result<&'s str> {
+        match column {
+            Some(column) => {
+                if self
+                    .vector_columns
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case(column))
+                {
+                    Ok(column)
+                } else {
+                    Err(rusqlite::Error::InvalidColumnName(format!(
+                        "{} has no vector column {}",
+                        self.name, column
+                    )))
+                }
+            }
+            None => match self.vector_columns.as_slice() {
+                [column] => Ok(column.as_str()),
+                _ => Err(rusqlite::Error::InvalidColumnName(format!(
+                    "{} has {} vector columns, pick one with column()",
+                    self.name,
+                    self.vector_columns.len()
+                ))),
+            },
+        }
+    }
+
+    fn check_column(&self, column: &str) -> rusqlite::Result<()> {
+        if self.columns.iter().any(|c| c.eq_ignore_ascii_case(column)) {
+            Ok(())
+        } else {
+            Err(rusqlite::Error::InvalidColumnName(format!(
+                "{} has no column {}",
+                self.name, column
+            )))
+        }
+    }
+}
+
+/// A KNN query on a [`Vec0Table`], from [`Vec0Table::knn()`].
+///
+/// Scans check for interrupts between chunks, so a query run with
+/// `spawn_blocking()` can be cancelled on a timeout by calling `interrupt()`
+/// on the connection's [`rusqlite::InterruptHandle`]. It then fails with
+/// [`rusqlite::ErrorCode::OperationInterrupted`].
+pub struct Knn<'a> {
+    table: &'a Vec0Table<'a>,
+    query: &'a dyn ToSql,
+    column: Option<String>,
+    k: usize,
+    filters: Vec<(String, Box<dyn ToSql + 'a>)>,
+    select: Vec<String>,
+}
+
+impl<'a> Knn<'a> {
+    /// The vector column to search. Only needed on tables with more than one
+    /// vector column, otherwise the only one is used.
+    pub fn column(mut self, column: &str) -> Self {
+        self.column = Some(column.to_string());
+        self
+    }
+
+    /// How many rows to return, 10 by default.
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Only returns rows where `column = value`. `column` must be a partition
+    /// key or metadata column, which `vec0` filters during the KNN search.
+    pub fn filter(mut self, column: &str, value: impl ToSql + 'a) -> Self {
+        self.filters.push((column.to_string(), Box::new(value)));
+        self
+    }
+
+    /// Other columns to return, like auxiliary or metadata columns, for
+    /// [`Knn::execute_with()`] to read by name.
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.select
+            .extend(columns.iter().map(|column| column.to_string()));
+        self
+    }
+
+    /// Runs the query, nearest rows first.
+    pub fn execute(self) -> rusqlite::Result<Vec<SearchResult>> {
+        self.execute_with(|_| Ok(()))
+    }
+
+    /// Runs the query, nearest rows first, with `metadata` read from each row
+    /// by `f`. Columns from [`Knn::select()`] can be read by name.
+    pub fn execute_with<M>(
+        self,
+        mut f: impl FnMut(&Row<'_>) -> rusqlite::Result<M>,
+    ) -> rusqlite::Result<Vec<SearchResult<M>>> {
+        let table = self.table;
+        let column = table.vector_column(self.column.as_deref())?;
+
+        let mut sql = format!("select {}, distance", quote(&table.key));
+        for name in &self.select {
+            table.check_column(name)?;
+            sql.push_str(", ");
+            sql.push_str(&quote(name));
+        }
+        sql.push_str(&format!(
+            " from {} where {} match ?1 and k = ?2",
+            quote(&table.name),
+            quote(column)
+        ));
+        for (i, (name, _)) in self.filters.iter().enumerate() {
+            table.check_column(name)?;
+            sql.push_str(&format!(" and {} = ?{}", quote(name), i + 3));
+        }
+
+        let k = i64::try_from(self.k).unwrap_or(i64::MAX);
+        let mut params: Vec<&dyn ToSql> = vec![self.query, &k];
+        params.extend(self.filters.iter().map(|(_, value)| value.as_ref()));
+
+        let mut stmt = table.conn.prepare(&sql)?;
+        let mut rows = stmt.query(params.as_slice())?;
+        let mut results = Vec::new();
+        while let Some(row) = rows.next()? {
+            results.push(SearchResult {
+                rowid: row.get(0)?,
+                distance: row.get(1)?,
+                metadata: f(row)?,
+            });
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Float32Vector;
+
+    fn docs() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            create virtual table docs using vec0(
+              tenant_id integer partition key,
+              embedding float[2],
+              kind text,
+              +title text
+            );
+            insert into docs(rowid, tenant_id, embedding, kind, title) values
+              (1, 1, '[1, 1]', 'a', 'one'),
+              (2, 1, '[2, 2]', 'b', 'two'),
+              (3, 2, '[3, 3]', 'a', 'three'),
+              (4, 1, '[4, 4]', 'a', 'four');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_knn() {
+        let conn = docs();
+        let table = Vec0Table::open(&conn, "docs").unwrap();
+        assert_eq!(table.vector_columns(), ["embedding"]);
+
+        let query = Float32Vector(vec![0.0, 0.0]);
+        let rowids =
+            |results: Vec<SearchResult>| results.iter().map(|r| r.rowid).collect::<Vec<_>>();
+        assert_eq!(rowids(table.knn(&query).k(2).execute().unwrap()), [1, 2]);
+        assert_eq!(
+            rowids(
+                table
+                    .knn(&query)
+                    .filter("tenant_id", 1)
+                    .filter("kind", "a")
+                    .execute()
+                    .unwrap()
+            ),
+            [1, 4]
+        );
+
+        let results = table
+            .knn(&"[3, 3]")
+            .k(1)
+            .select(&["title"])
+            .execute_with(|row| row.get::<_, String>("title"))
+            .unwrap();
+        assert_eq!(
+            results,
+            [SearchResult {
+                rowid: 3,
+                distance: 0.0,
+                metadata: "three".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_knn_errors() {
+        let conn = docs();
+        assert!(Vec0Table::open(&conn, "missing")
+            .unwrap_err()
+            .to_string()
+            .contains("missing is not a vec0 table"));
+
+        conn.execute_batch(
+            "create virtual table pairs using vec0(
+              tenant integer, doc text, embedding float[2], primary key (tenant, doc)
+            )",
+        )
+        .unwrap();
+        assert!(Vec0Table::open(&conn, "pairs")
+            .unwrap_err()
+            .to_string()
+            .contains("pairs has a composite primary key, which Vec0Table doesn't support"));
+        conn.execute_batch(
+            "create virtual table texts using vec0(id text primary key, embedding float[2])",
+        )
+        .unwrap();
+        assert!(Vec0Table::open(&conn, "texts")
+            .unwrap_err()
+            .to_string()
+            .contains("texts can't be opened as a Vec0Table, its primary key isn't an integer"));
+
+        let table = Vec0Table::open(&conn, "docs").unwrap();
+        let query = Float32Vector(vec![0.0, 0.0]);
+        for (err, message) in [
+            (
+                table.knn(&query).filter("nope", 1).execute().unwrap_err(),
+                "docs has no column nope",
+            ),
+            (
+                table.knn(&query).column("title").execute().unwrap_err(),
+                "docs has no vector column title",
+            ),
+            (
+                table
+                    .knn(&query)
+                    .select(&["\"; drop table docs; --"])
+                    .execute()
+                    .unwrap_err(),
+                "docs has no column",
+            ),
+        ] {
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_knn_interrupt() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            "create virtual table items using vec0(embedding float[2], chunk_size=8)",
+        )
+        .unwrap();
+        let mut table = Vec0Table::open(&conn, "items").unwrap();
+        let vectors: Vec<(i64, Vec<f32>)> = (1..=2000)
+            .map(|rowid| (rowid, vec![rowid as f32; 2]))
+            .collect();
+        table
+            .upsert_batch(vectors.iter().map(|(rowid, v)| (*rowid, v.as_slice())))
+            .unwrap();
+
+        // a progress handler that returns true interrupts the statement like
+        // InterruptHandle::interrupt() does
+        conn.progress_handler(100, Some(|| true));
+        let query = Float32Vector(vec![0.0, 0.0]);
+        let err = table.knn(&query).execute().unwrap_err();
+        assert_eq!(
+            err.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::OperationInterrupted),
+            "{err}"
+        );
+
+        conn.progress_handler(100, None::<fn() -> bool>);
+        assert_eq!(table.knn(&query).k(1).execute().unwrap()[0].rowid, 1);
+    }
+
+    #[test]
+    fn test_upsert_batch() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            create virtual table docs using vec0(embedding float[2], +title text);
+            insert into docs(rowid, embedding, title) values (2, '[0, 0]', 'two');
+            "#,
+        )
+        .unwrap();
+        let mut table = Vec0Table::open(&conn, "docs").unwrap();
+        let vectors: Vec<(i64, Vec<f32>)> = (1..=20_005)
+            .map(|rowid| (rowid, vec![rowid as f32, 0.0]))
+            .collect();
+        let written = table
+            .upsert_batch(vectors.iter().map(|(rowid, v)| (*rowid, v.as_slice())))
+            .unwrap();
+        assert_eq!(written, 20_005);
+
+        let (count, embedding, title): (i64, Float32Vector, String) = conn
+            .query_row(
+                "select (select count(*) from docs), embedding, title from docs where rowid = 2",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 20_005);
+        assert_eq!(embedding, Float32Vector(vec![2.0, 0.0]));
+        // replacing the vector keeps the row's other columns
+        assert_eq!(title, "two");
+
+        // a failing row rolls back its own transaction
+        let bad: [(i64, &[f32]); 2] = [(1, &[0.0, 0.0]), (2, &[1.0])];
+        assert!(table.upsert_batch(bad.into_iter()).is_err());
+        let embedding: Float32Vector = conn
+            .query_row("select embedding from docs where rowid = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(embedding, Float32Vector(vec![1.0, 0.0]));
+        assert!(conn.is_autocommit());
+    }
+}
@@ -0,0 +1,542 @@
+//! Random vectors and tables, and differential checks of `sqlite-vec`
+//! against brute force and its plain C kernels, with the `testing` feature.
+//!
+//! The checks run against whatever build flags and CPU the crate was built
+//! for, so a property test or fuzz target in a downstream crate covers the
+//! kernels and `vec0` code paths that app actually uses:
+//!
+//! ```no_run
+//! # fn run() -> Result<(), sqlite_vec::testing::Mismatch> {
+//! use sqlite_vec::search::Metric;
+//! use sqlite_vec::testing::{check_kernels, RandomTable, Rng};
+//!
+//! let conn = rusqlite::Connection::open_in_memory()?;
+//! sqlite_vec::load(&conn)?;
+//! for seed in 0..100 {
+//!     let mut rng = Rng::new(seed);
+//!     let dimensions = 1 + rng.below(1024);
+//!     check_kernels(&mut rng, dimensions)?;
+//!     let table = RandomTable::create(&conn, &mut rng, "t", 16, Metric::Cosine, 500, "chunk_size=64")?;
+//!     let query = rng.float32_vector(16);
+//!     table.check_knn(&conn, &query.0, 10)?;
+//!     conn.execute("drop table t", [])?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::search::{Metric, SearchResult};
+use crate::statement::{Param, ZeroCopyStatement};
+use crate::table::quote;
+use crate::vector::{BitVector, Float32Vector, Int8Vector};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+
+#[link(name = "sqlite_vec0")]
+extern "C" {
+    fn vec_kernel_distance(
+        z_kernel: *const c_char,
+        b_scalar: c_int,
+        a: *const c_void,
+        b: *const c_void,
+        n: c_int,
+        p_distance: *mut f64,
+    ) -> c_int;
+}
+
+/// A small seeded random number generator (SplitMix64), so failures can be
+/// reproduced from their seed without another dependency.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Seeds a generator from a fuzzer's input, so every input drives a
+    /// different run.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        // FNV-1a
+        let seed = data.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Rng::new(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, `n` must be above 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in `[-1, 1)`.
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+
+    pub fn float32_vector(&mut self, dimensions: usize) -> Float32Vector {
+        Float32Vector((0..dimensions).map(|_| self.f32()).collect())
+    }
+
+    pub fn int8_vector(&mut self, dimensions: usize) -> Int8Vector {
+        Int8Vector((0..dimensions).map(|_| self.next_u64() as i8).collect())
+    }
+
+    /// A vector of `dimensions` bits, which must be a multiple of 8.
+    pub fn bit_vector(&mut self, dimensions: usize) -> BitVector {
+        BitVector((0..dimensions / 8).map(|_| self.next_u64() as u8).collect())
+    }
+}
+
+/// Why a check failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Mismatch {
+    /// A KNN query on a `vec0` table returned other rows or distances than
+    /// brute force.
+    Knn {
+        table: String,
+        query: Vec<f32>,
+        k: usize,
+        expected: Vec<SearchResult>,
+        actual: Vec<SearchResult>,
+    },
+    /// A kernel picked for this CPU disagrees with the plain C one.
+    Kernel {
+        kernel: Kernel,
+        dimensions: usize,
+        scalar: f64,
+        simd: f64,
+    },
+    /// The check couldn't run.
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Knn {
+                table,
+                query,
+                k,
+                expected,
+                actual,
+            } => {
+                let rows = |results: &[SearchResult]| {
+                    results
+                        .iter()
+                        .map(|r| format!("({}, {})", r.rowid, r.distance))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                write!(
+                    f,
+                    "KNN of {query:?} with k = {k} on {table} returned [{}], brute force [{}]",
+                    rows(actual),
+                    rows(expected)
+                )
+            }
+            Mismatch::Kernel {
+                kernel,
+                dimensions,
+                scalar,
+                simd,
+            } => write!(
+                f,
+                "{} kernel on {dimensions} dimensions returned {simd}, the plain C one {scalar}",
+                kernel.name()
+            ),
+            Mismatch::Sqlite(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Mismatch::Sqlite(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Mismatch {
+    fn from(err: rusqlite::Error) -> Self {
+        Mismatch::Sqlite(err)
+    }
+}
+
+/// The distances that `vec_debug()` reports picking kernels for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    L2Float,
+    CosineFloat,
+    /// The inner product itself, not its negative.
+    DotFloat,
+    Hamming,
+    L2Int8,
+    DotInt8,
+}
+
+impl Kernel {
+    pub const ALL: [Kernel; 6] = [
+        Kernel::L2Float,
+        Kernel::CosineFloat,
+        Kernel::DotFloat,
+        Kernel::Hamming,
+        Kernel::L2Int8,
+        Kernel::DotInt8,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Kernel::L2Float => "l2_float",
+            Kernel::CosineFloat => "cosine_float",
+            Kernel::DotFloat => "dot_float",
+            Kernel::Hamming => "hamming",
+            Kernel::L2Int8 => "l2_int8",
+            Kernel::DotInt8 => "dot_int8",
+        }
+    }
+}
+
+/// The distance between `a` and `b` with `kernel`, the plain C one when
+/// `scalar` is true, or the one picked for this CPU otherwise. `a` and `b`
+/// are `float32` or `int8` vectors as bytes, or bit vectors for
+/// [`Kernel::Hamming`].
+///
+/// # Panics
+///
+/// If `a` and `b` have different lengths, or one that isn't a whole number
+/// of `float32` values.
+pub fn kernel_distance(kernel: Kernel, scalar: bool, a: &[u8], b: &[u8]) -> f64 {
+    assert_eq!(a.len(), b.len(), "vectors of different lengths");
+    let n = match kernel {
+        Kernel::L2Float | Kernel::CosineFloat | Kernel::DotFloat => {
+            assert!(a.len().is_multiple_of(4), "not a float32 vector");
+            a.len() / 4
+        }
+        Kernel::Hamming | Kernel::L2Int8 | Kernel::DotInt8 => a.len(),
+    };
+    // float32 kernels read aligned f32 values
+    let (a, b): (Vec<u32>, Vec<u32>) = (aligned(a), aligned(b));
+    let name = CString::new(kernel.name()).unwrap();
+    let mut distance = 0.0;
+    // SAFETY: both buffers hold n elements of the kernel's type
+    let rc = unsafe {
+        vec_kernel_distance(
+            name.as_ptr(),
+            scalar as c_int,
+            a.as_ptr().cast(),
+            b.as_ptr().cast(),
+            n as c_int,
+            &mut distance,
+        )
+    };
+    assert_eq!(
+        rc,
+        rusqlite::ffi::SQLITE_OK,
+        "unknown kernel {}",
+        kernel.name()
+    );
+    distance
+}
+
+fn aligned(bytes: &[u8]) -> Vec<u32> {
+    let mut words = vec![0u32; bytes.len().div_ceil(4)];
+    // SAFETY: words has room for every byte
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), words.as_mut_ptr().cast(), bytes.len())
+    };
+    words
+}
+
+/// Compares every kernel picked for this CPU with the plain C one, on random
+/// vectors of `dimensions` values. Float distances may differ by rounding,
+/// integer ones must be equal.
+pub fn check_kernels(rng: &mut Rng, dimensions: usize) -> Result<(), Mismatch> {
+    // the first sqlite3_vec_init() picks the kernels
+    let conn = Connection::open_in_memory()?;
+    crate::load(&conn)?;
+    for kernel in Kernel::ALL {
+        let (a, b, exact) = match kernel {
+            Kernel::L2Float | Kernel::CosineFloat | Kernel::DotFloat => (
+                rng.float32_vector(dimensions).as_bytes().into_owned(),
+                rng.float32_vector(dimensions).as_bytes().into_owned(),
+                false,
+            ),
+            Kernel::Hamming => (
+                rng.bit_vector(dimensions * 8).0,
+                rng.bit_vector(dimensions * 8).0,
+                true,
+            ),
+            Kernel::L2Int8 | Kernel::DotInt8 => (
+                rng.int8_vector(dimensions).as_bytes().into_owned(),
+                rng.int8_vector(dimensions).as_bytes().into_owned(),
+                true,
+            ),
+        };
+        let scalar = kernel_distance(kernel, true, &a, &b);
+        let simd = kernel_distance(kernel, false, &a, &b);
+        let equal = if exact {
+            scalar == simd
+        } else {
+            close(scalar, simd)
+        };
+        // cosine of a zero vector
+        let both_nan = scalar.is_nan() && simd.is_nan();
+        if !equal && !both_nan {
+            return Err(Mismatch::Kernel {
+                kernel,
+                dimensions,
+                scalar,
+                simd,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-4 * (1.0 + a.abs())
+}
+
+/// A `vec0` table of random `float32` vectors, and a copy of its rows to
+/// check queries against.
+#[derive(Debug, Clone)]
+pub struct RandomTable {
+    pub name: String,
+    pub metric: Metric,
+    pub rows: Vec<(i64, Vec<f32>)>,
+}
+
+impl RandomTable {
+    /// Creates the `vec0` table `name` with an `embedding float[dimensions]`
+    /// column and `rows` random rows. `options` are more table options for
+    /// the `CREATE VIRTUAL TABLE`, like `"chunk_size=8"`, or `""`.
+    ///
+    /// Rowids have random gaps, and some vectors repeat earlier ones, so
+    /// queries see ties.
+    pub fn create(
+        conn: &Connection,
+        rng: &mut Rng,
+        name: &str,
+        dimensions: usize,
+        metric: Metric,
+        rows: usize,
+        options: &str,
+    ) -> rusqlite::Result<Self> {
+        let metric_name = match metric {
+            Metric::L2 => "l2",
+            Metric::L1 => "l1",
+            Metric::Cosine => "cosine",
+            Metric::Dot => "dot",
+        };
+        let options = if options.is_empty() {
+            String::new()
+        } else {
+            format!(", {options}")
+        };
+        conn.execute_batch(&format!(
+            "create virtual table {} using vec0(embedding float[{dimensions}] distance_metric={metric_name}{options})",
+            quote(name)
+        ))?;
+
+        let mut table = RandomTable {
+            name: name.to_string(),
+            metric,
+            rows: Vec::with_capacity(rows),
+        };
+        let mut rowid = 0;
+        for _ in 0..rows {
+            rowid += 1 + rng.below(3) as i64;
+            let vector = if !table.rows.is_empty() && rng.below(16) == 0 {
+                table.rows[rng.below(table.rows.len())].1.clone()
+            } else {
+                rng.float32_vector(dimensions).0
+            };
+            table.rows.push((rowid, vector));
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        let mut insert = ZeroCopyStatement::prepare(
+            conn,
+            &format!(
+                "insert into {}(rowid, embedding) values (?, ?)",
+                quote(name)
+            ),
+        )?;
+        for (rowid, vector) in &table.rows {
+            insert.execute(&[Param::Integer(*rowid), Param::Float32(vector)])?;
+        }
+        drop(insert);
+        tx.commit()?;
+        Ok(table)
+    }
+
+    /// The `k` rows nearest to `query`, by brute force in `f64`.
+    pub fn brute_force(&self, query: &[f32], k: usize) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = self
+            .rows
+            .iter()
+            .map(|(rowid, vector)| SearchResult {
+                rowid: *rowid,
+                distance: exact_distance(self.metric, query, vector),
+                metadata: (),
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then(a.rowid.cmp(&b.rowid))
+        });
+        results.truncate(k);
+        results
+    }
+
+    /// Runs a KNN query for `query` on the table and compares it with
+    /// [`RandomTable::brute_force()`]. Rows at nearly the same distance may
+    /// come in either order, but every distance must be right.
+    pub fn check_knn(&self, conn: &Connection, query: &[f32], k: usize) -> Result<(), Mismatch> {
+        let expected = self.brute_force(query, k);
+        let actual = conn
+            .prepare(&format!(
+                "select rowid, distance from {} where embedding match ? and k = ?",
+                quote(&self.name)
+            ))?
+            .query_map(
+                rusqlite::params![Float32Vector(query.to_vec()), k as i64],
+                |row| {
+                    Ok(SearchResult {
+                        rowid: row.get(0)?,
+                        distance: row.get(1)?,
+                        metadata: (),
+                    })
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let vectors: HashMap<i64, &[f32]> = self
+            .rows
+            .iter()
+            .map(|(rowid, vector)| (*rowid, vector.as_slice()))
+            .collect();
+        let mut seen = Vec::with_capacity(actual.len());
+        let matches = actual.len() == expected.len()
+            && actual.iter().zip(&expected).all(|(actual, expected)| {
+                let found = match vectors.get(&actual.rowid) {
+                    Some(vector) => exact_distance(self.metric, query, vector),
+                    None => return false,
+                };
+                let first = !seen.contains(&actual.rowid);
+                seen.push(actual.rowid);
+                first && close(expected.distance, actual.distance) && close(found, actual.distance)
+            });
+        if matches {
+            Ok(())
+        } else {
+            Err(Mismatch::Knn {
+                table: self.name.clone(),
+                query: query.to_vec(),
+                k,
+                expected,
+                actual,
+            })
+        }
+    }
+}
+
+fn exact_distance(metric: Metric, a: &[f32], b: &[f32]) -> f64 {
+    let pairs = a.iter().zip(b).map(|(a, b)| (*a as f64, *b as f64));
+    match metric {
+        Metric::L2 => pairs.map(|(a, b)| (a - b) * (a - b)).sum::<f64>().sqrt(),
+        Metric::L1 => pairs.map(|(a, b)| (a - b).abs()).sum(),
+        Metric::Dot => -pairs.map(|(a, b)| a * b).sum::<f64>(),
+        Metric::Cosine => {
+            let (mut ab, mut aa, mut bb) = (0.0, 0.0, 0.0);
+            for (a, b) in pairs {
+                ab += a * b;
+                aa += a * a;
+                bb += b * b;
+            }
+            1.0 - ab / (aa.sqrt() * bb.sqrt())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_kernels() {
+        let mut rng = Rng::new(0);
+        for dimensions in (1..70).chain([127, 128, 129, 255, 256, 1000]) {
+            check_kernels(&mut rng, dimensions).unwrap();
+        }
+        let (a, b) = ([3u8, 0xff], [1u8, 0x0f]);
+        assert_eq!(kernel_distance(Kernel::Hamming, false, &a, &b), 5.0);
+        assert_eq!(kernel_distance(Kernel::DotInt8, true, &a, &b), 3.0 - 15.0);
+    }
+
+    #[test]
+    fn test_check_knn() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+        let metrics = [Metric::L2, Metric::L1, Metric::Cosine, Metric::Dot];
+        let options = ["", "chunk_size=8", "chunk_size=8, scan=sequential"];
+        for seed in 0..24 {
+            let mut rng = Rng::new(seed);
+            let dimensions = 1 + rng.below(40);
+            let rows = rng.below(200);
+            let table = RandomTable::create(
+                &conn,
+                &mut rng,
+                "t",
+                dimensions,
+                metrics[seed as usize % 4],
+                rows,
+                options[seed as usize % 3],
+            )
+            .unwrap();
+            for k in [0, 1, 10, 300] {
+                let query = rng.float32_vector(dimensions);
+                table.check_knn(&conn, &query.0, k).unwrap();
+            }
+            conn.execute("drop table t", []).unwrap();
+        }
+
+        // rows changed behind the copy's back are caught
+        let mut rng = Rng::from_bytes(b"fuzz input");
+        let table = RandomTable::create(&conn, &mut rng, "t", 4, Metric::L2, 50, "").unwrap();
+        let (rowid, vector) = &table.rows[0];
+        conn.execute(
+            "update t set embedding = ? where rowid = ?",
+            rusqlite::params![
+                Float32Vector(vector.iter().map(|x| x + 10.0).collect()),
+                rowid
+            ],
+        )
+        .unwrap();
+        let err = table.check_knn(&conn, &[10.0; 4], 3).unwrap_err();
+        assert!(matches!(err, Mismatch::Knn { .. }), "{err}");
+        assert!(
+            err.to_string()
+                .starts_with("KNN of [10.0, 10.0, 10.0, 10.0] with k = 3 on t returned"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! Typed wrappers around the BLOB formats that `sqlite-vec` reads and writes.
+//!
+//! `sqlite-vec` stores vectors as tightly packed elements in the host's native
+//! byte order: 4 bytes per element for `float32`, 1 byte per element for
+//! `int8`, and 1 bit per element for `bit` vectors. These types convert
+//! between that layout and regular Rust collections without relying on the
+//! alignment of the underlying buffer.
+//!
+//! With the `rusqlite` feature, each type also implements `ToSql`/`FromSql`
+//! and binds as a plain BLOB, which `vec0` columns and the `vec_*` functions
+//! accept directly. The `sqlx` feature does the same with sqlx's
+//! `Type`/`Encode`/`Decode`.
+//!
+//! The `ndarray` and `nalgebra` features add `From` conversions between
+//! [`Float32Vector`]/[`Int8Vector`] and 1-D arrays, owned or borrowed, so
+//! arrays can be bound as parameters with a single `.into()`.
+//!
+//! With the `serde` feature, the types serialize as plain sequences of their
+//! elements, like `[0.1, 0.2]` in JSON. A [`BitVector`] is its packed bytes.
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// Returned when a BLOB can't be a vector of the requested element type,
+/// because its length isn't a multiple of the element size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidLength {
+    pub element_size: usize,
+    pub length: usize,
+}
+
+impl fmt::Display for InvalidLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid vector BLOB length {}, must be divisible by {}",
+            self.length, self.element_size
+        )
+    }
+}
+
+impl std::error::Error for InvalidLength {}
+
+/// A `float32` vector, as created by `vec_f32()`.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Float32Vector(pub Vec<f32>);
+
+impl Float32Vector {
+    /// Number of dimensions in the vector.
+    pub fn dimensions(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The vector in `sqlite-vec`'s BLOB format. Borrows the elements directly
+    /// on little-endian hosts, because the in-memory layout already matches.
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        #[cfg(target_endian = "little")]
+        {
+            // SAFETY: f32 has no padding or invalid bit patterns, and u8 has an
+            // alignment of 1, so any f32 slice can be viewed as bytes.
+            Cow::Borrowed(unsafe {
+                std::slice::from_raw_parts(
+                    self.0.as_ptr().cast::<u8>(),
+                    std::mem::size_of_val(self.0.as_slice()),
+                )
+            })
+        }
+        #[cfg(not(target_endian = "little"))]
+        {
+            Cow::Owned(self.0.iter().flat_map(|v| v.to_ne_bytes()).collect())
+        }
+    }
+
+    /// Parses a `float32` vector BLOB. The input doesn't need to be aligned.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidLength> {
+        if !bytes.len().is_multiple_of(std::mem::size_of::<f32>()) {
+            return Err(InvalidLength {
+                element_size: std::mem::size_of::<f32>(),
+                length: bytes.len(),
+            });
+        }
+        Ok(Self(
+            bytes
+                .chunks_exact(std::mem::size_of::<f32>())
+                .map(|chunk| f32::from_ne_bytes(chunk.try_into().unwrap()))
+                .collect(),
+        ))
+    }
+}
+
+impl From<Vec<f32>> for Float32Vector {
+    fn from(value: Vec<f32>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[f32]> for Float32Vector {
+    fn from(value: &[f32]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl From<Float32Vector> for Vec<f32> {
+    fn from(value: Float32Vector) -> Self {
+        value.0
+    }
+}
+
+/// An `int8` vector, as created by `vec_int8()` or `vec_quantize_int8()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Int8Vector(pub Vec<i8>);
+
+impl Int8Vector {
+    /// Number of dimensions in the vector.
+    pub fn dimensions(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The vector in `sqlite-vec`'s BLOB format.
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        // SAFETY: i8 and u8 have identical size and alignment.
+        Cow::Borrowed(unsafe {
+            std::slice::from_raw_parts(self.0.as_ptr().cast::<u8>(), self.0.len())
+        })
+    }
+
+    /// Parses an `int8` vector BLOB. Every length is valid.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.iter().map(|&b| b as i8).collect())
+    }
+}
+
+impl From<Vec<i8>> for Int8Vector {
+    fn from(value: Vec<i8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[i8]> for Int8Vector {
+    fn from(value: &[i8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl From<Int8Vector> for Vec<i8> {
+    fn from(value: Int8Vector) -> Self {
+        value.0
+    }
+}
+
+/// A `bit` vector, as created by `vec_bit()` or `vec_quantize_binary()`.
+///
+/// Bits are packed 8 to a byte, least significant bit first, so dimension `i`
+/// lives in bit `i % 8` of byte `i / 8`. The number of dimensions is always a
+/// multiple of 8.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct BitVector(pub Vec<u8>);
+
+impl BitVector {
+    /// Number of dimensions (bits) in the vector.
+    pub fn dimensions(&self) -> usize {
+        self.0.len() * 8
+    }
+
+    /// Value of the bit at dimension `i`, or `None` if out of range.
+    pub fn get(&self, i: usize) -> Option<bool> {
+        self.0.get(i / 8).map(|byte| (byte >> (i % 8)) & 1 == 1)
+    }
+
+    /// Packs a slice of booleans into a bit vector. Panics if the length isn't
+    /// a multiple of 8, since `sqlite-vec` can't represent partial bytes.
+    pub fn from_bits(bits: &[bool]) -> Self {
+        assert!(
+            bits.len().is_multiple_of(8),
+            "bit vectors must have a multiple of 8 dimensions"
+        );
+        Self(
+            bits.chunks_exact(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u8, |byte, (i, &bit)| byte | ((bit as u8) << i))
+                })
+                .collect(),
+        )
+    }
+
+    /// The vector in `sqlite-vec`'s BLOB format.
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    /// Parses a `bit` vector BLOB. Every length is valid.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for BitVector {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+mod rusqlite_impls {
+    use super::*;
+    use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+    fn to_sql_output(bytes: Cow<'_, [u8]>) -> ToSqlOutput<'_> {
+        match bytes {
+            Cow::Borrowed(b) => ToSqlOutput::Borrowed(ValueRef::Blob(b)),
+            Cow::Owned(b) => ToSqlOutput::Owned(rusqlite::types::Value::Blob(b)),
+        }
+    }
+
+    impl ToSql for Float32Vector {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(to_sql_output(self.as_bytes()))
+        }
+    }
+
+    impl FromSql for Float32Vector {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            Float32Vector::from_bytes(value.as_blob()?)
+                .map_err(|err| FromSqlError::Other(Box::new(err)))
+        }
+    }
+
+    impl ToSql for Int8Vector {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(to_sql_output(self.as_bytes()))
+        }
+    }
+
+    impl FromSql for Int8Vector {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            Ok(Int8Vector::from_bytes(value.as_blob()?))
+        }
+    }
+
+    impl ToSql for BitVector {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(to_sql_output(self.as_bytes()))
+        }
+    }
+
+    impl FromSql for BitVector {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            Ok(BitVector::from_bytes(value.as_blob()?))
+        }
+    }
+}
+
+#[cfg(feature = "sqlx")]
+mod sqlx_impls {
+    use super::*;
+    use ::sqlx::encode::IsNull;
+    use ::sqlx::error::BoxDynError;
+    use ::sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+    use ::sqlx::{Decode, Encode, Type};
+
+    // Vectors are BLOBs to sqlx, so type checks and encoding defer to Vec<u8>
+    macro_rules! blob_impls {
+        ($ty:ty, |$bytes:ident| $from_bytes:expr) => {
+            impl Type<Sqlite> for $ty {
+                fn type_info() -> SqliteTypeInfo {
+                    <Vec<u8> as Type<Sqlite>>::type_info()
+                }
+
+                fn compatible(ty: &SqliteTypeInfo) -> bool {
+                    <Vec<u8> as Type<Sqlite>>::compatible(ty)
+                }
+            }
+
+            impl<'q> Encode<'q, Sqlite> for $ty {
+                fn encode_by_ref(
+                    &self,
+                    buf: &mut Vec<SqliteArgumentValue<'q>>,
+                ) -> Result<IsNull, BoxDynError> {
+                    <Vec<u8> as Encode<'q, Sqlite>>::encode(self.as_bytes().into_owned(), buf)
+                }
+            }
+
+            impl<'r> Decode<'r, Sqlite> for $ty {
+                fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+                    let $bytes = <&[u8] as Decode<'r, Sqlite>>::decode(value)?;
+                    Ok($from_bytes)
+                }
+            }
+        };
+    }
+
+    blob_impls!(Float32Vector, |bytes| Float32Vector::from_bytes(bytes)?);
+    blob_impls!(Int8Vector, |bytes| Int8Vector::from_bytes(bytes));
+    blob_impls!(BitVector, |bytes| BitVector::from_bytes(bytes));
+}
+
+#[cfg(feature = "ndarray")]
+mod ndarray_impls {
+    use super::*;
+    use ndarray::{Array1, ArrayView1};
+
+    // Views can be strided, so elements are copied one by one instead of
+    // assuming a contiguous slice
+    macro_rules! ndarray_impls {
+        ($ty:ident, $elem:ty) => {
+            impl From<ArrayView1<'_, $elem>> for $ty {
+                fn from(view: ArrayView1<'_, $elem>) -> Self {
+                    $ty(view.iter().copied().collect())
+                }
+            }
+
+            impl From<&Array1<$elem>> for $ty {
+                fn from(array: &Array1<$elem>) -> Self {
+                    $ty::from(array.view())
+                }
+            }
+
+            impl From<Array1<$elem>> for $ty {
+                fn from(array: Array1<$elem>) -> Self {
+                    $ty::from(array.view())
+                }
+            }
+
+            impl From<$ty> for Array1<$elem> {
+                fn from(vector: $ty) -> Self {
+                    Array1::from(vector.0)
+                }
+            }
+        };
+    }
+
+    ndarray_impls!(Float32Vector, f32);
+    ndarray_impls!(Int8Vector, i8);
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use super::*;
+    use nalgebra::{DVector, DVectorView};
+
+    macro_rules! nalgebra_impls {
+        ($ty:ident, $elem:ty) => {
+            impl From<DVectorView<'_, $elem>> for $ty {
+                fn from(view: DVectorView<'_, $elem>) -> Self {
+                    $ty(view.iter().copied().collect())
+                }
+            }
+
+            impl From<&DVector<$elem>> for $ty {
+                fn from(vector: &DVector<$elem>) -> Self {
+                    $ty(vector.as_slice().to_vec())
+                }
+            }
+
+            impl From<DVector<$elem>> for $ty {
+                fn from(vector: DVector<$elem>) -> Self {
+                    $ty(vector.data.into())
+                }
+            }
+
+            impl From<$ty> for DVector<$elem> {
+                fn from(vector: $ty) -> Self {
+                    DVector::from_vec(vector.0)
+                }
+            }
+        };
+    }
+
+    nalgebra_impls!(Float32Vector, f32);
+    nalgebra_impls!(Int8Vector, i8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float32_roundtrip() {
+        let v = Float32Vector(vec![0.1, -2.5, 3.0]);
+        let bytes = v.as_bytes().into_owned();
+        assert_eq!(bytes.len(), 12);
+        assert_eq!(Float32Vector::from_bytes(&bytes).unwrap(), v);
+
+        // unaligned input
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&bytes);
+        assert_eq!(Float32Vector::from_bytes(&shifted[1..]).unwrap(), v);
+
+        assert_eq!(
+            Float32Vector::from_bytes(&[0, 0, 0]),
+            Err(InvalidLength {
+                element_size: 4,
+                length: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_int8_roundtrip() {
+        let v = Int8Vector(vec![-128, 0, 127]);
+        assert_eq!(v.as_bytes().as_ref(), &[0x80, 0x00, 0x7f]);
+        assert_eq!(Int8Vector::from_bytes(&v.as_bytes()), v);
+    }
+
+    #[test]
+    fn test_bit_roundtrip() {
+        let v = BitVector::from_bits(&[true, false, false, false, false, false, false, true]);
+        assert_eq!(v.as_bytes().as_ref(), &[0b1000_0001]);
+        assert_eq!(v.dimensions(), 8);
+        assert_eq!(v.get(0), Some(true));
+        assert_eq!(v.get(1), Some(false));
+        assert_eq!(v.get(8), None);
+        assert_eq!(BitVector::from_bytes(&v.as_bytes()), v);
+    }
+
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn test_rusqlite_types() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::load(&conn).unwrap();
+
+        let (json, v): (String, Float32Vector) = conn
+            .query_row(
+                "select vec_to_json(?1), vec_f32(?1)",
+                [Float32Vector(vec![1.0, 2.0])],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(json, "[1.000000,2.000000]");
+        assert_eq!(v, Float32Vector(vec![1.0, 2.0]));
+
+        let v: Int8Vector = conn
+            .query_row("select vec_int8('[1, -2, 3]')", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(v, Int8Vector(vec![1, -2, 3]));
+
+        let v: BitVector = conn
+            .query_row(
+                "select vec_quantize_binary('[1, -1, 1, -1, 1, -1, 1, -1]')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(v, BitVector(vec![0b0101_0101]));
+
+        let length: i64 = conn
+            .query_row(
+                "select vec_length(vec_int8(?))",
+                [Int8Vector(vec![1; 4])],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(length, 4);
+
+        let err = conn
+            .query_row("select X'000000'", [], |row| row.get::<_, Float32Vector>(0))
+            .unwrap_err();
+        assert!(matches!(err, rusqlite::Error::FromSqlConversionFailure(..)));
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_ndarray() {
+        let array = ndarray::array![1.0f32, 2.0, 3.0, 4.0];
+        assert_eq!(
+            Float32Vector::from(&array),
+            Float32Vector(vec![1.0, 2.0, 3.0, 4.0])
+        );
+        // strided view
+        let every_other = array.slice(ndarray::s![..;2]);
+        assert_eq!(
+            Float32Vector::from(every_other),
+            Float32Vector(vec![1.0, 3.0])
+        );
+        let back: ndarray::Array1<f32> = Float32Vector::from(array.clone()).into();
+        assert_eq!(back, array);
+
+        let v: Int8Vector = ndarray::array![-1i8, 0, 1].into();
+        assert_eq!(v, Int8Vector(vec![-1, 0, 1]));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_nalgebra() {
+        let vector = nalgebra::DVector::from_vec(vec![1.0f32, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            Float32Vector::from(&vector),
+            Float32Vector(vec![1.0, 2.0, 3.0, 4.0])
+        );
+        assert_eq!(
+            Float32Vector::from(vector.rows(1, 2)),
+            Float32Vector(vec![2.0, 3.0])
+        );
+        let back: nalgebra::DVector<f32> = Float32Vector::from(vector.clone()).into();
+        assert_eq!(back, vector);
+
+        let v: Int8Vector = nalgebra::DVector::from_vec(vec![-1i8, 0, 1]).into();
+        assert_eq!(v, Int8Vector(vec![-1, 0, 1]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let v = Float32Vector(vec![0.5, -1.0]);
+        let json = serde_json::to_string(&v).unwrap();
+        assert_eq!(json, "[0.5,-1.0]");
+        assert_eq!(serde_json::from_str::<Float32Vector>(&json).unwrap(), v);
+        assert_eq!(
+            serde_json::to_string(&Int8Vector(vec![-1, 2])).unwrap(),
+            "[-1,2]"
+        );
+        assert_eq!(
+            serde_json::from_str::<BitVector>("[5]").unwrap(),
+            BitVector(vec![0b101])
+        );
+    }
+}
@@ -34,6 +34,84 @@ extern "C" {
 SQLITE_VEC_API int sqlite3_vec_init(sqlite3 *db, char **pzErrMsg,
                   const sqlite3_api_routines *pApi);
 
+/*
+** Calls xProgress(pArg, zOperation, zTable, done, total) during long vec0
+** operations on db, like 'optimize', 'rebuild', 'train' and batch inserts, so
+** apps can show their progress. A non-zero return cancels the operation with
+** SQLITE_INTERRUPT. xDestroy(pArg), if not NULL, is called once the handler
+** is replaced or db is closed. A NULL xProgress removes the handler. db must
+** have sqlite-vec loaded already.
+*/
+SQLITE_VEC_API int vec0_set_progress_handler(
+    sqlite3 *db,
+    int (*xProgress)(void *pArg, const char *zOperation, const char *zTable,
+                     sqlite3_int64 done, sqlite3_int64 total),
+    void *pArg, void (*xDestroy)(void *pArg));
+
+/*
+** Supplies the keys of encrypt='aes-gcm' vec0 tables on db that have none
+** from vec0_set_key(). xKey(pArg, zSchema, zTable, key) writes the 32 byte
+** key of zTable to key and returns non-zero, or returns 0 when it has none.
+** It's called when a table is created and the first time each connected
+** table reads or writes a vector. xDestroy(pArg), if not NULL, is called once
+** the handler is replaced or db is closed. A NULL xKey removes the handler.
+** db must have sqlite-vec loaded already.
+*/
+SQLITE_VEC_API int vec0_set_key_handler(
+    sqlite3 *db,
+    int (*xKey)(void *pArg, const char *zSchema, const char *zTable,
+                unsigned char *key),
+    void *pArg, void (*xDestroy)(void *pArg));
+
+/*
+** Offloads the distances of KNN queries on float32 vec0 columns of db, like
+** to a GPU. xDistances(pArg, zMetric, query, vectors, n, dimensions, k,
+** distances) writes the distances between query and each of the n vectors,
+** stored one after the other, to distances and returns 0. zMetric is "l2",
+** "l1", "cosine" or "dot" (the negative inner product), and k is how many
+** rows the query returns. Any other return has sqlite-vec compute the batch
+** itself, so a handler can decline batches too small to be worth it, or fail
+** over to the CPU. It's called with whole chunks of the table during a scan,
+** from several threads at once for threads=N tables, and with the
+** candidates of rescoring passes. xDestroy(pArg), if not NULL, is called once
+** the handler is replaced or db is closed. A NULL xDistances removes the
+** handler. db must have sqlite-vec loaded already.
+*/
+SQLITE_VEC_API int vec0_set_distance_handler(
+    sqlite3 *db,
+    int (*xDistances)(void *pArg, const char *zMetric, const float *query,
+                      const float *vectors, int n, int dimensions,
+                      sqlite3_int64 k, float *distances),
+    void *pArg, void (*xDestroy)(void *pArg));
+
+/*
+** Calls xSlowQuery(pArg, zTable, zPlan, elapsedMs) after each KNN query on
+** db's vec0 tables that took at least thresholdMs milliseconds, with
+** threshold 0 after every one, so apps can log slow queries. zPlan is what
+** vec_debug_last_plan() returns for the query, like "knn on docs.embedding
+** via chunk scan". xDestroy(pArg), if not NULL, is called once the handler
+** is replaced or db is closed. A NULL xSlowQuery removes the handler. db must
+** have sqlite-vec loaded already.
+*/
+SQLITE_VEC_API int vec0_set_slow_query_handler(
+    sqlite3 *db, sqlite3_int64 thresholdMs,
+    void (*xSlowQuery)(void *pArg, const char *zTable, const char *zPlan,
+                       sqlite3_int64 elapsedMs),
+    void *pArg, void (*xDestroy)(void *pArg));
+
+/*
+** Computes the distance between a and b, n elements or, for "hamming", n
+** bytes each, with the kernel vec_debug() reports when bScalar is 0, or with
+** the plain C one otherwise, so tests can check SIMD kernels against it.
+** zKernel is "l2_float", "cosine_float", "dot_float" (the inner product
+** itself), "hamming", "l2_int8" or "dot_int8". Returns SQLITE_NOTFOUND for
+** any other. Kernels are picked for the CPU by the first sqlite3_vec_init(),
+** before it the compile-time ones are used.
+*/
+SQLITE_VEC_API int vec_kernel_distance(const char *zKernel, int bScalar,
+                                       const void *a, const void *b, int n,
+                                       double *pDistance);
+
 #ifdef __cplusplus
 }  /* end of the 'extern "C"' block */
 #endif
@@ -0,0 +1,87 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_add_column(db):
+    db.execute("create virtual table v using vec0(a float[1], chunk_size=8)")
+    for i in range(10):
+        db.execute("insert into v(rowid, a) values (?, ?)", [i + 1, f"[{i}]"])
+
+    db.execute("insert into v(v) values ('add_column=+notes text')")
+    db.execute("insert into v(v) values ('add_column=kind text')")
+    db.execute("insert into v(v) values ('add_column=score float')")
+    assert rows(db, "select name from pragma_table_info('v')") == [
+        ("rowid",),
+        ("a",),
+        ("notes",),
+        ("kind",),
+        ("score",),
+    ]
+    # only the new columns' storage was written, one metadata chunk per chunk
+    assert rows(db, "select count(*) from v_auxiliary") == [(10,)]
+    assert rows(db, "select count(*) from v_metadatachunks00") == [(2,)]
+
+    # existing rows read NULL for auxiliary columns, zero values for metadata
+    assert rows(db, "select rowid, notes, kind, score from v where rowid <= 2") == [
+        (1, None, "", 0.0),
+        (2, None, "", 0.0),
+    ]
+
+    db.execute(
+        "insert into v(rowid, a, notes, kind, score) values (11, '[1]', 'new', 'big', 1.5)"
+    )
+    db.execute("update v set notes = 'old', kind = 'small' where rowid = 3")
+    assert rows(db, "select rowid, notes, kind, score from v where kind != ''") == [
+        (3, "old", "small", 0.0),
+        (11, "new", "big", 1.5),
+    ]
+    assert rows(
+        db, "select rowid, distance from v where a match '[1]' and k = 3 and kind = 'big'"
+    ) == [(11, 0.0)]
+
+    db.execute("alter table v rename to w")
+    assert rows(db, "select notes, kind from w where rowid = 11") == [("new", "big")]
+
+
+def test_add_column_other_connection(tmp_path):
+    path = str(tmp_path / "test.db")
+
+    def connect():
+        db = sqlite3.connect(path)
+        db.enable_load_extension(True)
+        db.load_extension("dist/vec0")
+        return db
+
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[1], +label text)")
+    db.execute("insert into v(rowid, a, label) values (1, '[1]', 'one')")
+    db.commit()
+
+    other = connect()
+    assert rows(other, "select label from v") == [("one",)]
+    db.execute("insert into v(v) values ('add_column=+extra integer')")
+    db.execute("update v set extra = 7 where rowid = 1")
+    db.commit()
+    assert rows(other, "select label, extra from v") == [("one", 7)]
+
+
+def test_add_column_errors(db):
+    db.execute("create virtual table v using vec0(a float[1], b text)")
+    for definition, message in [
+        ("b float[4]", "add_column only supports metadata and auxiliary columns"),
+        ("c integer partition key", "add_column only supports metadata and auxiliary columns"),
+        ("c text primary key", "add_column only supports metadata and auxiliary columns"),
+        ("+B text", "v already has a column named B"),
+        ("distance float", "v already has a column named distance"),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=message):
+            db.execute(f"insert into v(v) values ('add_column={definition}')")
+    assert rows(db, "select name from pragma_table_info('v')") == [
+        ("rowid",),
+        ("a",),
+        ("b",),
+    ]
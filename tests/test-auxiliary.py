@@ -164,6 +164,51 @@ def test_vacuum(db, snapshot):
     assert cur_page_count < prev_page_count
 
 
+def test_columns_of_a_row_read_together(db):
+    db.execute(
+        "create virtual table v using vec0(a float[1], +x text, +y integer, +z blob, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, x, y, z) values (?, '[1]', ?, ?, ?)",
+        [(i, f"x{i}", i * 10, bytes([i])) for i in range(1, 21)],
+    )
+    expected = [(i, f"x{i}", i * 10, bytes([i])) for i in range(1, 21)]
+    assert exec_rows(db, "select rowid, x, y, z from v") == expected
+    assert exec_rows(db, "select rowid, z, y, x from v where rowid = 7") == [
+        (7, bytes([7]), 70, "x7")
+    ]
+    assert exec_rows(
+        db, "select rowid, x, y, z from v where a match '[1]' and k = 20"
+    ) == expected
+    # two cursors on the same table
+    assert exec_rows(
+        db,
+        "select l.x, r.x from v l join v r on r.rowid = l.y / 10 + 1 where l.rowid < 3",
+    ) == [("x1", "x2"), ("x2", "x3")]
+
+    # written to while the cursor reads it: later reads see the new values
+    db.execute("update v set x = x || '!', y = y + 1")
+    assert exec_rows(db, "select x, y from v where rowid in (1, 20)") == [
+        ("x1!", 11),
+        ("x20!", 201),
+    ]
+
+    db.execute("insert into v(v) values ('add_column=w text')")
+    db.execute("update v set w = 'new' where rowid = 1")
+    assert exec_rows(db, "select x, w from v where rowid in (1, 2)") == [
+        ("x1!", "new"),
+        ("x2!", ""),
+    ]
+    db.execute("insert into v(v) values ('drop_column=x')")
+    assert exec_rows(db, "select y, z, w from v where rowid = 1") == [
+        (11, bytes([1]), "new")
+    ]
+
+
+def exec_rows(db, sql):
+    return [tuple(row) for row in db.execute(sql).fetchall()]
+
+
 def exec(db, sql, parameters=[]):
     try:
         rows = db.execute(sql, parameters).fetchall()
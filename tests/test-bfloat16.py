@@ -0,0 +1,66 @@
+import json
+import math
+import sqlite3
+import struct
+import pytest
+
+
+def _bf16(list):
+    # the upper halves of float32s, for values that are exact in bfloat16
+    return b"".join(struct.pack("<f", x)[2:] for x in list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_bfloat16_distances(db):
+    # long enough for the SIMD kernels, with a scalar tail
+    x = [1, 2, 3, 4, 5, 6, 7, 8, 9]
+    y = [-1, 0, 1, 0.5, 0.25, 2, 2, -4, 8]
+    a, b = _bf16(x), _bf16(y)
+    distance = lambda fn, a, b: db.execute(
+        f"select vec_distance_{fn}(vec_bf16(?), vec_bf16(?))", [a, b]
+    ).fetchone()[0]
+
+    assert distance("l2", a, b) == pytest.approx(
+        math.sqrt(sum((i - j) ** 2 for i, j in zip(x, y)))
+    )
+    assert distance("l1", a, b) == sum(abs(i - j) for i, j in zip(x, y))
+    assert distance("dot", a, b) == -sum(i * j for i, j in zip(x, y))
+    assert distance("cosine", a, a) == pytest.approx(0, abs=1e-6)
+    with pytest.raises(sqlite3.OperationalError, match="Vector type mistmatch"):
+        db.execute("select vec_distance_l2(vec_bf16(?), vec_f16(?))", [a, a])
+
+
+def test_bfloat16_quantize_int8(db):
+    values = [0.5, -0.5, 1, -1, 0.25]
+    assert rows(db, "select vec_quantize_int8(vec_bf16(?), 'unit')", [_bf16(values)]) == rows(
+        db, "select vec_quantize_int8(?, 'unit')", [json.dumps(values)]
+    )
+    assert rows(
+        db, "select vec_length(vec_quantize_int8(vec_bf16('[0.5]'), 'unit'))"
+    ) == [(1,)]
+
+
+def test_bfloat16_vec0(db):
+    db.execute(
+        "create virtual table v using vec0(a bfloat16[2], b bf16[2] distance_metric=cosine index=hnsw)"
+    )
+    vectors = [[1, 0], [0, 1], [-1, -1], [0.5, 0.5]]
+    for i, vector in enumerate(vectors):
+        db.execute(
+            "insert into v(rowid, a, b) values (?, vec_bf16(?), vec_bf16(?))",
+            [i + 1, _bf16(vector), _bf16(vector)],
+        )
+    assert rows(db, "select vec_type(a), vec_to_json(a) from v where rowid = 4") == [
+        ("bfloat16", "[0.500000,0.500000]")
+    ]
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 1024 * 4
+
+    knn = rows(db, "select rowid, distance from v where a match vec_bf16('[1, 0.5]') and k = 2")
+    assert knn == [(1, 0.5), (4, 0.5)] or knn == [(4, 0.5), (1, 0.5)]
+    assert rows(db, "select rowid from v where b match vec_bf16('[1, 1]') and k = 1") == [(4,)]
+
+    with pytest.raises(sqlite3.OperationalError, match="expected to be of type bfloat16"):
+        db.execute("insert into v(rowid, a, b) values (5, vec_f16('[1, 1]'), vec_bf16('[1, 1]'))")
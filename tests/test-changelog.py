@@ -0,0 +1,103 @@
+import sqlite3
+import struct
+import time
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_changelog(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          id text primary key,
+          a float[2],
+          +note text,
+          tag text,
+          +expires_at integer,
+          expires_at=expires_at,
+          changelog=on
+        )
+        """
+    )
+    before = int(time.time())
+    db.execute(
+        "insert into v(id, a, note, tag, expires_at) values ('x', '[1, 2]', 'a', 't', null), ('y', '[3, 4]', 'b', 't', 100)"
+    )
+    db.execute("update v set note = 'c' where id = 'x'")
+    db.execute("update v set a = '[5, 6]', tag = 'u' where id = 'x'")
+    db.execute("delete from v where id = 'x'")
+    db.execute("insert into v(v) values ('expire=100')")
+    assert rows(db, "select change_id, id, op from v_changelog") == [
+        (1, "x", "insert"),
+        (2, "y", "insert"),
+        (3, "x", "update"),
+        (4, "x", "update"),
+        (5, "x", "delete"),
+        (6, "y", "delete"),
+    ]
+    assert rows(
+        db, "select count(*) from v_changelog where timestamp between ? and ?",
+        [before, int(time.time())],
+    ) == [(6,)]
+
+    # a sync layer purges what it has seen
+    db.execute("insert into v(v) values ('purge_changelog=4')")
+    assert rows(db, "select change_id from v_changelog") == [(5,), (6,)]
+    db.execute("insert into v(id, a, tag) values ('z', '[0, 0]', 't')")
+    db.execute("insert into v(v) values ('purge_changelog')")
+    assert rows(db, "select count(*) from v_changelog") == [(0,)]
+    # change ids keep counting up after a purge
+    db.execute("delete from v where id = 'z'")
+    assert rows(db, "select change_id, id, op from v_changelog") == [(8, "z", "delete")]
+
+    db.execute("alter table v rename to w")
+    assert rows(db, "select count(*) from w_changelog") == [(1,)]
+    db.execute("drop table w")
+    assert rows(db, "select name from sqlite_master where name like 'w%'") == []
+
+
+def test_changelog_integer_rowids(db):
+    db.execute("create virtual table v using vec0(a float[2], changelog=on)")
+    db.execute("insert into v(rowid, a) values (10, '[1, 1]')")
+    db.execute(
+        "insert into v(v, rowid, a) values ('batch', ?, ?)",
+        [struct.pack("=2q", 20, 21), struct.pack("4f", 1, 2, 3, 4)],
+    )
+    db.execute("delete from v where rowid = 20")
+    assert rows(db, "select id, op from v_changelog") == [
+        (10, "insert"),
+        (20, "insert"),
+        (21, "insert"),
+        (20, "delete"),
+    ]
+
+    # a rolled back statement leaves no change behind
+    db.commit()
+    db.execute("begin")
+    db.execute("update v set a = '[9, 9]' where rowid = 10")
+    db.execute("rollback")
+    assert rows(db, "select count(*) from v_changelog") == [(4,)]
+
+
+def test_changelog_errors(db):
+    db.execute("create virtual table v using vec0(a float[2])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="'purge_changelog' requires the changelog=on table option",
+    ):
+        db.execute("insert into v(v) values ('purge_changelog')")
+    assert rows(db, "select name from sqlite_master where name = 'v_changelog'") == []
+
+    db.execute("create virtual table w using vec0(a float[2], changelog=on)")
+    with pytest.raises(
+        sqlite3.OperationalError, match="change_id must be a non-negative integer"
+    ):
+        db.execute("insert into w(w) values ('purge_changelog=-1')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: changelog must be on or off",
+    ):
+        db.execute("create virtual table x using vec0(a float[2], changelog=yes)")
@@ -0,0 +1,168 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect():
+    db = sqlite3.connect(":memory:")
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+SCHEMA = """
+  create virtual table v using vec0(
+    id text primary key,
+    a float[2],
+    b int8[2],
+    tag text,
+    shard integer partition key,
+    +note text,
+    row_version=true,
+    changelog=on
+  )
+"""
+
+
+def sync(source, target, since=0):
+    (changeset,) = source.execute("select vec0_changeset('v', ?)", [since]).fetchone()
+    (changes,) = target.execute(
+        "select vec0_apply_changeset('v', ?)", [changeset]
+    ).fetchone()
+    return changes
+
+
+def test_changeset():
+    laptop = connect()
+    phone = connect()
+    for db in [laptop, phone]:
+        db.execute(SCHEMA)
+
+    laptop.execute(
+        "insert into v(id, a, b, tag, shard, note) values ('x', ?, vec_int8('[1, 2]'), 't', 0, 'first')",
+        [_f32([1, 2])],
+    )
+    laptop.execute(
+        "insert into v(id, a, b, tag, shard) values ('y', ?, vec_int8('[3, 4]'), 'u', 1)",
+        [_f32([3, 4])],
+    )
+    laptop.execute("update v set note = 'edited' where id = 'x'")
+    assert sync(laptop, phone) == 2
+    everything = "select id, a, b, tag, shard, note from v order by id"
+    assert rows(phone, everything) == rows(laptop, everything)
+    knn = "select id from v where a match ? and k = 1 and shard = 1"
+    assert rows(phone, knn, [_f32([3, 4])]) == [("y",)]
+
+    # rows come in the order of their last change, and the phone logged what
+    # it applied, so sending it back changes nothing on the laptop
+    assert rows(phone, "select id, op from v_changelog") == [
+        ("y", "insert"),
+        ("x", "insert"),
+    ]
+    assert sync(phone, laptop) == 0
+    assert rows(laptop, "select max(change_id) from v_changelog") == [(3,)]
+
+    # only what changed since the last sync
+    phone.execute("update v set b = vec_int8('[7, 8]'), tag = 'w', shard = 2 where id = 'y'")
+    phone.execute("delete from v where id = 'x'")
+    phone.execute(
+        "insert into v(id, a, b, tag, shard) values ('z', ?, vec_int8('[5, 6]'), 'v', 0)",
+        [_f32([5, 6])],
+    )
+    assert sync(phone, laptop, since=2) == 3
+    assert rows(laptop, everything) == rows(phone, everything)
+    assert rows(laptop, "select id, __version from v order by id") == [("y", 2), ("z", 1)]
+    assert rows(laptop, "select * from vec0_integrity_check('v')") == []
+
+    # a row that no longer exists anywhere is left alone
+    laptop.execute("delete from v where id = 'z'")
+    phone.execute("delete from v where id = 'z'")
+    assert sync(laptop, phone, since=3) == 0
+
+    # nothing changed since the latest change_id
+    (latest,) = laptop.execute("select max(change_id) from v_changelog").fetchone()
+    assert sync(laptop, phone, since=latest) == 0
+
+
+def test_changeset_integer_rowids():
+    source = connect()
+    target = connect()
+    source.execute("create virtual table v using vec0(a bit[8], +n integer, changelog=on)")
+    target.execute("create virtual table v using vec0(a bit[8], +n integer, +extra text)")
+    source.execute("insert into v(rowid, a, n) values (1, vec_bit(x'0f'), 10), (2, vec_bit(x'f0'), 20)")
+    source.execute("delete from v where rowid = 1")
+    target.execute("insert into v(rowid, a, n, extra) values (1, vec_bit(x'ff'), 0, 'e')")
+    assert sync(source, target) == 2
+    # columns the changeset doesn't have keep their defaults
+    assert rows(target, "select rowid, a, n, extra from v") == [(2, b"\xf0", 20, None)]
+
+
+def test_changeset_errors():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[2])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_changeset\\(\\) requires the changelog=on table option on v",
+    ):
+        db.execute("select vec0_changeset('v')")
+    with pytest.raises(sqlite3.OperationalError, match="t is not a vec0 table"):
+        db.execute("select vec0_changeset('t')")
+    db.execute(
+        "create virtual table w using vec0(a float[2], +note text, changelog=on)"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_changeset\\(\\) since must be a non-negative change_id",
+    ):
+        db.execute("select vec0_changeset('w', -1)")
+    db.execute(
+        "create virtual table c using vec0(a text, b integer, emb float[2], primary key (a, b), changelog=on)"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_changeset\\(\\) doesn't support composite PRIMARY KEY tables",
+    ):
+        db.execute("select vec0_changeset('c')")
+
+    db.execute("insert into w(rowid, a, note) values (1, '[1, 2]', 'n')")
+    (changeset,) = db.execute("select vec0_changeset('w')").fetchone()
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='vec0_apply_changeset\\(\\) v has no column "note"',
+    ):
+        db.execute("select vec0_apply_changeset('v', ?)", [changeset])
+    db.execute("create virtual table t using vec0(id text primary key, a float[2], +note text)")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='vec0_apply_changeset\\(\\) changeset is keyed on "rowid", t is keyed on "id"',
+    ):
+        db.execute("select vec0_apply_changeset('t', ?)", [changeset])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_apply_changeset\\(\\) argument isn't a vec0 changeset",
+    ):
+        db.execute("select vec0_apply_changeset('v', x'00')")
+
+    # a broken change rolls back the ones before it
+    db.execute("insert into w(rowid, a, note) values (2, '[3, 4]', 'm')")
+    (changeset,) = db.execute("select vec0_changeset('w')").fetchone()
+    db.execute("create virtual table x using vec0(a float[2], +note text)")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_apply_changeset\\(\\) changeset is truncated or corrupt",
+    ):
+        db.execute("select vec0_apply_changeset('x', ?)", [changeset[:-5]])
+    assert rows(db, "select count(*) from x") == [(0,)]
+    assert db.execute("select vec0_apply_changeset('x', ?)", [changeset]).fetchone() == (2,)
+    assert rows(db, "select rowid, a, note from x") == [
+        (1, _f32([1, 2]), "n"),
+        (2, _f32([3, 4]), "m"),
+    ]
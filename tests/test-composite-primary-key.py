@@ -0,0 +1,125 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_composite_primary_key(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          tenant_id integer,
+          doc_id text,
+          a float[2],
+          +body text,
+          primary key (tenant_id, doc_id)
+        )
+        """
+    )
+    for tenant_id in [1, 2]:
+        for i, doc_id in enumerate(["x", "y", "z"]):
+            db.execute(
+                "insert into v(tenant_id, doc_id, a, body) values (?, ?, ?, ?)",
+                [tenant_id, doc_id, f"[{tenant_id}, {i}]", f"{tenant_id}{doc_id}"],
+            )
+
+    assert rows(db, "select rowid, key00, key01 from v_rowids") == [
+        (1, 1, "x"),
+        (2, 1, "y"),
+        (3, 1, "z"),
+        (4, 2, "x"),
+        (5, 2, "y"),
+        (6, 2, "z"),
+    ]
+    assert rows(db, "select body from v where tenant_id = 2 and doc_id = 'y'") == [
+        ("2y",)
+    ]
+    assert rows(db, "select vec_debug_last_plan()") == [("key lookup on v",)]
+    assert rows(db, "select body from v where tenant_id = 3 and doc_id = 'y'") == []
+    assert rows(db, "select doc_id from v where doc_id = 'z'") == [("z",), ("z",)]
+
+    # KNN queries only consider the rows matching the key constraints
+    assert rows(
+        db,
+        "select tenant_id, doc_id, distance from v where a match '[1, 0]' and k = 2 and tenant_id = 2",
+    ) == [(2, "x", 1.0), (2, "y", pytest.approx(1.4142135))]
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("knn key-filtered on v.a via chunk scan",)
+    ]
+    assert rows(
+        db,
+        "select rowid from v where a match '[1, 0]' and k = 5 and doc_id = 'y' and rowid in (2, 3, 4)",
+    ) == [(2,)]
+
+    with pytest.raises(
+        sqlite3.OperationalError, match="UNIQUE constraint failed on v primary key"
+    ):
+        db.execute("insert into v(tenant_id, doc_id, a) values (1, 'x', '[0, 0]')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Primary key type mismatch: The primary key column doc_id has type TEXT, but INTEGER was provided.",
+    ):
+        db.execute("insert into v(tenant_id, doc_id, a) values (1, 4, '[0, 0]')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="The primary key column tenant_id has type INTEGER, but NULL was provided.",
+    ):
+        db.execute("insert into v(doc_id, a) values ('w', '[0, 0]')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="UPDATEs on vec0 primary key values are not allowed.",
+    ):
+        db.execute("update v set doc_id = 'w' where tenant_id = 1 and doc_id = 'x'")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Only = constraints are supported on vec0 primary key columns in KNN queries",
+    ):
+        db.execute("select rowid from v where a match '[1, 0]' and k = 2 and tenant_id > 1")
+
+    db.execute("update v set body = 'new' where tenant_id = 1 and doc_id = 'x'")
+    db.execute("delete from v where tenant_id = 2")
+    assert rows(db, "select tenant_id, doc_id, body from v") == [
+        (1, "x", "new"),
+        (1, "y", "1y"),
+        (1, "z", "1z"),
+    ]
+    assert rows(db, "select count(*) from v_rowids") == [(3,)]
+
+    # deleted keys can be inserted again
+    db.execute("insert into v(tenant_id, doc_id, a) values (2, 'x', '[9, 9]')")
+    assert rows(db, "select rowid, tenant_id, doc_id from v where tenant_id = 2") == [
+        (7, 2, "x")
+    ]
+
+
+def test_composite_primary_key_errors(db):
+    for columns, message in [
+        (
+            "a float[2], primary key (b)",
+            "Primary key column b is not declared as a column",
+        ),
+        (
+            "a float[2], b float, primary key (b)",
+            "Primary key column b must be declared as INTEGER or TEXT",
+        ),
+        (
+            "a float[2], b text, primary key (b, b)",
+            "Column b appears more than once in the primary key",
+        ),
+        (
+            "a float[2], b text, c text, primary key (b), primary key (c)",
+            "Only one primary key constraint is allowed",
+        ),
+        (
+            "a float[2], b text, primary key ()",
+            "could not parse primary key constraint",
+        ),
+        (
+            "id text primary key, a float[2], b text, primary key (b)",
+            "A primary key constraint can't be combined with a primary key column",
+        ),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=message):
+            db.execute(f"create virtual table v using vec0({columns})")
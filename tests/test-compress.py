@@ -0,0 +1,240 @@
+import random
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect(path=":memory:"):
+    db = sqlite3.connect(path)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+def lz4_block_decompress(src, size):
+    # a plain reading of the LZ4 block format, to check the stored bytes
+    out = bytearray()
+    i = 0
+    while True:
+        token = src[i]
+        i += 1
+        literals = token >> 4
+        if literals == 15:
+            while True:
+                literals += src[i]
+                i += 1
+                if src[i - 1] != 255:
+                    break
+        out += src[i : i + literals]
+        i += literals
+        if i == len(src):
+            break
+        offset = src[i] | (src[i + 1] << 8)
+        i += 2
+        length = token & 15
+        if length == 15:
+            while True:
+                length += src[i]
+                i += 1
+                if src[i - 1] != 255:
+                    break
+        for _ in range(length + 4):
+            out.append(out[-offset])
+    assert len(out) == size
+    return bytes(out)
+
+
+def stored(blob, size):
+    # a 0x00 byte then the value as is, or 0x01, the decompressed size and
+    # an LZ4 block
+    if blob[0] == 0:
+        return blob[1:]
+    assert blob[0] == 1
+    assert struct.unpack("<I", blob[1:5])[0] == size
+    return lz4_block_decompress(blob[5:], size)
+
+
+SCHEMA = """
+  create virtual table %s using vec0(
+    a int8[64],
+    b bit[128],
+    label text,
+    +note text,
+    +n integer,
+    chunk_size=64
+    %s
+  )
+"""
+
+
+def test_compress(tmp_path):
+    path = str(tmp_path / "compressed.db")
+    db = connect(path)
+    db.execute(SCHEMA % ("v", ", compress='lz4'"))
+    db.execute(SCHEMA % ("u", ""))
+    rng = random.Random(0)
+    for i in range(1, 151):
+        # quantized vectors are mostly a few small values
+        a = [rng.choice([0, 0, 0, 0, 0, 1, -1]) for _ in range(64)]
+        b = bytes(rng.choice([0, 255]) for _ in range(16))
+        note = "the same note, again and again " * rng.randint(0, 8)
+        for t in ["u", "v"]:
+            db.execute(
+                f"insert into {t}(rowid, a, b, label, note, n) "
+                "values (?, vec_int8(?), vec_bit(?), ?, ?, ?)",
+                [i, str(a), b, "x" if i % 2 else "y", note, i],
+            )
+    db.commit()
+
+    # the same rows, in less space
+    size = "select sum(length(vectors)) from %s_vector_chunks00"
+    assert db.execute(size % "v").fetchone()[0] < db.execute(size % "u").fetchone()[0] / 2
+    size = "select sum(length(value00)) from %s_auxiliary"
+    assert db.execute(size % "v").fetchone()[0] < db.execute(size % "u").fetchone()[0] / 2
+    # short values stay TEXT, longer ones are compressed into BLOBs
+    assert rows(db, "select typeof(value00), count(*) from v_auxiliary group by 1") == [
+        ("blob", 113),
+        ("text", 37),
+    ]
+    (chunk,) = db.execute("select vectors from v_vector_chunks00 where rowid = 1").fetchone()
+    (plain,) = db.execute("select vectors from u_vector_chunks00 where rowid = 1").fetchone()
+    assert stored(chunk, len(plain)) == plain
+
+    everything = "select rowid, a, b, label, note, n from %s order by rowid"
+    assert rows(db, everything % "v") == rows(db, everything % "u")
+    knn = (
+        "select rowid, distance from %s where a match vec_int8(?) and k = 10 "
+        "and label = 'x'"
+    )
+    query = [str([1] * 64)]
+    assert rows(db, knn % "v", query) == rows(db, knn % "u", query)
+    knn = "select rowid, distance, note from %s where b match vec_bit(?) and k = 5"
+    assert rows(db, knn % "v", [bytes(16)]) == rows(db, knn % "u", [bytes(16)])
+
+    for t in ["u", "v"]:
+        db.execute(f"update {t} set a = vec_int8(?), note = 'short' where rowid = 3", query)
+        db.execute(f"update {t} set note = ? where rowid = 4", ["long " * 100])
+        db.execute(f"delete from {t} where rowid % 3 = 0 and rowid > 3")
+        db.execute(f"insert into {t}({t}) values ('optimize')")
+        db.execute(f"insert into {t}({t}) values ('rechunk=16')")
+    assert rows(db, everything % "v") == rows(db, everything % "u")
+    knn = "select rowid, distance from %s where a match vec_int8(?) and k = 10"
+    assert rows(db, knn % "v", query) == rows(db, knn % "u", query)
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+    db.commit()
+    db.close()
+
+    db = connect(path)
+    assert rows(db, everything % "v") == rows(db, everything % "u")
+    db.execute("alter table v rename to w")
+    assert rows(db, "select note from w where rowid = 4") == [("long " * 100,)]
+
+
+def test_compress_roundtrip():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[1], +note text, compress='lz4')")
+    rng = random.Random(1)
+    notes = [
+        "",
+        "a",
+        "a" * 13,
+        "a" * 100000,
+        "ab" * 5000 + "c",
+        "".join(rng.choice("abc") for _ in range(5000)),
+        "".join(chr(rng.randint(32, 0x2FFF)) for _ in range(3000)),
+        ("%d " % rng.randint(0, 9)) * 300 + "x" * 270 + "yz" * 600,
+    ]
+    for i, note in enumerate(notes):
+        db.execute("insert into v(rowid, a, note) values (?, '[0]', ?)", [i, note])
+    assert rows(db, "select note from v order by rowid") == [(note,) for note in notes]
+    for (i, value) in rows(db, "select rowid, value00 from v_auxiliary"):
+        if isinstance(value, bytes):
+            encoded = notes[i].encode()
+            assert stored(value, len(encoded)) == encoded
+
+
+def test_compress_batch_and_pq():
+    db = connect()
+    db.execute(
+        "create virtual table v using vec0(a float[4] quantize=pq(m=2, nbits=2), "
+        "chunk_size=8, compress='lz4')"
+    )
+    vectors = [[float(i % 3), 1, 0, 1] for i in range(20)]
+    db.execute(
+        "insert into v(v, rowid, a) values ('batch', ?, ?)",
+        [struct.pack("=20q", *range(1, 21)), b"".join(_f32(v) for v in vectors)],
+    )
+    (chunk,) = db.execute("select vectors from v_vector_chunks00 where rowid = 1").fetchone()
+    assert stored(chunk, 8 * 16)[:16] == _f32(vectors[0])
+    db.execute("insert into v(v) values ('train')")
+    (chunk,) = db.execute("select vectors from v_vector_chunks00 where rowid = 1").fetchone()
+    # one code of m bytes per row, the same for equal vectors whatever
+    # centroids training happened to pick
+    codes = stored(chunk, 8 * 2)
+    codes = [codes[i : i + 2] for i in range(0, len(codes), 2)]
+    assert len(codes) == 8
+    for i, code in enumerate(codes):
+        assert code == codes[i % 3]
+    assert len(set(code[1] for code in codes)) == 1
+    assert len(rows(db, "select rowid from v where a match ? and k = 3", [_f32([2, 1, 0, 1])])) == 3
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_compress_corrupt():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[2], +note text, chunk_size=8, compress='lz4')")
+    db.execute("insert into v(rowid, a, note) values (1, '[1, 2]', ?)", ["n" * 100])
+    db.execute("update v_vector_chunks00 set vectors = x'01ff000000'")
+    with pytest.raises(
+        sqlite3.DatabaseError,
+        match="vectors blob of chunk 1 of main.v_vector_chunks00 is corrupt, "
+        "it doesn't decompress to 64 bytes",
+    ):
+        db.execute("select rowid from v where a match '[1, 2]' and k = 1").fetchall()
+    with pytest.raises(sqlite3.DatabaseError, match="doesn't decompress to 64 bytes"):
+        db.execute("select a from v where rowid = 1").fetchall()
+    assert rows(db, "select * from vec0_integrity_check('v')") == [
+        ("v_vector_chunks00", 1, "chunk 1 has a vectors blob that doesn't decompress"),
+    ]
+    db.execute("update v_vector_chunks00 set vectors = x'00' || zeroblob(8)")
+    assert rows(db, "select * from vec0_integrity_check('v')") == [
+        (
+            "v_vector_chunks00",
+            1,
+            "chunk 1 has a 8 byte vectors blob once decompressed, instead of 64 bytes",
+        ),
+    ]
+
+    db.execute("update v_auxiliary set value00 = x'01ff'")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "note" value of rowid 1 is corrupt, it doesn\'t decompress',
+    ):
+        db.execute("select note from v").fetchall()
+
+
+def test_compress_errors():
+    db = connect()
+    for value in ["zstd", "lz4hc", "''"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="vec0 constructor error: compress must be 'lz4'",
+        ):
+            db.execute(f"create virtual table v using vec0(a float[2], compress={value})")
+    db.execute("select vec0_set_key('v', ?)", [bytes(32)])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: compress='lz4' can't be combined with "
+        "encrypt='aes-gcm'",
+    ):
+        db.execute(
+            "create virtual table v using vec0(a float[2], compress='lz4', encrypt='aes-gcm')"
+        )
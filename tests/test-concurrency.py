@@ -0,0 +1,44 @@
+import sqlite3
+import pytest
+
+
+def connect(path):
+    db = sqlite3.connect(path, isolation_level=None)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    db.enable_load_extension(False)
+    return db
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_changes_from_other_connections(tmp_path):
+    path = str(tmp_path / "concurrency.db")
+    writer = connect(path)
+    writer.execute("pragma journal_mode=wal")
+    writer.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    writer.execute(
+        "insert into v(rowid, a) select value, json_array(value, 0) from json_each('[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]')"
+    )
+
+    reader = connect(path)
+    knn = "select rowid from v where a match '[4.1, 0]' and k = 3"
+    assert rows(reader, knn) == [(4,), (5,), (3,)]
+
+    # the reader picks up the writer's new chunk size
+    writer.execute("insert into v(v) values ('rechunk=16')")
+    assert rows(reader, knn) == [(4,), (5,), (3,)]
+    reader.execute("insert into v(rowid, a) values (11, '[4, 1]')")
+    assert rows(writer, knn) == [(4,), (5,), (11,)]
+    assert rows(writer, "select count(*) from v_chunks") == [(1,)]
+
+    # a read transaction keeps seeing the table as it was when it started
+    reader.execute("begin")
+    assert rows(reader, knn) == [(4,), (5,), (11,)]
+    writer.execute("insert into v(v) values ('rechunk=8')")
+    assert rows(reader, knn) == [(4,), (5,), (11,)]
+    reader.execute("commit")
+    assert rows(reader, knn) == [(4,), (5,), (11,)]
+    assert rows(reader, "select count(*) from v_chunks") == [(2,)]
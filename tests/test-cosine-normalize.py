@@ -0,0 +1,70 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def _i64(list):
+    return struct.pack("%sq" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_normalize_stores_unit_vectors(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[2] distance_metric=cosine normalize=true,
+          b float[2] distance_metric=cosine
+        )
+        """
+    )
+    vectors = [[3, 4], [1, 0], [-2, 1], [0, 0.5]]
+    for i, vector in enumerate(vectors):
+        db.execute(
+            "insert into v(rowid, a, b) values (?, ?, ?)",
+            [i + 1, _f32(vector), _f32(vector)],
+        )
+    assert rows(db, "select vec_to_json(a), vec_to_json(b) from v where rowid = 1") == [
+        ("[0.600000,0.800000]", "[3.000000,4.000000]")
+    ]
+
+    # distances match the column that normalizes at query time
+    q = _f32([1, 2])
+    normalized = rows(db, "select rowid, distance from v where a match ? and k = 4", [q])
+    plain = rows(db, "select rowid, distance from v where b match ? and k = 4", [q])
+    assert [r[0] for r in normalized] == [r[0] for r in plain]
+    for (_, x), (_, y) in zip(normalized, plain):
+        assert x == pytest.approx(y, abs=1e-6)
+
+    db.execute("update v set a = ? where rowid = 2", [_f32([0, 10])])
+    assert rows(db, "select vec_to_json(a) from v where rowid = 2") == [
+        ("[0.000000,1.000000]",)
+    ]
+
+    db.execute(
+        "insert into v(v, rowid, a, b) values ('batch', ?, ?, ?)",
+        [_i64([5]), _f32([0, -2]), _f32([0, -2])],
+    )
+    assert rows(db, "select vec_to_json(a) from v where rowid = 5") == [
+        ("[0.000000,-1.000000]",)
+    ]
+
+
+def test_normalize_constructor_errors(db):
+    for column in [
+        "a float[2] normalize=true",
+        "a float[2] distance_metric=l2 normalize=true",
+        "a int8[2] distance_metric=cosine normalize=true",
+        "a float[2] distance_metric=cosine normalize=yes",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0({column})")
+    db.execute(
+        "create virtual table v using vec0(a float[2] normalize=false distance_metric=cosine)"
+    )
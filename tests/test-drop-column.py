@@ -0,0 +1,121 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_drop_column(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[1],
+          +x text,
+          +y text,
+          +z text,
+          m1 integer,
+          m2 text,
+          m3 text,
+          chunk_size=8
+        )
+        """
+    )
+    for i in range(10):
+        db.execute(
+            "insert into v(rowid, a, x, y, z, m1, m2, m3) values (?, ?, ?, ?, ?, ?, ?, ?)",
+            [i + 1, f"[{i}]", f"x{i}", f"y{i}", f"z{i}", i, f"m2-{i}", f"long m3 value {i}"],
+        )
+
+    db.execute("insert into v(v) values ('drop_column=y')")
+    db.execute("insert into v(v) values ('drop_column=M2')")
+    assert rows(db, "select name from pragma_table_info('v')") == [
+        ("rowid",),
+        ("a",),
+        ("x",),
+        ("z",),
+        ("m1",),
+        ("m3",),
+    ]
+    # later columns moved down into the freed slots
+    assert rows(db, "select * from v_auxiliary where rowid = 1") == [(1, "x0", "z0")]
+    assert rows(
+        db,
+        "select name from sqlite_master where name like 'v_metadata%' order by 1",
+    ) == [
+        ("v_metadatachunks00",),
+        ("v_metadatachunks01",),
+        ("v_metadatachunks02",),
+        ("v_metadatatext01",),
+        ("v_metadatatext02",),
+    ]
+    # the dropped column's storage is emptied, and kept for later columns
+    assert rows(db, "select count(*) from v_metadatachunks02") == [(0,)]
+    assert rows(db, "select count(*) from v_metadatatext02") == [(0,)]
+    assert rows(db, "select rowid, x, z, m1, m3 from v where m3 = 'long m3 value 3'") == [
+        (4, "x3", "z3", 3, "long m3 value 3")
+    ]
+
+    db.execute(
+        "insert into v(rowid, a, x, z, m1, m3) values (11, '[1]', 'x', 'z', 11, 'new')"
+    )
+    db.execute("update v set z = 'zz', m3 = 'updated' where rowid = 2")
+    assert sorted(
+        rows(db, "select rowid, z, m3 from v where a match '[1]' and k = 3 and m1 > 0")
+    ) == [(2, "zz", "updated"), (3, "z2", "long m3 value 2"), (11, "z", "new")]
+
+    # a dropped name can be added again, as a new empty column
+    db.execute("insert into v(v) values ('add_column=+y integer')")
+    db.execute("insert into v(v) values ('drop_column=x')")
+    db.execute("insert into v(v) values ('drop_column=z')")
+    db.execute("insert into v(v) values ('drop_column=y')")
+    assert rows(db, "select * from v_auxiliary") == []
+    db.execute("insert into v(v) values ('add_column=+notes text')")
+    assert rows(db, "select rowid, notes from v where rowid <= 2") == [
+        (1, None),
+        (2, None),
+    ]
+
+    db.execute("alter table v rename to w")
+    assert rows(db, "select name from pragma_table_info('w')") == [
+        ("rowid",),
+        ("a",),
+        ("m1",),
+        ("m3",),
+        ("notes",),
+    ]
+    assert rows(db, "select m3 from w where rowid = 11") == [("new",)]
+    db.execute("insert into w(w) values ('add_column=m4 text')")
+    assert rows(db, "select count(*) from w_metadatachunks02") == [(2,)]
+    assert rows(db, "select count(*) from w where m4 = ''") == [(11,)]
+
+    db.execute("drop table w")
+    assert rows(db, "select name from sqlite_master where name like 'w%'") == []
+
+
+def test_drop_column_errors(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          user_id integer partition key,
+          a float[1],
+          +expires integer,
+          expires_at=expires
+        )
+        """
+    )
+    for name, message in [
+        ("b", "v has no column named b"),
+        ("a", "Only metadata and auxiliary columns can be dropped, a is a vector column"),
+        ("user_id", "user_id is a partition key column"),
+        ("expires", "expires can't be dropped, it's the expires_at column"),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=message):
+            db.execute(f"insert into v(v) values ('drop_column={name}')")
+
+    db.setconfig(sqlite3.SQLITE_DBCONFIG_DEFENSIVE, True)
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="add_column can't be used on connections with SQLITE_DBCONFIG_DEFENSIVE enabled",
+    ):
+        db.execute("insert into v(v) values ('add_column=+notes text')")
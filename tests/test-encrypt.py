@@ -0,0 +1,185 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect(path=":memory:"):
+    db = sqlite3.connect(path)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+KEY = bytes(range(32))
+
+
+def test_encrypt(tmp_path):
+    path = str(tmp_path / "encrypted.db")
+    db = connect(path)
+    db.execute("select vec0_set_key('v', ?)", [KEY])
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[4],
+          b int8[2],
+          label text,
+          +note text,
+          chunk_size=8,
+          encrypt='aes-gcm'
+        )
+        """
+    )
+    for i in range(1, 23):
+        db.execute(
+            "insert into v(rowid, a, b, label, note) values (?, ?, vec_int8(?), ?, 'n')",
+            [i, _f32([i, 1, 2, 3]), "[%d, 1]" % i, "y" if i == 22 else "x"],
+        )
+    db.commit()
+
+    # the plaintext vectors aren't anywhere in the chunk blobs
+    chunks = b"".join(
+        blob for (blob,) in rows(db, "select vectors from v_vector_chunks00")
+    )
+    assert len(chunks) == 3 * 8 * (16 + 28)
+    for i in range(1, 23):
+        assert _f32([i, 1, 2, 3]) not in chunks
+
+    knn = "select rowid from v where a match ? and k = 3"
+    assert rows(db, knn, [_f32([5.2, 1, 2, 3])]) == [(5,), (6,), (4,)]
+    assert rows(
+        db, "select rowid from v where b match vec_int8('[22, 1]') and k = 1 and label = 'y'"
+    ) == [(22,)]
+    assert rows(db, "select a, vec_to_json(b) from v where rowid = 7") == [
+        (_f32([7, 1, 2, 3]), "[7,1]")
+    ]
+
+    db.execute("update v set a = ? where rowid = 5", [_f32([100, 0, 0, 0])])
+    db.execute("delete from v where rowid = 4")
+    assert rows(db, knn, [_f32([5.2, 1, 2, 3])]) == [(6,), (7,), (3,)]
+    # a deleted slot is cleared, its nonce and tag too
+    (chunk,) = db.execute("select vectors from v_vector_chunks00 where rowid = 1").fetchone()
+    assert chunk[3 * 16 : 4 * 16] == bytes(16)
+    assert chunk[8 * 16 + 3 * 28 : 8 * 16 + 4 * 28] == bytes(28)
+
+    db.execute("delete from v where rowid % 3 = 0")
+    db.execute("insert into v(v) values ('optimize')")
+    db.execute("insert into v(v) values ('rechunk=16')")
+    assert rows(db, knn, [_f32([5.2, 1, 2, 3])]) == [(7,), (8,), (2,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+    db.commit()
+    db.close()
+
+    # a new connection needs the key again
+    db = connect(path)
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="v is encrypted, set its key with vec0_set_key\\(\\) first",
+    ):
+        db.execute(knn, [_f32([5.2, 1, 2, 3])]).fetchall()
+    with pytest.raises(sqlite3.OperationalError, match="v is encrypted"):
+        db.execute("insert into v(rowid, a, b) values (99, ?, vec_int8('[1, 1]'))", [_f32([1, 1, 1, 1])])
+    # metadata and auxiliary columns aren't encrypted
+    assert rows(db, "select count(*) from v where label = 'y'") == [(1,)]
+    db.execute("select vec0_set_key('v', ?)", [KEY])
+    assert rows(db, knn, [_f32([5.2, 1, 2, 3])]) == [(7,), (8,), (2,)]
+
+    # renamed tables keep their key
+    db.execute("alter table v rename to w")
+    assert rows(db, "select rowid from w where rowid = 7") == [(7,)]
+    assert rows(db, "select a from w where rowid = 7") == [(_f32([7, 1, 2, 3]),)]
+
+
+def test_encrypt_batch():
+    db = connect()
+    db.execute("select vec0_set_key('v', ?)", [KEY])
+    db.execute("create virtual table v using vec0(a float[2], encrypt='aes-gcm')")
+    db.execute(
+        "insert into v(v, rowid, a) values ('batch', ?, ?)",
+        [struct.pack("=3q", 1, 2, 3), _f32([1, 1, 2, 2, 3, 3])],
+    )
+    assert _f32([2, 2]) not in db.execute("select vectors from v_vector_chunks00").fetchone()[0]
+    assert rows(db, "select rowid from v where a match '[2, 2.1]' and k = 2") == [(2,), (3,)]
+
+
+def test_encrypt_tampering():
+    db = connect()
+    db.execute("select vec0_set_key('v', ?)", [KEY])
+    db.execute("create virtual table v using vec0(a float[2], chunk_size=8, encrypt='aes-gcm')")
+    db.execute("insert into v(rowid, a) values (1, '[1, 2]'), (2, '[3, 4]')")
+    (chunk,) = db.execute("select vectors from v_vector_chunks00").fetchone()
+
+    # swapping two vectors and their seals fails, they're bound to their rowid
+    swapped = bytearray(chunk)
+    swapped[0:8], swapped[8:16] = chunk[8:16], chunk[0:8]
+    swapped[64:92], swapped[92:120] = chunk[92:120], chunk[64:92]
+    db.execute("update v_vector_chunks00 set vectors = ?", [bytes(swapped)])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "a" vector of rowid 1 failed authentication, its chunk was modified or is corrupt',
+    ):
+        db.execute("select rowid from v where a match '[1, 2]' and k = 1").fetchall()
+    with pytest.raises(sqlite3.OperationalError, match="rowid 2 failed authentication"):
+        db.execute("select a from v where rowid = 2").fetchall()
+
+    db.execute("update v_vector_chunks00 set vectors = ?", [chunk])
+    assert rows(db, "select rowid from v where a match '[1, 2]' and k = 1") == [(1,)]
+
+
+def test_encrypt_aes_gcm():
+    # the stored bytes are standard AES-256-GCM
+    aead = pytest.importorskip("cryptography.hazmat.primitives.ciphers.aead")
+    db = connect()
+    db.execute("select vec0_set_key('v', ?)", [KEY])
+    db.execute("create virtual table v using vec0(a float[3], chunk_size=8, encrypt='aes-gcm')")
+    db.execute("insert into v(rowid, a) values (-5, '[1, 2, 3]')")
+    (chunk,) = db.execute("select vectors from v_vector_chunks00").fetchone()
+    ciphertext = chunk[0:12]
+    nonce, tag = chunk[8 * 12 : 8 * 12 + 12], chunk[8 * 12 + 12 : 8 * 12 + 28]
+    aad = struct.pack("<q", -5) + b"\x00"
+    assert aead.AESGCM(KEY).decrypt(nonce, ciphertext + tag, aad) == _f32([1, 2, 3])
+
+
+def test_encrypt_errors():
+    db = connect()
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: v is encrypt='aes-gcm' but has no key, "
+        "set one with vec0_set_key\\(\\) or vec0_set_key_handler\\(\\) before creating it",
+    ):
+        db.execute("create virtual table v using vec0(a float[2], encrypt='aes-gcm')")
+    assert rows(db, "select count(*) from sqlite_master") == [(0,)]
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: encrypt must be 'aes-gcm'",
+    ):
+        db.execute("create virtual table v using vec0(a float[2], encrypt=aes)")
+
+    db.execute("select vec0_set_key('v', ?)", [KEY])
+    for column in ["a float[8] index=hnsw()", "a float[8] quantize=int8"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match='Vector column "a" can\'t have an index= or quantize= option '
+            "in an encrypt='aes-gcm' table",
+        ):
+            db.execute(
+                "create virtual table v using vec0(%s, encrypt='aes-gcm')" % column
+            )
+
+    # the _info table keeps a check of the key, never the key
+    db.execute("create virtual table v using vec0(a float[2], encrypt='aes-gcm')")
+    (check,) = db.execute("select value from v_info where key = 'key_check'").fetchone()
+    assert len(check) == 28 and KEY not in check
+    db.execute("delete from v_info where key = 'key_check'")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_set_key\\(\\) v has no valid 'key_check' entry in its _info shadow table",
+    ):
+        db.execute("select vec0_set_key('v', ?)", [KEY])
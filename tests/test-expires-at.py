@@ -0,0 +1,82 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_expires_at(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          session_id text primary key,
+          a float[1],
+          +expires_at integer,
+          chunk_size=8,
+          expires_at=expires_at
+        )
+        """
+    )
+    for i in range(20):
+        db.execute(
+            "insert into v(session_id, a, expires_at) values (?, ?, ?)",
+            [f"s{i}", f"[{i}]", 1000 + i if i < 16 else None],
+        )
+    assert rows(db, "select count(*) from v_chunks") == [(3,)]
+
+    db.execute("insert into v(v) values ('expire=1009')")
+    assert rows(db, "select count(*), min(session_id) from v") == [(10, "s10")]
+    # the first chunk only held expired rows
+    assert rows(db, "select chunk_id from v_chunks") == [(2,), (3,)]
+    assert rows(db, "select count(*) from v_vector_chunks00") == [(2,)]
+    assert rows(db, "select count(*) from v_rowids") == [(10,)]
+    assert rows(
+        db, "select session_id, distance from v where a match '[0]' and k = 1"
+    ) == [("s10", 10.0)]
+
+    # rows without an expiry time are kept, and 'expire' on its own uses the
+    # current time
+    db.execute("insert into v(v) values ('expire')")
+    assert rows(db, "select session_id from v") == [
+        ("s16",),
+        ("s17",),
+        ("s18",),
+        ("s19",),
+    ]
+
+    # 'optimize' purges expired rows too
+    db.execute(
+        "insert into v(session_id, a, expires_at) values ('old', '[1]', 1), ('new', '[1]', 99999999999)"
+    )
+    db.execute("insert into v(v) values ('optimize')")
+    assert rows(db, "select session_id from v where a match '[1]' and k = 2") == [
+        ("new",),
+        ("s16",),
+    ]
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="expire time must be a non-negative integer",
+    ):
+        db.execute("insert into v(v) values ('expire=soon')")
+
+
+def test_expires_at_errors(db):
+    db.execute("create virtual table v using vec0(a float[1])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="'expire' requires the expires_at table option",
+    ):
+        db.execute("insert into v(v) values ('expire')")
+
+    for columns in [
+        "a float[1], expires_at=ttl",
+        "a float[1], +ttl text, expires_at=ttl",
+        "a float[1], ttl integer, expires_at=ttl",
+    ]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="expires_at must name an INTEGER auxiliary column, like \\+ttl integer",
+        ):
+            db.execute(f"create virtual table w using vec0({columns})")
@@ -0,0 +1,120 @@
+import json
+import math
+import sqlite3
+import struct
+import pytest
+
+
+def _f64(list):
+    return struct.pack("<%sd" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_float64_distances(db):
+    x = [1, 2, 3, 4.5, 1e-3]
+    y = [-1, 0, 1, 0.5, 2.5e-3]
+    a, b = _f64(x), _f64(y)
+    distance = lambda fn, a, b: db.execute(
+        f"select vec_distance_{fn}(vec_f64(?), vec_f64(?))", [a, b]
+    ).fetchone()[0]
+
+    assert distance("l2", a, b) == pytest.approx(
+        math.sqrt(sum((i - j) ** 2 for i, j in zip(x, y))), rel=1e-15
+    )
+    assert distance("l1", a, b) == pytest.approx(
+        sum(abs(i - j) for i, j in zip(x, y)), rel=1e-15
+    )
+    assert distance("dot", a, b) == pytest.approx(
+        -sum(i * j for i, j in zip(x, y)), rel=1e-15
+    )
+    assert distance("cosine", a, a) == pytest.approx(0, abs=1e-15)
+
+    # a difference far below float32 precision
+    near = _f64([1, 1 + 1e-12])
+    assert distance("l2", _f64([1, 1]), near) == pytest.approx(1e-12, rel=1e-3)
+
+    with pytest.raises(sqlite3.OperationalError, match="Vector type mistmatch"):
+        db.execute("select vec_distance_l2(vec_f64(?), vec_f32('[1, 1]'))", [near])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Cannot calculate hamming distance between two float64 vectors.",
+    ):
+        distance("hamming", a, a)
+
+
+def test_float64_functions(db):
+    # shortest round-trip doubles
+    values = [0.1, 1 / 3, -2.5e-3, 123456.789, 1e300]
+    assert rows(db, "select vec_to_json(vec_f64(?))", [_f64(values)]) == [
+        ("[0.1,0.3333333333333333,-0.0025,123456.789,1.0e+300]",)
+    ]
+    (json,) = db.execute("select vec_to_json(vec_f64(?))", [_f64(values)]).fetchone()
+    assert rows(db, "select vec_f64(?)", [json]) == [(_f64(values),)]
+
+    assert rows(
+        db,
+        "select vec_to_json(vec_add(vec_f64('[0.1, 1]'), vec_f64('[0.2, 1]'))), "
+        "vec_to_json(vec_slice(vec_f64('[1, 2, 3]'), 1, 3)), "
+        "vec_type(vec_scale(vec_f64('[1, 2]'), 0.1))",
+    ) == [("[0.30000000000000004,2.0]", "[2.0,3.0]", "float64")]
+    assert rows(db, "select value from vec_each(vec_f64('[0.1, 2]'))") == [
+        (0.1,),
+        (2.0,),
+    ]
+    assert rows(
+        db,
+        "select vec_type(vec_avg(vec_f64(value))), vec_to_json(vec_avg(vec_f64(value))) "
+        "from json_each('[[0.1, 1], [0.2, 3]]')",
+    ) == [("float64", "[0.15000000000000002,2.0]")]
+    assert rows(
+        db,
+        "select vec_valid(vec_f64('[1]'), 'float64', 1), vec_valid(?, 'float64')",
+        [_f64([math.inf])],
+    ) == [(None, "element 0 is infinite")]
+
+
+def test_float64_quantize_int8(db):
+    # one int8 element per float64 one
+    assert rows(db, "select vec_quantize_int8(vec_f64('[0.5]'), 'unit')") == rows(
+        db, "select vec_quantize_int8('[0.5]', 'unit')"
+    )
+    values = [0.5, -0.5, 1, -1, 0.1]
+    assert rows(db, "select vec_quantize_int8(vec_f64(?), 'unit')", [_f64(values)]) == rows(
+        db, "select vec_quantize_int8(?, 'unit')", [json.dumps(values)]
+    )
+
+
+def test_float64_vec0(db):
+    db.execute(
+        "create virtual table v using vec0(a float64[2], b f64[2] distance_metric=cosine index=hnsw)"
+    )
+    vectors = [[1, 0], [0, 1], [-1, -1], [0.5, 0.25]]
+    for i, vector in enumerate(vectors):
+        db.execute(
+            "insert into v(rowid, a, b) values (?, vec_f64(?), vec_f64(?))",
+            [i + 1, _f64(vector), _f64(vector)],
+        )
+    assert rows(db, "select vec_type(a), vec_to_json(a) from v where rowid = 4") == [
+        ("float64", "[0.5,0.25]")
+    ]
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 1024 * 16
+
+    # distances are rounded to float32 for ranking
+    assert rows(
+        db, "select rowid, distance from v where a match vec_f64('[1, 0.5]') and k = 2"
+    ) == [(1, 0.5), (4, pytest.approx(math.sqrt(0.3125), rel=1e-7))]
+    assert rows(db, "select rowid from v where a match vec_f64('[1, 0.1]') and k = 3") == [
+        (1,),
+        (4,),
+        (2,),
+    ]
+    assert rows(db, "select rowid from v where b match vec_f64('[1, 1]') and k = 1") == [(4,)]
+
+    with pytest.raises(sqlite3.OperationalError, match="expected to be of type float64"):
+        db.execute("insert into v(rowid, a, b) values (5, '[1, 1]', vec_f64('[1, 1]'))")
+    # quantize, like ivf and normalize, is for float32 columns only
+    with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+        db.execute("create virtual table q using vec0(a float64[2] quantize=int8)")
@@ -27,6 +27,25 @@ def test_info(db, snapshot):
     assert exec(db, "select key, typeof(value) from v_info order by 1") == snapshot()
 
 
+@pytest.mark.parametrize("threads", [1, 4])
+def test_knn_interrupt(db, threads):
+    db.execute(
+        f"create virtual table v using vec0(a float[2], chunk_size=8, threads={threads})"
+    )
+    db.executemany(
+        "insert into v(rowid, a) values (?, ?)",
+        [(i, f"[{i}, {i}]") for i in range(1, 2001)],
+    )
+    # a progress handler that returns non-zero interrupts the statement like
+    # sqlite3_interrupt() does, once the chunk scan is underway
+    db.set_progress_handler(lambda: 1, 500)
+    with pytest.raises(sqlite3.OperationalError, match="^interrupted$"):
+        db.execute("select rowid from v where a match '[0, 0]' and k = 3").fetchall()
+    db.set_progress_handler(None, 0)
+    rows = db.execute("select rowid from v where a match '[0, 0]' and k = 3").fetchall()
+    assert [row[0] for row in rows] == [1, 2, 3]
+
+
 def exec(db, sql, parameters=[]):
     try:
         rows = db.execute(sql, parameters).fetchall()
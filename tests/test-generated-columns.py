@@ -0,0 +1,107 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect():
+    db = sqlite3.connect(":memory:")
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+def test_generated_columns(db):
+    db.execute(
+        "create virtual table v using vec0("
+        "norm float generated always as (sqrt(-vec_distance_dot(embedding, embedding))), "
+        "embedding float[2], "
+        "q float[2] quantize=int8, "
+        "dims integer generated as vec_length(embedding), "
+        "gap float generated as vec_distance_l2(embedding, q), "
+        "label text, chunk_size=8)"
+    )
+    db.execute(
+        "insert into v(rowid, embedding, q, label) values (1, '[3, 4]', '[3, 4]', 'a')"
+    )
+    db.execute(
+        "insert into v(rowid, embedding, q, label) values (2, '[6, 8]', '[6, 8]', 'b')"
+    )
+    assert rows(db, "select rowid, norm, dims, gap < 0.1 from v order by rowid") == [
+        (1, 5.0, 2, 1),
+        (2, 10.0, 2, 1),
+    ]
+    assert rows(
+        db, "select rowid from v where embedding match '[0, 0]' and k = 2 and norm > 6"
+    ) == [(2,)]
+
+    # computed again from the new vector, the unchanged one read back from
+    # its chunk
+    db.execute("update v set embedding = '[0, 1]' where rowid = 1")
+    assert rows(db, "select norm, dims, round(gap, 1) from v where rowid = 1") == [
+        (1.0, 2, 4.2)
+    ]
+    # and left alone when no vector changes
+    db.execute("update v set label = 'c' where rowid = 2")
+    assert rows(db, "select norm, label from v where rowid = 2") == [(10.0, "c")]
+
+
+def test_generated_columns_changeset():
+    source = connect()
+    target = connect()
+    for db in [source, target]:
+        db.execute(
+            "create virtual table v using vec0(a float[2], "
+            "n float generated as -vec_distance_dot(a, a), +note text, changelog=on)"
+        )
+    source.execute("insert into v(rowid, a, note) values (1, '[1, 2]', 'x')")
+    source.execute("update v set a = '[2, 2]' where rowid = 1")
+    (changeset,) = source.execute("select vec0_changeset('v')").fetchone()
+    target.execute("select vec0_apply_changeset('v', ?)", [changeset])
+    assert rows(target, "select rowid, n, note from v") == [(1, 8.0, "x")]
+
+
+def test_generated_columns_errors(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], n float generated as vec_length(a))"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "n" column is generated, so it can\'t be inserted',
+    ):
+        db.execute("insert into v(rowid, a, n) values (1, '[1, 1]', 2.0)")
+    db.execute("insert into v(rowid, a) values (1, '[1, 1]')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "n" column is generated, so it can\'t be updated',
+    ):
+        db.execute("update v set n = 3.0 where rowid = 1")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="add_column can't add generated metadata columns",
+    ):
+        db.execute("insert into v(v) values ('add_column=m float generated as 1.0')")
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: invalid expression for generated metadata "
+        "column n: no such column: title",
+    ):
+        db.execute(
+            "create virtual table u using vec0(a float[2], "
+            "n float generated as length(title), +title text)"
+        )
+    with pytest.raises(
+        sqlite3.OperationalError, match="expected `generated as <expression>`"
+    ):
+        db.execute("create virtual table u using vec0(a float[2], n float generated as)")
+
+    db.execute(
+        "create virtual table b using vec0(a float[2], big boolean generated as vec_length(a))"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError, match="Expected 0 or 1 for BOOLEAN metadata column big"
+    ):
+        db.execute("insert into b(rowid, a) values (1, '[1, 1]')")
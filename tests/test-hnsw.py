@@ -0,0 +1,284 @@
+import sqlite3
+import random
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def recall(db, table, queries, k=10, constraints=""):
+    """Fraction of the exact top-k (from the unindexed `exact` column) that the
+    HNSW column also returns."""
+    hits = 0
+    for q in queries:
+        approx = db.execute(
+            f"select rowid from {table} where embedding match ? and k = ? {constraints}",
+            [q, k],
+        ).fetchall()
+        exact = db.execute(
+            f"select rowid from {table} where exact match ? and k = ?", [q, k]
+        ).fetchall()
+        hits += len(set(r[0] for r in approx) & set(r[0] for r in exact))
+    return hits / (len(queries) * k)
+
+
+def fill(db, n, dimensions, seed=0):
+    rng = random.Random(seed)
+    db.execute("begin")
+    for i in range(1, n + 1):
+        v = _f32([rng.random() for _ in range(dimensions)])
+        db.execute("insert into v(rowid, embedding, exact) values (?, ?, ?)", [i, v, v])
+    db.execute("commit")
+    return [_f32([rng.random() for _ in range(dimensions)]) for _ in range(20)]
+
+
+def test_hnsw_shadow_tables(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], b float[2] index=hnsw(m=4, ef_construction=20))"
+    )
+    names = [
+        row[0]
+        for row in db.execute(
+            "select name from sqlite_master where name like 'v_%' order by 1"
+        )
+    ]
+    assert "v_hnsw01" in names
+    assert "v_hnsw00" not in names
+
+    db.execute("insert into v(rowid, a, b) values (1, '[1, 1]', '[1, 1]')")
+    assert [row[0] for row in db.execute("select rowid from v_hnsw01")] == [1]
+    assert db.execute(
+        "select value from v_info where key = 'hnsw01_entrypoint'"
+    ).fetchone()[0] == 1
+
+    db.execute("alter table v rename to v2")
+    assert db.execute("select count(*) from v2_hnsw01").fetchone()[0] == 1
+
+    db.execute("drop table v2")
+    assert (
+        db.execute("select count(*) from sqlite_master where name like 'v2%'").fetchone()[0]
+        == 0
+    )
+
+
+def test_hnsw_constructor_errors(db):
+    for option in [
+        "index=hnsw(m=1)",
+        "index=hnsw(m=1000)",
+        "index=hnsw(ef_construction=0)",
+        "index=hnsw(unknown=4)",
+        "index=hnsw(m=4 ef_search=10)",
+        "index=hnsw(m=)",
+        "index=annoy",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0(a float[2] {option})")
+
+
+def test_hnsw_matches_exact_distances(db):
+    for metric in ["l2", "cosine", "l1"]:
+        check_matches_exact_distances(db, metric)
+
+
+def check_matches_exact_distances(db, metric):
+    db.execute("drop table if exists v")
+    db.execute(
+        f"""
+        create virtual table v using vec0(
+          embedding float[2] distance_metric={metric} index=hnsw,
+          exact float[2] distance_metric={metric}
+        )
+        """
+    )
+    for i, vector in enumerate([[1, 2], [3, 4], [-1, 0.5], [0.2, -3]]):
+        db.execute(
+            "insert into v(rowid, embedding, exact) values (?, ?, ?)",
+            [i + 1, _f32(vector), _f32(vector)],
+        )
+    q = _f32([0.5, 0.5])
+    approx = rows(db, "select rowid, distance from v where embedding match ? and k = 4", [q])
+    exact = rows(db, "select rowid, distance from v where exact match ? and k = 4", [q])
+    assert approx == exact
+
+
+def test_hnsw_recall(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[16] index=hnsw(m=8, ef_construction=64),
+          exact float[16]
+        )
+        """
+    )
+    queries = fill(db, 2000, 16)
+    assert recall(db, "v", queries) >= 0.8
+
+    # larger query-time candidate lists should only help
+    db.execute("insert into v(v) values ('ef_search=256')")
+    assert recall(db, "v", queries) >= 0.95
+
+    with pytest.raises(sqlite3.OperationalError, match="ef_search must be"):
+        db.execute("insert into v(v) values ('ef_search=0')")
+
+
+def test_hnsw_query_ef_search(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[16] index=hnsw(m=4, ef_construction=16, ef_search=10, seed=7),
+          exact float[16]
+        )
+        """
+    )
+    queries = fill(db, 2000, 16)
+    low = recall(db, "v", queries)
+    high = recall(db, "v", queries, constraints="and ef_search = 256")
+    assert high >= 0.95 and high > low
+    db.execute(
+        "select rowid from v where embedding match ? and k = 10 and ef_search = 256", [queries[0]]
+    ).fetchall()
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("knn on v.embedding via hnsw index",)
+    ]
+    # the override only lasts for one query
+    assert recall(db, "v", queries) == low
+    assert recall(db, "v", queries, constraints="and ef_search = 10") == low
+
+    q = queries[0]
+    for value in [0, 4097, 1.5, "64"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="ef_search value in knn query must be an integer between 1 and 4096",
+        ):
+            db.execute(
+                "select rowid from v where embedding match ? and k = 1 and ef_search = ?",
+                [q, value],
+            )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='ef_search in knn query needs an index=hnsw column, "exact" isn\'t one',
+    ):
+        db.execute(
+            "select rowid from v where exact match ? and k = 1 and ef_search = 64", [q]
+        )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='A value was provided for the hidden "ef_search" column.',
+    ):
+        db.execute(
+            "insert into v(rowid, embedding, exact, ef_search) values (1, ?, ?, 64)",
+            [q, q],
+        )
+
+    # a candidate list longer than the table finds the exact neighbors
+    db.commit()
+    db.execute("drop table v")
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[16] index=hnsw(m=4, ef_construction=16, ef_search=10),
+          exact float[16]
+        )
+        """
+    )
+    for q in fill(db, 60, 16):
+        assert rows(
+            db,
+            "select rowid, distance from v where embedding match ? and k = 10 "
+            "and ef_search = 256",
+            [q],
+        ) == rows(db, "select rowid, distance from v where exact match ? and k = 10", [q])
+
+
+def test_hnsw_seed(db):
+    for table in ["v", "w"]:
+        db.execute(
+            f"create virtual table {table} using vec0("
+            "embedding float[8] index=hnsw(m=4, ef_construction=16, seed=3))"
+        )
+        rng = random.Random(0)
+        for i in range(1, 201):
+            db.execute(
+                f"insert into {table}(rowid, embedding) values (?, ?)",
+                [i, _f32([rng.random() for _ in range(8)])],
+            )
+    # the same seed and inserts build the same graph
+    graph = rows(db, "select * from v_hnsw00")
+    assert graph == rows(db, "select * from w_hnsw00")
+    assert len(graph) == 200
+
+    with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+        db.execute(
+            "create virtual table x using vec0(embedding float[8] index=hnsw(seed=-1))"
+        )
+
+
+def test_hnsw_delete_and_update(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[8] index=hnsw(m=6, ef_construction=40),
+          exact float[8]
+        )
+        """
+    )
+    queries = fill(db, 500, 8, seed=1)
+    db.execute("delete from v where rowid % 2 = 0")
+    assert db.execute("select count(*) from v_hnsw00").fetchone()[0] == 250
+    # no deleted rows leak into results
+    for q in queries:
+        for row in db.execute(
+            "select rowid from v where embedding match ? and k = 20", [q]
+        ):
+            assert row[0] % 2 == 1
+    assert recall(db, "v", queries) >= 0.8
+
+    zero = _f32([0.0] * 8)
+    db.execute("update v set embedding = ?, exact = ? where rowid = 3", [zero, zero])
+    assert rows(
+        db, "select rowid, distance from v where embedding match ? and k = 1", [zero]
+    ) == [(3, 0.0)]
+
+    db.execute("delete from v")
+    assert db.execute("select count(*) from v_hnsw00").fetchone()[0] == 0
+    assert (
+        db.execute("select count(*) from v_info where key = 'hnsw00_entrypoint'").fetchone()[0]
+        == 0
+    )
+    assert rows(db, "select rowid from v where embedding match ? and k = 5", [zero]) == []
+
+
+def test_hnsw_filters_fall_back_to_exact(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          user_id integer partition key,
+          embedding float[2] index=hnsw,
+          genre text
+        )
+        """
+    )
+    db.executemany(
+        "insert into v(rowid, user_id, embedding, genre) values (?, ?, ?, ?)",
+        [
+            [1, 1, "[1, 1]", "a"],
+            [2, 2, "[1, 1.1]", "b"],
+            [3, 1, "[5, 5]", "b"],
+        ],
+    )
+    q = "[1, 1]"
+    assert rows(
+        db, "select rowid from v where embedding match ? and k = 3 and user_id = 1", [q]
+    ) == [(1,), (3,)]
+    assert rows(
+        db, "select rowid from v where embedding match ? and k = 3 and genre = 'b'", [q]
+    ) == [(2,), (3,)]
+    assert rows(
+        db, "select rowid from v where embedding match ? and k = 3 and rowid in (2, 3)", [q]
+    ) == [(2,), (3,)]
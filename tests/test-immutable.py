@@ -0,0 +1,130 @@
+import sqlite3
+import pytest
+
+
+def connect(path, uri=False):
+    db = sqlite3.connect(path, isolation_level=None, uri=uri)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    db.enable_load_extension(False)
+    return db
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+KNN = "select rowid from v where a match '[3.1, 3.1]' and k = 3"
+
+
+def test_immutable_refuses_writes(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], +label text, chunk_size=8)"
+    )
+    db.execute(
+        "insert into v(rowid, a, label) "
+        "select value, json_array(value, value), 'row ' || value "
+        "from json_each('[1, 2, 3, 4, 5]')"
+    )
+    db.execute("insert into v(v) values ('immutable=1')")
+
+    immutable = "v is immutable, 'immutable=0' allows writes"
+    for sql in [
+        "insert into v(rowid, a) values (6, '[6, 6]')",
+        "update v set label = 'x' where rowid = 1",
+        "delete from v where rowid = 1",
+        "insert into v(v) values ('optimize')",
+        "insert into v(v) values ('rechunk=16')",
+        "alter table v rename to w",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=immutable):
+            db.execute(sql)
+    with pytest.raises(sqlite3.OperationalError, match="could not repair v: " + immutable):
+        db.execute("select * from vec0_repair('v')").fetchall()
+    with pytest.raises(sqlite3.OperationalError):
+        db.execute("drop table v")
+
+    # queries and connection settings still work
+    db.execute("insert into v(v) values ('flush')")
+    db.execute("insert into v(v) values ('ef_search=10')")
+    assert rows(db, KNN) == [(3,), (4,), (2,)]
+    assert rows(db, "select label from v where rowid = 5") == [("row 5",)]
+
+    db.execute("insert into v(v) values ('immutable=0')")
+    db.execute("insert into v(rowid, a) values (6, '[3, 3]')")
+    assert rows(db, KNN) == [(3,), (6,), (4,)]
+    db.execute("drop table v")
+
+    with pytest.raises(sqlite3.OperationalError, match="immutable must be 0 or 1"):
+        db.execute("create virtual table v using vec0(a float[2], immutable=yes)")
+    db.execute("create virtual table v using vec0(a float[2], immutable=1)")
+    with pytest.raises(sqlite3.OperationalError, match=immutable):
+        db.execute("insert into v(rowid, a) values (1, '[1, 1]')")
+    with pytest.raises(sqlite3.OperationalError, match="immutable must be 0 or 1"):
+        db.execute("insert into v(v) values ('immutable=2')")
+
+
+def test_immutable_across_connections(tmp_path):
+    path = str(tmp_path / "immutable.db")
+    writer = connect(path)
+    writer.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    writer.execute(
+        "insert into v(rowid, a) select value, json_array(value, value) "
+        "from json_each('[1, 2, 3, 4, 5]')"
+    )
+    reader = connect(path)
+    assert rows(reader, KNN) == [(3,), (4,), (2,)]
+
+    writer.execute("insert into v(v) values ('immutable=1')")
+    with pytest.raises(sqlite3.OperationalError, match="v is immutable"):
+        reader.execute("insert into v(rowid, a) values (6, '[3, 3]')")
+
+    # rolled back, the table stays immutable
+    reader.execute("begin")
+    reader.execute("insert into v(v) values ('immutable=0')")
+    reader.execute("rollback")
+    with pytest.raises(sqlite3.OperationalError, match="v is immutable"):
+        writer.execute("insert into v(rowid, a) values (6, '[3, 3]')")
+
+    reader.execute("insert into v(v) values ('immutable=0')")
+    writer.execute("insert into v(rowid, a) values (6, '[3, 3]')")
+    assert rows(reader, KNN) == [(3,), (6,), (4,)]
+
+    # 'immutable=1' is kept in the database, and works on read-only ones
+    writer.execute("insert into v(v) values ('immutable=1')")
+    writer.close()
+    reader.close()
+    ro = connect(f"file:{path}?mode=ro", uri=True)
+    assert rows(ro, KNN) == [(3,), (6,), (4,)]
+    assert rows(ro, "select count(*) from v") == [(6,)]
+
+
+def test_immutable_chunk_cache(tmp_path):
+    path = str(tmp_path / "immutable-cache.db")
+    db = connect(path)
+    db.execute("select vec_cache_size(1024)")
+    last_plan = lambda: db.execute("select vec_debug_last_plan()").fetchone()[0]
+    db.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    db.execute("create table other(x)")
+    db.execute(
+        "insert into v(rowid, a) select value, json_array(value, value) "
+        "from json_each('[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]')"
+    )
+    db.execute("insert into v(v) values ('immutable=1')")
+    assert rows(db, KNN) == [(3,), (4,), (2,)]
+    assert last_plan() == "knn on v.a via chunk scan, 0 of 2 chunks cached"
+
+    # cached chunks of immutable tables survive writes to other tables, and
+    # are used in explicit transactions
+    other = connect(path)
+    other.execute("insert into other values (1)")
+    db.execute("begin")
+    assert rows(db, KNN) == [(3,), (4,), (2,)]
+    assert last_plan() == "knn on v.a via chunk scan, 2 of 2 chunks cached"
+    db.execute("commit")
+
+    # until the table can be written again
+    other.execute("insert into v(v) values ('immutable=0')")
+    other.execute("delete from v where rowid = 3")
+    assert rows(db, KNN) == [(4,), (2,), (5,)]
+    assert last_plan() == "knn on v.a via chunk scan, 0 of 2 chunks cached"
@@ -0,0 +1,81 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def _i64(list):
+    return struct.pack("%sq" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_batch_insert(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], b int8[2] index=hnsw, chunk_size=8)"
+    )
+    n = 20
+    db.execute(
+        "insert into v(v, rowid, a, b) values ('batch', ?, ?, ?)",
+        [
+            _i64([i * 10 for i in range(1, n + 1)]),
+            b"".join(_f32([i, -i]) for i in range(1, n + 1)),
+            bytes(x for i in range(1, n + 1) for x in (i, 0)),
+        ],
+    )
+    assert db.execute("select count(*) from v").fetchone()[0] == n
+    assert db.execute("select count(*) from v_chunks").fetchone()[0] == 3
+    assert rows(db, "select rowid, vec_to_json(a), vec_to_json(vec_int8(b)) from v where rowid = 70") == [
+        (70, "[7.000000,-7.000000]", "[7,0]")
+    ]
+    assert rows(db, "select rowid from v where a match ? and k = 2", [_f32([3.1, -3.1])]) == [
+        (30,),
+        (40,),
+    ]
+    # ANN indexes are kept up to date
+    assert db.execute("select count(*) from v_hnsw01").fetchone()[0] == n
+
+    # without rowids, rowids are auto-assigned like regular INSERTs
+    db.execute("delete from v where rowid <= 100")
+    db.execute(
+        "insert into v(v, a, b) values ('batch', ?, ?)",
+        [_f32([0, 0, 1, 1]), bytes([0, 0, 1, 1])],
+    )
+    assert rows(db, "select rowid from v where rowid > 200 order by 1") == [(201,), (202,)]
+    assert db.execute("select count(*) from v").fetchone()[0] == 12
+
+    # the regular insert path still finds free slots afterwards
+    db.execute("insert into v(rowid, a, b) values (1, '[1, 1]', vec_int8('[1, 1]'))")
+    assert db.execute("select count(*) from v").fetchone()[0] == 13
+
+
+def test_batch_insert_errors(db):
+    db.execute("create virtual table v using vec0(a float[2], b float[1])")
+    db.execute("insert into v(rowid, a, b) values (1, '[1, 1]', '[1]')")
+
+    with pytest.raises(sqlite3.OperationalError, match="must be a BLOB of concatenated 8-byte vectors"):
+        db.execute("insert into v(v, a, b) values ('batch', '[1, 1]', ?)", [_f32([1])])
+    with pytest.raises(sqlite3.OperationalError, match="different row counts"):
+        db.execute(
+            "insert into v(v, a, b) values ('batch', ?, ?)", [_f32([1, 1]), _f32([1, 2])]
+        )
+    with pytest.raises(sqlite3.OperationalError, match="rowids must be a BLOB of 1 64-bit"):
+        db.execute(
+            "insert into v(v, rowid, a, b) values ('batch', 2, ?, ?)", [_f32([1, 1]), _f32([1])]
+        )
+    # a duplicate rowid rejects the whole batch
+    with pytest.raises(sqlite3.OperationalError, match="UNIQUE constraint failed on v primary key"):
+        db.execute(
+            "insert into v(v, rowid, a, b) values ('batch', ?, ?, ?)",
+            [_i64([2, 1]), _f32([2, 2, 3, 3]), _f32([2, 3])],
+        )
+    assert rows(db, "select rowid from v") == [(1,)]
+
+    db.execute("create virtual table m using vec0(a float[2], genre text)")
+    with pytest.raises(sqlite3.OperationalError, match="batch inserts are only supported"):
+        db.execute("insert into m(m, a) values ('batch', ?)", [_f32([1, 1])])
@@ -0,0 +1,124 @@
+import math
+import random
+import struct
+import pytest
+
+
+def _i8(list):
+    return struct.pack("%sb" % len(list), *list)
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def _i64(list):
+    return struct.pack("%sq" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_int8_distances(db):
+    # lengths on both sides of every SIMD width, so the tails get covered too
+    rng = random.Random(0)
+    for d in list(range(1, 70)) + [127, 128, 129, 255, 256, 1000]:
+        for extremes in [False, True]:
+            if extremes:
+                x = [rng.choice([-128, 127]) for _ in range(d)]
+                y = [rng.choice([-128, 127]) for _ in range(d)]
+            else:
+                x = [rng.randint(-128, 127) for _ in range(d)]
+                y = [rng.randint(-128, 127) for _ in range(d)]
+            l2, dot, cosine = db.execute(
+                "select vec_distance_l2(vec_int8(:a), vec_int8(:b)), "
+                "vec_distance_dot(vec_int8(:a), vec_int8(:b)), "
+                "vec_distance_cosine(vec_int8(:a), vec_int8(:b))",
+                {"a": _i8(x), "b": _i8(y)},
+            ).fetchone()
+            ab = sum(i * j for i, j in zip(x, y))
+            aa = sum(i * i for i in x)
+            bb = sum(j * j for j in y)
+            assert dot == -ab, d
+            assert l2 == pytest.approx(
+                math.sqrt(sum((i - j) ** 2 for i, j in zip(x, y))), rel=1e-6
+            ), d
+            if aa and bb:
+                assert cosine == pytest.approx(
+                    1 - ab / math.sqrt(aa * bb), abs=1e-6
+                ), d
+
+    (cosine,) = db.execute(
+        "select vec_distance_cosine(vec_int8(?), vec_int8(?))",
+        [_i8([-128] * 40), _i8([127] * 40)],
+    ).fetchone()
+    assert cosine == pytest.approx(2.0)
+
+
+def test_int8_vec0_knn(db):
+    db.execute(
+        "create virtual table v using vec0("
+        "l2 int8[35], dot int8[35] distance_metric=dot, "
+        "cosine int8[35] distance_metric=cosine)"
+    )
+    rng = random.Random(1)
+    vectors = [[rng.randint(-128, 127) for _ in range(35)] for _ in range(50)]
+    for i, v in enumerate(vectors):
+        db.execute(
+            "insert into v(rowid, l2, dot, cosine) "
+            "values (:rowid, vec_int8(:v), vec_int8(:v), vec_int8(:v))",
+            {"rowid": i + 1, "v": _i8(v)},
+        )
+    q = [rng.randint(-128, 127) for _ in range(35)]
+
+    def expected(distance):
+        return sorted(
+            (distance(v), i + 1) for i, v in enumerate(vectors)
+        )[:5]
+
+    ab = lambda v: sum(i * j for i, j in zip(q, v))
+    assert [r[0] for r in rows(
+        db, "select rowid from v where dot match vec_int8(?) and k = 5", [_i8(q)]
+    )] == [i for _, i in expected(lambda v: -ab(v))]
+    assert [r[0] for r in rows(
+        db, "select rowid from v where l2 match vec_int8(?) and k = 5", [_i8(q)]
+    )] == [i for _, i in expected(lambda v: sum((i - j) ** 2 for i, j in zip(q, v)))]
+    assert [r[0] for r in rows(
+        db, "select rowid from v where cosine match vec_int8(?) and k = 5", [_i8(q)]
+    )] == [
+        i
+        for _, i in expected(
+            lambda v: 1 - ab(v) / math.sqrt(sum(i * i for i in q) * sum(j * j for j in v))
+        )
+    ]
+
+
+def test_quantize_int8_dot_cosine(db):
+    # dot and cosine of quantize=int8 columns come from the integer sums of
+    # the codes, they should match the dequantized vectors
+    db.execute(
+        "create virtual table v using vec0("
+        "dot float[40] distance_metric=dot quantize=int8, "
+        "cosine float[40] distance_metric=cosine quantize=int8)"
+    )
+    rng = random.Random(2)
+    vectors = b"".join(_f32([rng.uniform(-1, 3) for _ in range(40)]) for _ in range(21))
+    db.execute(
+        "insert into v(v, rowid, dot, cosine) values ('batch', ?, ?, ?)",
+        [_i64(range(1, 22)), vectors, vectors],
+    )
+    for column in ["dot", "cosine"]:
+        (q,) = db.execute(f"select {column} from v where rowid = 21").fetchone()
+        result = rows(
+            db,
+            f"select rowid, distance from v where {column} match ? and k = 21",
+            [q],
+        )
+        assert len(result) == 21
+        for rowid, distance in result:
+            (exact,) = db.execute(
+                f"select vec_distance_{column}({column}, ?) from v where rowid = ?",
+                [q, rowid],
+            ).fetchone()
+            assert distance == pytest.approx(exact, rel=1e-5, abs=1e-5), column
@@ -0,0 +1,216 @@
+import random
+import struct
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def check(db, table="v"):
+    return rows(db, "select * from vec0_integrity_check(?)", [table])
+
+
+def fill(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          id text primary key,
+          a float[4] index=hnsw(m=4),
+          b float[4] index=ivf(nlist=2),
+          shard integer partition key,
+          tag text,
+          +note text,
+          chunk_size=8
+        )
+        """
+    )
+    rng = random.Random(7)
+    for i in range(1, 41):
+        vector = _f32([rng.uniform(-1, 1) for _ in range(4)])
+        db.execute(
+            "insert into v(id, a, b, shard, tag, note) values (?, ?, ?, ?, ?, ?)",
+            [f"doc{i}", vector, vector, i % 2, f"tag{i}", f"note {i}"],
+        )
+    db.execute("insert into v(v) values ('train')")
+
+
+def test_integrity_check_healthy_tables(db):
+    fill(db)
+    assert check(db) == []
+    db.execute("delete from v where id in ('doc3', 'doc10', 'doc11')")
+    assert check(db) == []
+    db.execute("insert into v(v) values ('optimize')")
+    assert check(db) == []
+    db.execute("insert into v(v) values ('rechunk=16')")
+    assert check(db) == []
+    db.execute("delete from v")
+    assert check(db) == []
+
+
+def test_integrity_check_chunks(db):
+    fill(db)
+    db.execute("update v_chunks set validity = x'FE' where chunk_id = 1")
+    db.execute("update v_chunks set rowids = x'00' where chunk_id = 2")
+    db.execute("delete from v_vector_chunks00 where rowid = 3")
+    db.execute("insert into v_metadatachunks00 values (99, zeroblob(64))")
+    assert check(db) == [
+        ("v_rowids", 1, "rowid 1 points to slot 0 of chunk 1, which isn't marked valid"),
+        (
+            "v_chunks",
+            2,
+            "chunk 2 has a 1 byte validity bitmap and a 1 byte rowids blob, instead of 1 and 64 bytes",
+        ),
+        ("v_vector_chunks00", 3, "chunk 3 has no row"),
+        ("v_metadatachunks00", 99, "row 99 has no chunk"),
+    ]
+
+
+def test_integrity_check_rows(db):
+    fill(db)
+    db.execute("delete from v_rowids where rowid = 5")
+    db.execute("delete from v_metadatatext00 where rowid = 6")
+    db.execute("insert into v_metadatatext00 values (100, 'lost')")
+    problems = check(db)
+    assert ("v_chunks", 1, "slot 2 of chunk 1 is marked valid for rowid 5, which has no _rowids row") in problems
+    assert ("v_metadatatext00", 100, "rowid 100 has no _rowids row") in problems
+    assert ("v_auxiliary", 5, "rowid 5 has no _rowids row") in problems
+    assert ("v_hnsw00", 5, "node 5 has no _rowids row") in problems
+    list = rows(db, "select centroid_id from v_ivflists01 where rowid = 5")[0][0]
+    assert ("v_ivflists01", 5, f"rowid 5 is in list {list} but has no _rowids row") in problems
+
+
+def test_integrity_check_hnsw(db):
+    fill(db)
+    db.execute("update v_hnsw00 set level = 20 where rowid = 1")
+    db.execute("update v_hnsw00 set vector = x'00' where rowid = 2")
+    # the first level's link count is the first 8 bytes of the neighbors blob
+    db.execute(
+        "update v_hnsw00 set neighbors = x'6400000000000000' || substr(neighbors, 9) where rowid = 3"
+    )
+    db.execute("delete from v_hnsw00 where rowid = 4")
+    links = rows(db, "select length(neighbors) from v_hnsw00 where rowid = 2")[0][0]
+    problems = check(db)
+    assert problems[:3] == [
+        ("v_hnsw00", 1, "node 1 has level 20, outside of 0 to 16"),
+        (
+            "v_hnsw00",
+            2,
+            f"node 2 has a {links} byte neighbors blob and a 1 byte vector, instead of {links} and 16 bytes",
+        ),
+        ("v_hnsw00", 3, "node 3 has 100 links on level 0, which has 8 slots"),
+    ]
+    assert ("v_hnsw00", 4, "rowid 4 has no node") in problems
+
+    entrypoint = rows(db, "select value from v_info where key = 'hnsw00_entrypoint'")[0][0]
+    db.execute("delete from v_hnsw00 where rowid = ?", [entrypoint])
+    assert (
+        "v_hnsw00",
+        None,
+        f"the entry point {entrypoint} has no node",
+    ) in check(db)
+    db.execute("delete from v_info where key = 'hnsw00_entrypoint'")
+    assert ("v_hnsw00", None, "the graph has nodes but no entry point") in check(db)
+
+
+def test_integrity_check_ivf(db):
+    fill(db)
+    db.execute("update v_ivfcentroids01 set centroid_id = 5 where centroid_id = 1")
+    db.execute("update v_ivflists01 set vector = x'00' where rowid = 1")
+    db.execute(
+        "insert into v_ivflists01 select 1 - centroid_id, rowid, vector from v_ivflists01 where rowid = 2"
+    )
+    db.execute("delete from v_ivflists01 where rowid = 3")
+    lists = dict(rows(db, "select rowid, min(centroid_id) from v_ivflists01 group by rowid"))
+    problems = check(db)
+    assert ("v_ivfcentroids01", 5, "centroid 5 is outside of 0 to 1") in problems
+    assert (
+        "v_ivflists01",
+        1,
+        f"rowid 1 has a 1 byte vector in list {lists[1]}, instead of 16 bytes",
+    ) in problems
+    assert ("v_ivflists01", 2, "rowid 2 is in 2 lists") in problems
+    assert ("v_ivflists01", 3, "rowid 3 isn't in any list") in problems
+    orphans = [
+        p[1] for p in problems if p[2].endswith("is in list 1, whose centroid doesn't exist")
+    ]
+    assert sorted(orphans) == [
+        rowid for (rowid,) in rows(db, "select rowid from v_ivflists01 where centroid_id = 1 order by rowid")
+    ]
+
+
+def test_integrity_check_untrained_ivf(db):
+    db.execute("create virtual table v using vec0(a float[2] index=ivf(nlist=4))")
+    for i in range(1, 11):
+        db.execute("insert into v(rowid, a) values (?, ?)", [i, _f32([i, i])])
+    assert check(db) == []
+
+
+def repair(db, table="v"):
+    return rows(db, "select * from vec0_repair(?)", [table])
+
+
+def test_repair(db):
+    fill(db)
+    query = _f32([0.5] * 4)
+    knn = lambda column: rows(
+        db, f"select id, distance from v where {column} match ? and k = 10", [query]
+    )
+    expected = {column: knn(column) for column in ["a", "b"]}
+    assert repair(db) == []
+
+    db.execute("update v_chunks set validity = x'00' where chunk_id = 2")
+    db.execute("update v_chunks set rowids = x'00' where chunk_id = 3")
+    db.execute("update v_rowids set chunk_offset = 7 where rowid = 7")
+    db.execute("delete from v_auxiliary where rowid = 9")
+    db.execute("delete from v_hnsw00 where rowid = 12")
+    db.execute("update v_hnsw00 set level = 99 where rowid = 14")
+    db.execute("delete from v_ivflists01 where rowid = 13")
+    db.execute("delete from v_info where key = 'hnsw00_entrypoint'")
+    assert check(db) != []
+
+    repairs = repair(db)
+    assert ("v_chunks", 3, "rebuilt the validity bitmap and rowids of chunk 3 from _rowids") in repairs
+    assert ("v_chunks", 2, "marked slot 0 of chunk 2 valid for rowid 2") in repairs
+    assert ("v_rowids", 7, "pointed rowid 7 to slot 3 of chunk 1, which holds it") in repairs
+    assert ("v_auxiliary", 9, "added rowid 9, with NULL values") in repairs
+    assert ("v_hnsw00", 14, "deleted node 14: node 14 has level 99, outside of 0 to 16") in repairs
+    assert ("v_hnsw00", 12, "added rowid 12") in repairs
+    assert ("v_hnsw00", 14, "added rowid 14") in repairs
+    assert ("v_ivflists01", 13, "added rowid 13") in repairs
+    assert len([r for r in repairs if r[2].startswith("moved the entry point")]) == 1
+    assert check(db) == []
+    assert repair(db) == []
+    assert rows(db, "select count(*) from v") == [(40,)]
+    assert knn("b") == expected["b"]
+    assert len(knn("a")) == 10
+
+
+def test_repair_lost_rows(db):
+    fill(db)
+    # rowid 5 points nowhere and its slot is gone, so are its vectors
+    db.execute("update v_rowids set chunk_id = 99 where rowid = 5")
+    db.execute("update v_chunks set validity = x'FB' where chunk_id = 1")
+    db.execute("delete from v_rowids where rowid = 6")
+    repairs = repair(db)
+    assert ("v_rowids", 5, "deleted rowid 5, which isn't in any chunk") in repairs
+    assert ("v_auxiliary", 5, "deleted rowid 5, which has no _rowids row") in repairs
+    assert ("v_hnsw00", 5, "deleted node 5: node 5 has no _rowids row") in repairs
+    # only _rowids had the TEXT primary key of rowid 6
+    assert ("v_chunks", 2, "cleared slot 2 of chunk 2, whose rowid 6 has no _rowids row") in repairs
+    assert check(db) == []
+    assert rows(db, "select count(*) from v where id in ('doc5', 'doc6')") == [(0,)]
+    assert rows(db, "select count(*) from v") == [(38,)]
+
+
+def test_repair_transactions(db):
+    fill(db)
+    db.execute("delete from v_auxiliary where rowid = 9")
+    db.commit()
+    db.execute("begin")
+    assert repair(db) == [("v_auxiliary", 9, "added rowid 9, with NULL values")]
+    db.execute("rollback")
+    assert check(db) == [("v_auxiliary", 9, "rowid 9 has no _auxiliary row")]
@@ -0,0 +1,219 @@
+import sqlite3
+import random
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def recall(db, queries, k=10, constraints=""):
+    """Fraction of the exact top-k (from the unindexed `exact` column) that the
+    IVF column also returns."""
+    hits = 0
+    for q in queries:
+        approx = db.execute(
+            f"select rowid from v where embedding match ? and k = ? {constraints}",
+            [q, k],
+        ).fetchall()
+        exact = db.execute(
+            "select rowid from v where exact match ? and k = ?", [q, k]
+        ).fetchall()
+        hits += len(set(r[0] for r in approx) & set(r[0] for r in exact))
+    return hits / (len(queries) * k)
+
+
+def fill(db, n, dimensions, seed=0, start=1):
+    rng = random.Random(seed)
+    db.execute("begin")
+    for i in range(start, start + n):
+        v = _f32([rng.random() for _ in range(dimensions)])
+        db.execute("insert into v(rowid, embedding, exact) values (?, ?, ?)", [i, v, v])
+    db.execute("commit")
+    return [_f32([rng.random() for _ in range(dimensions)]) for _ in range(20)]
+
+
+def test_ivf_shadow_tables(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], b float[2] index=ivf(nlist=4))"
+    )
+    names = [
+        row[0]
+        for row in db.execute(
+            "select name from sqlite_master where name like 'v_%' order by 1"
+        )
+    ]
+    assert "v_ivfcentroids01" in names
+    assert "v_ivflists01" in names
+    assert "v_ivflists00" not in names
+
+    db.execute("insert into v(rowid, a, b) values (1, '[1, 1]', '[1, 1]')")
+    # untrained, so the row waits in the unassigned list
+    assert rows(db, "select centroid_id, rowid from v_ivflists01") == [(-1, 1)]
+
+    db.execute("alter table v rename to v2")
+    assert db.execute("select count(*) from v2_ivflists01").fetchone()[0] == 1
+    assert db.execute("select count(*) from v2_ivfcentroids01").fetchone()[0] == 0
+
+    db.execute("drop table v2")
+    assert (
+        db.execute("select count(*) from sqlite_master where name like 'v2%'").fetchone()[0]
+        == 0
+    )
+
+
+def test_ivf_constructor_errors(db):
+    for option in [
+        "index=ivf(nlist=0)",
+        "index=ivf(nlist=100000)",
+        "index=ivf(nprobe=0)",
+        "index=ivf(m=4)",
+        "index=ivf(nlist=)",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0(a float[2] {option})")
+    with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+        db.execute("create virtual table v using vec0(a int8[2] index=ivf)")
+
+
+def test_ivf_train_and_recall(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[16] index=ivf(nlist=32, nprobe=4),
+          exact float[16]
+        )
+        """
+    )
+    queries = fill(db, 2000, 16)
+
+    # before training, KNN queries are exact
+    assert recall(db, queries) == 1.0
+
+    db.execute("insert into v(v) values ('train')")
+    assert db.execute("select count(*) from v_ivfcentroids00").fetchone()[0] == 32
+    assert (
+        db.execute("select count(*) from v_ivflists00 where centroid_id = -1").fetchone()[0]
+        == 0
+    )
+    assert db.execute("select count(*) from v_ivflists00").fetchone()[0] == 2000
+    assert recall(db, queries) >= 0.5
+
+    # probing every list is exhaustive
+    db.execute("insert into v(v) values ('nprobe=32')")
+    assert recall(db, queries) == 1.0
+
+    with pytest.raises(sqlite3.OperationalError, match="nprobe must be"):
+        db.execute("insert into v(v) values ('nprobe=0')")
+
+    db.commit()
+
+    # rows inserted after training are assigned to a list right away
+    fill(db, 10, 16, seed=1, start=5000)
+    assert (
+        db.execute("select count(*) from v_ivflists00 where centroid_id = -1").fetchone()[0]
+        == 0
+    )
+    assert db.execute("select count(*) from v_ivflists00").fetchone()[0] == 2010
+
+
+def test_ivf_query_nprobe(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[16] index=ivf(nlist=32, nprobe=1),
+          exact float[16]
+        )
+        """
+    )
+    queries = fill(db, 2000, 16)
+    db.execute("insert into v(v) values ('train')")
+    low = recall(db, queries)
+    assert low < 1.0
+    # probing every list is exhaustive, for this query only
+    assert recall(db, queries, constraints="and nprobe = 32") == 1.0
+    db.execute(
+        "select rowid from v where embedding match ? and k = 10 and nprobe = 32", [queries[0]]
+    ).fetchall()
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("knn on v.embedding via ivf index",)
+    ]
+    assert recall(db, queries) == low
+
+    q = queries[0]
+    for value in [0, 65537, 1.5, "4"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="nprobe value in knn query must be an integer between 1 and 65536",
+        ):
+            db.execute(
+                "select rowid from v where embedding match ? and k = 1 and nprobe = ?",
+                [q, value],
+            )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='nprobe in knn query needs an index=ivf column, "exact" isn\'t one',
+    ):
+        db.execute("select rowid from v where exact match ? and k = 1 and nprobe = 4", [q])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='A value was provided for the hidden "nprobe" column.',
+    ):
+        db.execute(
+            "insert into v(rowid, embedding, exact, nprobe) values (1, ?, ?, 4)", [q, q]
+        )
+
+
+def test_ivf_matches_exact_distances(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[2] distance_metric=cosine index=ivf(nlist=2, nprobe=2),
+          exact float[2] distance_metric=cosine
+        )
+        """
+    )
+    for i, vector in enumerate([[1, 2], [3, 4], [-1, 0.5], [0.2, -3]]):
+        db.execute(
+            "insert into v(rowid, embedding, exact) values (?, ?, ?)",
+            [i + 1, _f32(vector), _f32(vector)],
+        )
+    db.execute("insert into v(v) values ('train')")
+    q = _f32([0.5, 0.5])
+    approx = rows(db, "select rowid, distance from v where embedding match ? and k = 4", [q])
+    exact = rows(db, "select rowid, distance from v where exact match ? and k = 4", [q])
+    assert approx == exact
+
+
+def test_ivf_delete_and_update(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[8] index=ivf(nlist=8, nprobe=8),
+          exact float[8]
+        )
+        """
+    )
+    queries = fill(db, 200, 8, seed=2)
+    db.execute("insert into v(v) values ('train')")
+    db.execute("delete from v where rowid % 2 = 0")
+    assert db.execute("select count(*) from v_ivflists00").fetchone()[0] == 100
+    for q in queries:
+        for row in db.execute("select rowid from v where embedding match ? and k = 20", [q]):
+            assert row[0] % 2 == 1
+
+    zero = _f32([0.0] * 8)
+    db.execute("update v set embedding = ?, exact = ? where rowid = 3", [zero, zero])
+    assert db.execute("select count(*) from v_ivflists00").fetchone()[0] == 100
+    assert rows(
+        db, "select rowid, distance from v where embedding match ? and k = 1", [zero]
+    ) == [(3, 0.0)]
+
+    db.execute("delete from v")
+    assert db.execute("select count(*) from v_ivflists00").fetchone()[0] == 0
+    assert rows(db, "select rowid from v where embedding match ? and k = 5", [zero]) == []
@@ -0,0 +1,75 @@
+import sqlite3
+import pytest
+
+
+def rowids(db, sql, params=[]):
+    return [row[0] for row in db.execute(sql, params).fetchall()]
+
+
+def test_default_k(db):
+    db.execute(
+        "create virtual table v using vec0(a float[1], b float[1] index=hnsw, "
+        "genre text, default_k=3, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, b, genre) values (?, ?, ?, ?)",
+        [(i, f"[{i}]", f"[{i}]", "ab"[i % 2]) for i in range(1, 21)],
+    )
+
+    # queries without a LIMIT or k get default_k rows, from ANN indexes too
+    assert rowids(db, "select rowid from v where a match '[0]'") == [1, 2, 3]
+    assert rowids(db, "select rowid from v where b match '[0]'") == [1, 2, 3]
+    assert rowids(db, "select rowid from v where a match '[0]' and genre = 'a'") == [
+        2,
+        4,
+        6,
+    ]
+    assert rowids(db, "select rowid from v where a match '[0]' and k = 5") == [
+        1,
+        2,
+        3,
+        4,
+        5,
+    ]
+
+    # tables without the option still need one
+    db.execute("create virtual table u using vec0(a float[1])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="A LIMIT or 'k = \\?' constraint is required on vec0 knn queries.",
+    ):
+        db.execute("select rowid from u where a match '[0]'").fetchall()
+
+
+def test_max_k(db):
+    db.execute("create virtual table v using vec0(a float[1], max_k=10)")
+    db.executemany(
+        "insert into v(rowid, a) values (?, ?)", [(i, f"[{i}]") for i in range(1, 21)]
+    )
+    assert len(rowids(db, "select rowid from v where a match '[0]' and k = 10")) == 10
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="k value in knn query too large, provided 11 and the limit is 10",
+    ):
+        db.execute("select rowid from v where a match '[0]' and k = 11").fetchall()
+    if sqlite3.sqlite_version_info >= (3, 41, 0):
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="LIMIT plus OFFSET in knn query too large, provided 12 and the limit is 10",
+        ):
+            db.execute("select rowid from v where a match '[0]' limit 6 offset 6").fetchall()
+
+
+def test_default_k_errors(db):
+    for option in ["default_k", "max_k"]:
+        for value in ["0", "4097", "x"]:
+            with pytest.raises(
+                sqlite3.OperationalError,
+                match=f"vec0 constructor error: {option} must be an integer between 1 and 4096",
+            ):
+                db.execute(f"create virtual table v using vec0(a float[1], {option}={value})")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: default_k=20 can't be larger than max_k=10",
+    ):
+        db.execute("create virtual table v using vec0(a float[1], default_k=20, max_k=10)")
@@ -0,0 +1,93 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_knn_dimensions(db):
+    db.execute(
+        "create virtual table v using vec0(a float[4], b float[4] distance_metric=cosine normalize=true, c bit[16])"
+    )
+    vectors = {
+        1: [1, 0, 9, 9],
+        2: [0, 1, 0, 0],
+        3: [2, 0, 0, 0],
+    }
+    for rowid, vector in vectors.items():
+        db.execute(
+            "insert into v(rowid, a, b, c) values (?, ?, ?, vec_bit(?))",
+            [rowid, _f32(vector), _f32(vector), bytes([rowid, 0xFF])],
+        )
+
+    knn = "select rowid, round(distance, 4) from v where a match ? and k = 3"
+    assert rows(db, knn, ["[1, 0, 0, 0]"]) == [(3, 1.0), (2, 1.4142), (1, 12.7279)]
+
+    # only the first 2 dimensions are compared, the query can be full length
+    # or already truncated
+    truncated = knn + " and dimensions = 2"
+    assert rows(db, truncated, ["[1, 0, 0, 0]"]) == [(1, 0.0), (3, 1.0), (2, 1.4142)]
+    assert rows(db, truncated, ["[1, 0]"]) == [(1, 0.0), (3, 1.0), (2, 1.4142)]
+    assert rows(db, knn + " and dimensions = 4", ["[1, 0, 0, 0]"]) == rows(
+        db, knn, ["[1, 0, 0, 0]"]
+    )
+    assert rows(db, "select vec_debug_last_plan()") == [("knn on v.a via chunk scan",)]
+
+    # prefixes of normalized vectors aren't unit length anymore
+    assert rows(
+        db,
+        "select rowid, round(distance, 4) from v where b match ? and k = 3 and dimensions = 2",
+        ["[3, 0]"],
+    ) == [(3, 0.0), (1, 0.0), (2, 1.0)]
+
+    assert rows(
+        db,
+        "select rowid, distance from v where c match vec_bit(?) and k = 3 and dimensions = 8",
+        [bytes([1])],
+    ) == [(1, 0.0), (3, 1.0), (2, 2.0)]
+
+
+def test_knn_dimensions_errors(db):
+    db.execute(
+        "create virtual table v using vec0(a float[4], b float[4] quantize=int8, c bit[16])"
+    )
+    db.execute(
+        "insert into v(rowid, a, b, c) values (1, '[1, 2, 3, 4]', '[1, 2, 3, 4]', vec_bit(X'FFFF'))"
+    )
+    knn = "select rowid from v where a match ? and k = 1 and dimensions = ?"
+    for value in [0, 5, -1, 1.5, "2"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="dimensions value in knn query must be an integer between 1 and 4",
+        ):
+            db.execute(knn, ["[1, 2, 3, 4]", value])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match=r"Dimension mismatch for query vector for the \"a\" column. Expected 4 dimensions but received 3.",
+    ):
+        db.execute(knn, ["[1, 2, 3]", 2])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='dimensions constraints are not supported on the quantized "b" column',
+    ):
+        db.execute(
+            "select rowid from v where b match '[1, 2]' and k = 1 and dimensions = 2"
+        )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='dimensions value on the bit vector column "c" must be divisible by 8, provided 4',
+    ):
+        db.execute(
+            "select rowid from v where c match vec_bit(X'FF') and k = 1 and dimensions = 4"
+        )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='A value was provided for the hidden "dimensions" column.',
+    ):
+        db.execute("insert into v(rowid, a, b, c, dimensions) values (2, '[1, 2, 3, 4]', '[1, 2, 3, 4]', vec_bit(X'FFFF'), 2)")
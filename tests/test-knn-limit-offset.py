@@ -0,0 +1,45 @@
+import sqlite3
+import pytest
+
+pytestmark = pytest.mark.skipif(
+    sqlite3.sqlite_version_info < (3, 41, 0),
+    reason="LIMIT and OFFSET are only passed to virtual tables in SQLite 3.41+",
+)
+
+
+def rowids(db, sql, params=[]):
+    return [row[0] for row in db.execute(sql, params).fetchall()]
+
+
+def test_limit_offset_pagination(db):
+    db.execute(
+        "create virtual table v using vec0(a float[1], b float[1] index=hnsw, genre text, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, b, genre) values (?, ?, ?, ?)",
+        [(i, f"[{i}]", f"[{i}]", "ab"[i % 2]) for i in range(1, 101)],
+    )
+
+    query = "select rowid from v where a match '[0]' order by distance limit ? offset ?"
+    assert rowids(db, query, [5, 10]) == [11, 12, 13, 14, 15]
+    assert rowids(db, query, [5, 0]) == [1, 2, 3, 4, 5]
+    # pages past the last row are short or empty
+    assert rowids(db, query, [5, 98]) == [99, 100]
+    assert rowids(db, query, [5, 200]) == []
+    assert rowids(db, query, [0, 2]) == []
+
+    # pages are taken after metadata constraints, and from ANN indexes
+    assert rowids(
+        db, "select rowid from v where a match '[0]' and genre = 'a' limit 3 offset 2"
+    ) == [6, 8, 10]
+    assert rowids(db, "select rowid from v where b match '[0]' limit 3 offset 3") == [
+        4,
+        5,
+        6,
+    ]
+    assert db.execute(
+        "explain query plan select rowid from v where a match '[0]' limit 5 offset 10"
+    ).fetchone()[3].endswith("{___}___+___ knn")
+
+    with pytest.raises(sqlite3.OperationalError, match="LIMIT plus OFFSET in knn query too large"):
+        db.execute(query, [4000, 200]).fetchall()
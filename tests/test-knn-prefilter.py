@@ -0,0 +1,128 @@
+import random
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def _i8(list):
+    return struct.pack("%sb" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_knn_prefilter_hamming(db):
+    db.execute(
+        "create virtual table v using vec0(a float[16], b int8[16] distance_metric=cosine, chunk_size=8)"
+    )
+    rng = random.Random(1)
+    for rowid in range(1, 201):
+        a = [rng.uniform(-1, 1) for _ in range(16)]
+        b = [rng.randint(-128, 127) for _ in range(16)]
+        db.execute(
+            "insert into v(rowid, a, b) values (?, ?, vec_int8(?))",
+            [rowid, _f32(a), _i8(b)],
+        )
+    queries = {
+        "a": _f32([rng.uniform(-1, 1) for _ in range(16)]),
+        "b": _i8([rng.randint(-128, 127) for _ in range(16)]),
+    }
+
+    def knn(column, k, constraints="", params=[]):
+        return rows(
+            db,
+            f"select rowid, distance from v where {column} match vec_{'f32' if column == 'a' else 'int8'}(?) "
+            f"and k = {k} {constraints}",
+            [queries[column], *params],
+        )
+
+    for column in ["a", "b"]:
+        prefilter = "and prefilter = 'hamming' and oversample = ?"
+
+        # every candidate is rescored, so the distances are exact and only
+        # the candidates the sign pass keeps can differ
+        approximate = knn(column, 10, prefilter, [2])
+        assert rows(db, "select vec_debug_last_plan()") == [
+            (f"knn on v.{column} via hamming prefilter chunk scan with rescoring",)
+        ]
+        assert len(approximate) == 10
+        assert [r[1] for r in approximate] == sorted(r[1] for r in approximate)
+        assert set(approximate) <= set(knn(column, 200))
+
+        # oversampling every row is the exact search
+        assert knn(column, 10, prefilter, [20]) == knn(column, 10)
+
+    # the default oversample is 8, case doesn't matter
+    assert len(knn("a", 5, "and prefilter = 'HAMMING'")) == 5
+
+
+def test_knn_prefilter_errors(db):
+    db.execute(
+        "create virtual table v using vec0(a float[8], b float[8] quantize=int8, c bit[8], d float[8] quantize=binary)"
+    )
+    db.execute(
+        "insert into v(rowid, a, b, c, d) values (1, ?, ?, vec_bit(X'FF'), ?)",
+        [_f32([1] * 8), _f32([1] * 8), _f32([1] * 8)],
+    )
+    query = _f32([1] * 8)
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="prefilter value in knn query must be 'hamming', provided binary",
+    ):
+        db.execute(
+            "select rowid from v where a match ? and k = 1 and prefilter = 'binary'",
+            [query],
+        )
+    for column, value in [("b", "?"), ("c", "vec_bit(X'FF')")]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=f'prefilter=\'hamming\' is only supported on float32 and int8 columns without quantize, not "{column}"',
+        ):
+            db.execute(
+                f"select rowid from v where {column} match {value} and k = 1 and prefilter = 'hamming'",
+                [query] if value == "?" else [],
+            )
+    for constraint in ["distance < 1", "dimensions = 4"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="prefilter='hamming' can't be combined with distance or dimensions constraints",
+        ):
+            db.execute(
+                f"select rowid from v where a match ? and k = 1 and prefilter = 'hamming' and {constraint}",
+                [query],
+            )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="oversample in knn query needs prefilter='hamming' or a quantize=binary column",
+    ):
+        db.execute(
+            "select rowid from v where a match ? and k = 1 and oversample = 4",
+            [query],
+        )
+    for value in [0, 1025, 1.5, "4"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="oversample value in knn query must be an integer between 1 and 1024",
+        ):
+            db.execute(
+                "select rowid from v where a match ? and k = 1 and prefilter = 'hamming' and oversample = ?",
+                [query, value],
+            )
+    # quantize=binary columns take oversample in place of their rescore option
+    assert rows(
+        db, "select rowid from v where d match ? and k = 1 and oversample = 2", [query]
+    ) == [(1,)]
+    for column in ["prefilter", "oversample"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=f'A value was provided for the hidden "{column}" column.',
+        ):
+            db.execute(
+                f"insert into v(rowid, a, b, c, d, {column}) values (2, ?, ?, vec_bit(X'FF'), ?, 1)",
+                [query, query, query],
+            )
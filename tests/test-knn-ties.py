@@ -0,0 +1,63 @@
+import json
+import random
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+@pytest.mark.parametrize(
+    "options",
+    [
+        "a float[2], chunk_size=8",
+        "a float[2], chunk_size=8, threads=3",
+        "a float[2] quantize=int8, chunk_size=8",
+        "a float[2] index=hnsw, chunk_size=8",
+        "a float[2] index=ivf(nlist=2), chunk_size=8",
+    ],
+)
+def test_knn_ties(db, options):
+    db.execute(f"create virtual table v using vec0({options})")
+    # every vector is the same distance from the query, inserted out of rowid
+    # order and into slots freed by deletes
+    ids = [17, 3, 40, 8, 25, 1, 33, 12, 5, 29, 2, 21]
+    db.executemany(
+        "insert into v(rowid, a) values (?, '[1, 1]')", [(rowid,) for rowid in ids]
+    )
+    db.execute("delete from v where rowid in (3, 25)")
+    db.executemany("insert into v(rowid, a) values (?, '[1, 1]')", [(50,), (4,)])
+    if "ivf" in options:
+        db.execute("insert into v(v) values ('train')")
+
+    expected = sorted(set(ids + [50, 4]) - {3, 25})
+    for k in [1, 5, len(expected)]:
+        assert rows(db, f"select rowid from v where a match '[0, 0]' and k = {k}") == [
+            (rowid,) for rowid in expected[:k]
+        ]
+
+
+@pytest.mark.parametrize("threads", [1, 3])
+def test_knn_ties_large_k(db, threads):
+    # integer coordinates, so L1 distances are exact and many of them tie
+    db.execute(
+        "create virtual table v using vec0(a float[3] distance_metric=l1, "
+        f"chunk_size=64, threads={threads})"
+    )
+    rng = random.Random(threads)
+    vectors = {}
+    for rowid in rng.sample(range(1, 100_000), 2000):
+        vectors[rowid] = [rng.randint(-3, 3) for _ in range(3)]
+        db.execute(
+            "insert into v(rowid, a) values (?, ?)", [rowid, json.dumps(vectors[rowid])]
+        )
+    query = [1, 0, -1]
+    expected = sorted(
+        (sum(abs(x - y) for x, y in zip(vector, query)), rowid)
+        for rowid, vector in vectors.items()
+    )
+    for k in [1, 10, 63, 64, 65, 500, 2000]:
+        assert rows(
+            db, f"select distance, rowid from v where a match '{query}' and k = {k}"
+        ) == [tuple(r) for r in expected[:k]]
@@ -0,0 +1,113 @@
+import random
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def chunk_size(db, table="v"):
+    return rows(
+        db, "select value from vec0_info(?) where key = 'chunk_size'", [table]
+    )[0][0]
+
+
+def test_large_dimensions(db):
+    db.execute(
+        "create virtual table v using vec0(a float[8192], b float[16384] distance_metric=cosine, chunk_size=8)"
+    )
+    rng = random.Random(5)
+    vectors = {}
+    for rowid in range(1, 21):
+        a = [rng.uniform(-1, 1) for _ in range(8192)]
+        b = [rng.uniform(-1, 1) for _ in range(16384)]
+        vectors[rowid] = (a, b)
+        db.execute(
+            "insert into v(rowid, a, b) values (?, ?, ?)", [rowid, _f32(a), _f32(b)]
+        )
+    assert rows(db, "select vec_length(a), vec_length(b) from v where rowid = 7") == [
+        (8192, 16384)
+    ]
+    assert rows(db, "select a from v where rowid = 7") == [(_f32(vectors[7][0]),)]
+
+    query = vectors[12]
+    for column, vector in [("a", query[0]), ("b", query[1])]:
+        result = rows(
+            db,
+            f"select rowid, distance from v where {column} match ? and k = 3",
+            [_f32(vector)],
+        )
+        assert len(result) == 3
+        assert result[0][0] == 12
+        assert result[0][1] == pytest.approx(0, abs=1e-3)
+
+
+def test_large_dimensions_chunk_size(db):
+    # 8192 float32 dimensions at the largest chunk_size is a 128MB BLOB, well
+    # under SQLite's default limit
+    db.execute("create virtual table v using vec0(a float[8192], chunk_size=4096)")
+    assert chunk_size(db) == 4096
+    db.execute("insert into v(rowid, a) values (1, ?)", [_f32([0.5] * 8192)])
+    assert rows(
+        db, "select rowid from v where a match ? and k = 1", [_f32([0.5] * 8192)]
+    ) == [(1,)]
+
+    # with a lower limit, the default chunk_size shrinks until a chunk fits
+    db.setlimit(sqlite3.SQLITE_LIMIT_LENGTH, 10_000_000)
+    db.execute("create virtual table w using vec0(a float[4], b float[3072])")
+    assert chunk_size(db, "w") == 808
+    assert chunk_size(db, "w") * 3072 * 4 <= 10_000_000
+    db.execute("insert into w(rowid, a, b) values (1, ?, ?)", [_f32([1] * 4), _f32([1] * 3072)])
+    assert rows(db, "select count(*) from w_chunks") == [(1,)]
+
+    # small vectors keep the default
+    db.execute("create virtual table small using vec0(a float[4])")
+    assert chunk_size(db, "small") == 1024
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='vec0 constructor error: chunk_size too large for vector column "b", at most 808 of its vectors fit in SQLite\'s 10000000 byte BLOB limit',
+    ):
+        db.execute(
+            "create virtual table x using vec0(a float[4], b float[3072], chunk_size=1024)"
+        )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='chunk_size too large for vector column "b", at most 808 of its vectors',
+    ):
+        db.execute("insert into w(w) values ('rechunk=1024')")
+    db.execute("insert into w(w) values ('rechunk=800')")
+    assert chunk_size(db, "w") == 800
+
+    db.setlimit(sqlite3.SQLITE_LIMIT_LENGTH, 100_000)
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='vec0 constructor error: Vector column "a" is too large, not even 8 of its vectors fit in SQLite\'s 100000 byte BLOB limit',
+    ):
+        db.execute("create virtual table x using vec0(a float[16384])")
+
+
+def test_large_dimensions_reconnect(tmp_path):
+    path = str(tmp_path / "large.db")
+    db = sqlite3.connect(path)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    db.setlimit(sqlite3.SQLITE_LIMIT_LENGTH, 10_000_000)
+    db.execute("create virtual table v using vec0(a float[3072])")
+    db.execute("insert into v(rowid, a) values (1, ?)", [_f32([1] * 3072)])
+    db.commit()
+    db.close()
+
+    db = sqlite3.connect(path)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    assert chunk_size(db) == 808
+    db.execute("insert into v(rowid, a) values (2, ?)", [_f32([2] * 3072)])
+    assert rows(db, "select rowid from v order by rowid") == [(1,), (2,)]
+    assert rows(db, "select count(*) from v_chunks") == [(1,)]
@@ -10,6 +10,7 @@ import inspect
 import pytest
 import json
 import numpy as np
+import math
 from math import isclose
 
 EXT_PATH = "./dist/vec0"
@@ -49,6 +50,19 @@ def _int8(list):
     return struct.pack("%sb" % len(list), *list)
 
 
+def _f16(list):
+    return struct.pack("<%se" % len(list), *list)
+
+
+def _bf16(list):
+    # the upper halves of float32s, for values that are exact in bfloat16
+    return b"".join(_f32([x])[2:] for x in list)
+
+
+def _f64(list):
+    return struct.pack("<%sd" % len(list), *list)
+
+
 def bitmap(bitstring):
     return bytes([int(bitstring, 2)])
 
@@ -97,28 +111,80 @@ def spread_args(args):
 
 
 FUNCTIONS = [
+    "vec0_apply_changeset",
+    "vec0_changeset",
+    "vec0_copy",
+    "vec0_deserialize",
+    "vec0_export",
+    "vec0_migrate_from_vss",
+    "vec0_serialize",
+    "vec0_set_distance_handler",
+    "vec0_set_key",
+    "vec0_set_key_handler",
+    "vec0_set_progress_handler",
+    "vec0_set_slow_query_handler",
+    "vec0_stats",
     "vec_add",
+    "vec_avg",
+    "vec_bf16",
     "vec_bit",
+    "vec_cache_size",
+    "vec_concat",
     "vec_debug",
+    "vec_debug_last_plan",
     "vec_distance_cosine",
+    "vec_distance_dot",
     "vec_distance_hamming",
+    "vec_distance_jaccard",
     "vec_distance_l1",
     "vec_distance_l2",
+    "vec_distance_sparse_dot",
+    "vec_distance_tanimoto",
+    "vec_f16",
     "vec_f32",
+    "vec_f32_to_bf16",
+    "vec_f32_to_f16",
+    "vec_f64",
+    "vec_from_pgvector",
     "vec_int8",
+    "vec_json_contains",
     "vec_length",
     "vec_normalize",
+    "vec_optimize_remaining",
+    "vec_project",
     "vec_quantize_binary",
     "vec_quantize_int8",
+    "vec_query",
+    "vec_rebuild_remaining",
+    "vec_register_query",
+    "vec_rrf",
+    "vec_scale",
+    "vec_score_l2",
+    "vec_similarity_cosine",
     "vec_slice",
     "vec_sub",
+    "vec_sum",
     "vec_to_json",
+    "vec_to_pgvector",
     "vec_type",
+    "vec_valid",
     "vec_version",
+    "vec_weighted_score",
 ]
 MODULES = [
     "vec0",
+    "vec0_info",
+    "vec0_integrity_check",
+    "vec0_kmeans",
+    "vec0_repair",
+    "vec_arrow_each",
     "vec_each",
+    "vec_faiss_each",
+    "vec_pairwise",
+    "vec_rerank_topk",
+    "vec_safetensors_each",
+    "vec_static_file",
+    "vec_topk",
     # "vec_static_blob_entries",
     # "vec_static_blobs",
 ]
@@ -267,9 +333,9 @@ def test_vec_static_blob_entries():
 def test_limits():
     db = connect(EXT_PATH)
     with _raises(
-        "vec0 constructor error: Dimension on vector column too large, provided 8193, maximum 8192"
+        "vec0 constructor error: Dimension on vector column too large, provided 16385, maximum 16384"
     ):
-        db.execute("create virtual table v using vec0(a float[8193])")
+        db.execute("create virtual table v using vec0(a float[16385])")
     with _raises("vec0 constructor error: chunk_size too large"):
         db.execute("create virtual table v using vec0(a float[4], chunk_size=8200)")
     db.execute("create virtual table v using vec0(a float[1])")
@@ -303,7 +369,124 @@ def test_vec_version():
 def test_vec_debug():
     vec_debug = lambda *args: db.execute("select vec_debug()", args).fetchone()[0]
     d = vec_debug().split("\n")
-    assert len(d) == 4
+    assert len(d) == 5
+    assert d[4].split(": ")[1] in ("scalar", "avx", "avx2", "avx512", "neon", "sve")
+
+
+def test_vec_cache_size(tmp_path):
+    path = str(tmp_path / "cache.db")
+    db = connect(EXT_PATH, path)
+    db.isolation_level = None
+    vec_cache_size = lambda *args: db.execute(
+        f"select vec_cache_size({spread_args(args)})", args
+    ).fetchone()[0]
+    last_plan = lambda: db.execute("select vec_debug_last_plan()").fetchone()[0]
+    knn = lambda: [
+        row[0]
+        for row in db.execute(
+            "select rowid from v where a match '[3.1, 3.1]' and k = 3"
+        ).fetchall()
+    ]
+
+    assert vec_cache_size() == 0
+    db.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    db.executemany(
+        "insert into v(rowid, a) values (?, ?)", [(i, _f32([i, i])) for i in range(1, 41)]
+    )
+    assert knn() == [3, 4, 2]
+    assert last_plan() == "knn on v.a via chunk scan"
+
+    assert vec_cache_size(1024) == 1024
+    assert vec_cache_size() == 1024
+    assert knn() == [3, 4, 2]
+    assert last_plan() == "knn on v.a via chunk scan, 0 of 5 chunks cached"
+    assert knn() == [3, 4, 2]
+    assert last_plan() == "knn on v.a via chunk scan, 5 of 5 chunks cached"
+
+    # writes on this connection and on others replace stale chunks
+    db.execute("update v set a = '[100, 100]' where rowid = 3")
+    assert knn() == [4, 2, 5]
+    assert last_plan() == "knn on v.a via chunk scan, 0 of 5 chunks cached"
+    other = connect(EXT_PATH, path)
+    other.execute("delete from v where rowid = 2")
+    other.commit()
+    assert knn() == [4, 5, 1]
+    assert last_plan() == "knn on v.a via chunk scan, 0 of 5 chunks cached"
+
+    # explicit transactions don't use the cache
+    db.execute("begin")
+    assert knn() == [4, 5, 1]
+    assert last_plan() == "knn on v.a via chunk scan, 0 of 5 chunks cached"
+    db.execute("rollback")
+
+    assert vec_cache_size(0) == 0
+    assert knn() == [4, 5, 1]
+    assert last_plan() == "knn on v.a via chunk scan"
+
+    # chunks of 8 float[16] vectors are 512 bytes, so 1 KiB holds 2 of them.
+    # Scans read chunks in order, and evict each one before it's read again.
+    db.execute("create virtual table w using vec0(a float[16], chunk_size=8)")
+    db.executemany(
+        "insert into w(rowid, a) values (?, ?)", [(i, _f32([i] * 16)) for i in range(1, 41)]
+    )
+    vec_cache_size(1)
+    for _ in range(2):
+        db.execute("select rowid from w where a match ? and k = 1", [_f32([1] * 16)]).fetchall()
+        assert last_plan() == "knn on w.a via chunk scan, 0 of 5 chunks cached"
+
+    for value in [-1, 1.5, "1", 1073741825]:
+        with _raises(
+            "vec_cache_size() must be an integer number of KiB between 0 and 1073741824"
+        ):
+            vec_cache_size(value)
+    with _raises("vec_cache_size() takes at most 1 argument"):
+        vec_cache_size(1, 2)
+
+
+def test_vec_debug_last_plan():
+    db = connect(EXT_PATH)
+    last_plan = lambda: db.execute("select vec_debug_last_plan()").fetchone()[0]
+    assert last_plan() is None
+
+    db.execute(
+        "create virtual table v using vec0(user_id integer partition key, a float[2], genre text, b float[2] index=hnsw)"
+    )
+    db.execute(
+        "insert into v(rowid, user_id, a, genre, b) values (1, 1, '[1, 1]', 'x', '[1, 1]')"
+    )
+    cases = [
+        ("select * from v", "0:1 fullscan", "fullscan on v"),
+        ("select * from v where rowid = 1", ":2!___ point", "point on v"),
+        (
+            "select rowid from v where a match '[1, 1]' and k = 1",
+            "0:3{___}___ knn",
+            "knn on v.a via chunk scan",
+        ),
+        (
+            "select rowid from v where a match '[1, 1]' and k = 1 and user_id = 1",
+            "0:3{___}___]Aa_ knn partition-pruned",
+            "knn partition-pruned on v.a via chunk scan",
+        ),
+        (
+            "select rowid from v where a match '[1, 1]' and k = 1 and user_id = 1 and genre = 'x'",
+            "0:3{___}___]Aa_&Aa_ knn partition-pruned metadata-filtered",
+            "knn partition-pruned metadata-filtered on v.a via chunk scan",
+        ),
+        (
+            "select rowid from v where b match '[1, 1]' and k = 1",
+            "1:3{___}___ knn",
+            "knn on v.b via hnsw index",
+        ),
+        (
+            "select rowid from v where b match '[1, 1]' and k = 1 and genre = 'x'",
+            "1:3{___}___&Aa_ knn metadata-filtered",
+            "knn metadata-filtered on v.b via chunk scan",
+        ),
+    ]
+    for sql, index, plan in cases:
+        assert explain_query_plan(sql, db=db).endswith(index)
+        db.execute(sql).fetchall()
+        assert last_plan() == plan
 
 
 def test_vec_bit():
@@ -340,6 +523,11 @@ def test_vec_f32():
     for test in tests:
         assert vec_f32(json.dumps(test)) == _f32(test)
 
+    # shortest round-trip representations of float32 values parse exactly
+    assert vec_f32("[8.55792e+09, 9.531752e+08, 1.2322554e+08]") == _f32(
+        [8557920256, 953175168, 123225536]
+    )
+
     if SUPPORTS_SUBTYPE:
         assert db.execute("select subtype(vec_f32(X'00000000'))").fetchone()[0] == 223
 
@@ -393,6 +581,80 @@ def test_vec_int8():
         assert db.execute("select subtype(vec_int8(?))", [b"\x00"]).fetchone()[0] == 225
 
 
+def test_vec_f16():
+    vec_f16 = lambda *args: db.execute("select vec_f16(?)", args).fetchone()[0]
+    assert vec_f16(_f16([1, -2.5])) == _f16([1, -2.5])
+    assert vec_f16("[1, -2.5, 0.1]") == _f16([1, -2.5, 0.1])
+    # out of range values round to infinity
+    assert vec_f16("[70000]") == b"\x00\x7c"
+    assert db.execute("select vec_type(vec_f16('[1]'))").fetchone()[0] == "float16"
+    assert db.execute("select vec_to_json(vec_f16('[0.5, -2]'))").fetchone()[0] == (
+        "[0.500000,-2.000000]"
+    )
+
+    if SUPPORTS_SUBTYPE:
+        assert db.execute("select subtype(vec_f16(?))", [_f16([1])]).fetchone()[0] == 226
+
+    with _raises("invalid float16 vector BLOB length. Must be divisible by 2, found 3"):
+        vec_f16(b"aaa")
+    with _raises("zero-length vectors are not supported."):
+        vec_f16(b"")
+
+
+def test_vec_f32_to_f16():
+    vec_f32_to_f16 = lambda *args: db.execute(
+        "select vec_f32_to_f16(?)", args
+    ).fetchone()[0]
+    assert vec_f32_to_f16(_f32([1, -2.5, 0.1])) == _f16([1, -2.5, 0.1])
+    assert vec_f32_to_f16("[65504, 1e-7]") == _f16([65504, 1e-7])
+    with _raises("vec_f32_to_f16() requires a float32 vector"):
+        db.execute("select vec_f32_to_f16(vec_int8('[1]'))")
+
+
+def test_vec_bf16():
+    vec_bf16 = lambda *args: db.execute("select vec_bf16(?)", args).fetchone()[0]
+    assert vec_bf16(_bf16([1, -2.5])) == _bf16([1, -2.5])
+    assert vec_bf16("[1, -2.5, 3e38]") == _bf16([1, -2.5]) + b"\x62\x7f"
+    assert db.execute("select vec_type(vec_bf16('[1]'))").fetchone()[0] == "bfloat16"
+    assert db.execute("select vec_to_json(vec_bf16('[0.5, -2]'))").fetchone()[0] == (
+        "[0.500000,-2.000000]"
+    )
+
+    if SUPPORTS_SUBTYPE:
+        assert db.execute("select subtype(vec_bf16(?))", [_bf16([1])]).fetchone()[0] == 227
+
+    with _raises("invalid bfloat16 vector BLOB length. Must be divisible by 2, found 3"):
+        vec_bf16(b"aaa")
+
+
+def test_vec_f32_to_bf16():
+    vec_f32_to_bf16 = lambda *args: db.execute(
+        "select vec_f32_to_bf16(?)", args
+    ).fetchone()[0]
+    assert vec_f32_to_bf16(_f32([1, -2.5])) == _bf16([1, -2.5])
+    # halfway cases round to the even mantissa
+    assert vec_f32_to_bf16(_f32([1.00390625, 1.01171875])) == _bf16([1, 1.015625])
+    with _raises("vec_f32_to_bf16() requires a float32 vector"):
+        db.execute("select vec_f32_to_bf16(vec_f16('[1]'))")
+
+
+def test_vec_f64():
+    vec_f64 = lambda *args: db.execute("select vec_f64(?)", args).fetchone()[0]
+    assert vec_f64(_f64([1, -2.5])) == _f64([1, -2.5])
+    # parsed straight into doubles, not rounded through float32
+    assert vec_f64("[0.1, 1e300]") == _f64([0.1, 1e300])
+    assert db.execute("select vec_type(vec_f64('[1]'))").fetchone()[0] == "float64"
+    assert db.execute("select vec_to_json(vec_f64('[0.1, -2]'))").fetchone()[0] == (
+        "[0.1,-2.0]"
+    )
+
+    if SUPPORTS_SUBTYPE:
+        assert db.execute("select subtype(vec_f64(?))", [_f64([1])]).fetchone()[0] == 228
+
+    with _raises("invalid float64 vector BLOB length. Must be divisible by 8, found 12"):
+        vec_f64(b"a" * 12)
+
+
 def npy_cosine(a, b):
     return 1 - (np.dot(a, b) / (np.linalg.norm(a) * np.linalg.norm(b)))
 
@@ -469,6 +731,24 @@ def test_ensure_vector_match_cleanup_on_second_vector_error():
         ).fetchone()
 
 
+def test_vec_distance_dot():
+    vec_distance_dot = lambda *args, a="?", b="?": db.execute(
+        f"select vec_distance_dot({a}, {b})", args
+    ).fetchone()[0]
+
+    assert vec_distance_dot("[1, 2, 3]", "[4, -5, 6]") == -12.0
+    assert vec_distance_dot("[1, 1]", "[-2, -2]") == 4.0
+    assert vec_distance_dot(b"\x01\x02", b"\x03\x04", a="vec_int8(?)", b="vec_int8(?)") == -11
+    assert vec_distance_dot(b"\x7f" * 20, b"\x7f" * 20, a="vec_int8(?)", b="vec_int8(?)") == -127 * 127 * 20
+    assert vec_distance_dot(_f16([1, 2, 3]), _f16([4, -5, 6]), a="vec_f16(?)", b="vec_f16(?)") == -12.0
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Cannot calculate dot product between two bitvectors.",
+    ):
+        db.execute("select vec_distance_dot(vec_bit(X'FF'), vec_bit(X'FF'))")
+
+
 def test_vec_distance_hamming():
     vec_distance_hamming = lambda *args: db.execute(
         "select vec_distance_hamming(vec_bit(?), vec_bit(?))", args
@@ -490,6 +770,60 @@ def test_vec_distance_hamming():
         db.execute("select vec_distance_hamming(vec_int8(X'FF'), vec_int8(X'FF'))")
 
 
+def test_vec_distance_jaccard():
+    vec_distance_jaccard = lambda *args: db.execute(
+        "select vec_distance_jaccard(vec_bit(?), vec_bit(?))", args
+    ).fetchone()[0]
+    assert vec_distance_jaccard(b"\xff", b"\x00") == 1.0
+    assert vec_distance_jaccard(b"\x0f", b"\x03") == 0.5
+    assert vec_distance_jaccard(b"\xab", b"\xab") == 0.0
+    assert vec_distance_jaccard(b"\x00", b"\x00") == 0.0
+    # 64-bit fast path
+    assert vec_distance_jaccard(b"\xff" * 8, b"\x0f" * 8) == 0.5
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Jaccard and Tanimoto distances require two bitvectors.",
+    ):
+        db.execute("select vec_distance_jaccard(vec_f32('[1.0]'), vec_f32('[1.0]'))")
+
+
+def test_vec_distance_sparse_dot():
+    vec_distance_sparse_dot = lambda *args: db.execute(
+        "select vec_distance_sparse_dot(?, ?)", args
+    ).fetchone()[0]
+    assert vec_distance_sparse_dot('{"1": 2, "5": 3}', '{"9": 1, "5": 2}') == -6.0
+    assert vec_distance_sparse_dot('{"1": 2}', '{"2": 2}') == 0.0
+    assert vec_distance_sparse_dot(" { } ", '{"2": 2}') == 0.0
+    # BLOBs are (u32 index, f32 value) pairs in ascending index order
+    assert vec_distance_sparse_dot(struct.pack("<IfIf", 1, 2, 5, 3), '{"5": 0.5}') == -1.5
+
+    for a, message in [
+        ('{"1": 1, "1": 2}', "sparse vector has a duplicate index 1."),
+        ('{"4294967296": 1}', "sparse vector indexes must be at most 4294967295."),
+        ("[1, 2]", "JSON sparse vector parsing error"),
+        ('{"a": 1}', "JSON sparse vector parsing error"),
+        ('{"1": 1,}', "JSON sparse vector parsing error"),
+        ('{"1": 1} x', "JSON sparse vector parsing error"),
+        (b"\x01\x00", "sparse vector blobs must be a series of 8-byte"),
+        (
+            struct.pack("<IfIf", 5, 1, 1, 1),
+            "sparse vector indexes must be in ascending order without duplicates.",
+        ),
+        (1, "sparse vectors must be a BLOB or JSON TEXT, found INTEGER."),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=re.escape(message)):
+            vec_distance_sparse_dot(a, "{}")
+
+
+def test_vec_distance_tanimoto():
+    vec_distance_tanimoto = lambda *args: db.execute(
+        "select vec_distance_tanimoto(vec_bit(?), vec_bit(?))", args
+    ).fetchone()[0]
+    assert vec_distance_tanimoto(b"\x0f", b"\x03") == 0.5
+    assert vec_distance_tanimoto(b"\xab", b"\xab") == 0.0
+
+
 def test_vec_distance_l1():
     vec_distance_l1 = lambda *args, a="?", b="?": db.execute(
         f"select vec_distance_l1({a}, {b})", args
@@ -692,6 +1026,101 @@ def test_vec_slice():
         vec_slice(b"\xab\xab\xab\xab", 0, 0)
 
 
+def test_vec_concat():
+    vec_concat = lambda sql, *args: db.execute(f"select {sql}", args).fetchone()[0]
+    assert vec_concat("vec_concat(?, ?)", _f32([1.1, 2.2]), _f32([3.3])) == _f32(
+        [1.1, 2.2, 3.3]
+    )
+    assert vec_concat("vec_concat('[1]', '[2]', '[3]')") == _f32([1, 2, 3])
+    assert vec_concat(
+        "vec_concat(vec_int8(?), vec_int8(?))", _int8([1, -2]), _int8([3])
+    ) == _int8([1, -2, 3])
+    assert vec_concat("vec_concat(vec_bit(X'AA'), vec_bit(X'BBCC'))") == b"\xAA\xBB\xCC"
+    assert vec_concat("vec_type(vec_concat(vec_f16('[1]'), vec_f16('[2]')))") == "float16"
+    # composes with vec_slice() and vec_normalize(), like re-normalizing a
+    # truncated Matryoshka embedding next to another one
+    assert vec_concat(
+        "vec_normalize(vec_concat(vec_slice('[3, 9, 9]', 0, 1), '[4]'))"
+    ) == _f32([0.6, 0.8])
+
+    with pytest.raises(
+        sqlite3.OperationalError, match=re.escape("vec_concat() requires at least 2 vectors.")
+    ):
+        vec_concat("vec_concat('[1]')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Vector type mismatch. First vector has type float32, while vector 3 has type int8.",
+    ):
+        vec_concat("vec_concat('[1]', '[2]', vec_int8('[3]'))")
+    with pytest.raises(
+        sqlite3.OperationalError, match="Error reading vector 2: zero-length vectors are not supported."
+    ):
+        vec_concat("vec_concat('[1]', '[]')")
+
+
+def test_vec_sum():
+    groups = """
+      with vecs_sum(g, v) as (
+        values (1, vec_f32('[1, 2]')), (1, '[3, 4]'), (1, null),
+          (2, vec_f32('[0.5, -0.5]')), (3, null)
+      )
+    """
+    assert execute_all(
+        db, groups + "select g, vec_sum(v) as s from vecs_sum group by g order by g"
+    ) == [
+        {"g": 1, "s": _f32([4, 6])},
+        {"g": 2, "s": _f32([0.5, -0.5])},
+        {"g": 3, "s": None},
+    ]
+    assert (
+        db.execute(groups + "select vec_sum(v) from vecs_sum where g = 4").fetchone()[0]
+        is None
+    )
+    # int8 and float16 vectors add up to float32 vectors
+    assert (
+        db.execute(
+            "select vec_sum(vec_int8(value)) from json_each('[\"[100, -1]\", \"[100, -1]\"]')"
+        ).fetchone()[0]
+        == _f32([200, -2])
+    )
+    assert (
+        db.execute(
+            "select vec_sum(vec_f16(value)) from json_each('[\"[1.5]\", \"[2]\"]')"
+        ).fetchone()[0]
+        == _f32([3.5])
+    )
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Vector dimension mismatch. The first vector has 2 dimensions, but a vector with 3 dimensions was provided.",
+    ):
+        db.execute("select vec_sum(value) from json_each('[[1, 2], [1, 2, 3]]')").fetchone()
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Vector type mismatch. The first vector has type float32, but a int8 vector was provided.",
+    ):
+        db.execute(
+            "select vec_sum(iif(key = 0, vec_f32(value), vec_int8(value))) from json_each('[[1], [1]]')"
+        ).fetchone()
+    with pytest.raises(
+        sqlite3.OperationalError, match="bit vectors cannot be summed or averaged."
+    ):
+        db.execute("select vec_sum(vec_bit(X'FF'))").fetchone()
+
+
+def test_vec_avg():
+    assert db.execute(
+        "select vec_avg(value) from json_each('[[1, 2], [3, 4], null, [5, 0]]')"
+    ).fetchone()[0] == _f32([3, 2])
+    assert (
+        db.execute(
+            "select vec_avg(vec_int8(value)) from json_each('[\"[1, 2]\", \"[2, 2]\"]')"
+        ).fetchone()[0]
+        == _f32([1.5, 2])
+    )
+    assert db.execute("select vec_avg(value) from json_each('[null]')").fetchone()[0] is None
+
+
 def test_vec_type():
     vec_type = lambda *args, a="?": db.execute(
         f"select vec_type({a})", args
@@ -708,6 +1137,43 @@ def test_vec_type():
         vec_type(None)
 
 
+def test_vec_valid():
+    vec_valid = lambda *args, a="?": db.execute(
+        f"select vec_valid({a}, {', '.join('?' * (len(args) - 1))})", args
+    ).fetchone()[0]
+    assert vec_valid("[1, 2]", "float32") is None
+    assert vec_valid("[1, 2]", "float32", 2) is None
+    assert vec_valid(_f32([1, 2, 3]), "FLOAT32", 3) is None
+    assert vec_valid("[1, -2]", "int8", 2) is None
+    assert vec_valid(b"\xaa", "bit", 8) is None
+    assert vec_valid("[1, 2]", "float16", 2) is None
+    assert vec_valid("[1, 2]", "bfloat16", a="vec_bf16(?)") is None
+
+    # diagnostics instead of errors
+    assert vec_valid("[1, 2]", "float32", 3) == "expected 3 dimensions, found 2"
+    assert vec_valid(b"\xaa\xbb", "float32") == (
+        "invalid float32 vector BLOB length. Must be divisible by 4, found 2"
+    )
+    assert vec_valid("x", "float32") == (
+        "JSON array parsing error: Input does not start with '['"
+    )
+    assert vec_valid("[1, 300]", "int8") == "JSON parsing error: value out of range for int8"
+    assert vec_valid(_f32([1, float("nan")]), "float32") == "element 1 is NaN"
+    assert vec_valid(_f32([float("-inf")]), "float32") == "element 0 is infinite"
+    assert vec_valid("[1e10]", "float16", a="vec_f16(?)") == "element 0 is infinite"
+    assert vec_valid("[1]", "float32", a="vec_int8(?)") == "vector is int8, not float32"
+    assert vec_valid(None, "float32") is not None
+
+    with _raises(
+        "vec_valid() type must be 'float32', 'int8', 'bit', 'float16', 'bfloat16' or 'float64'"
+    ):
+        vec_valid("[1]", "float128")
+    with _raises("vec_valid() dimensions must be a positive integer"):
+        vec_valid("[1]", "float32", 0)
+    with _raises("vec_valid() takes 2 or 3 arguments."):
+        db.execute("select vec_valid('[1]')")
+
+
 def test_vec_add():
     vec_add = lambda *args, a="?", b="?": db.execute(
         f"select vec_add({a}, {b})", args
@@ -764,6 +1230,35 @@ def test_vec_sub():
         vec_sub(_int8([2]), _f32([1]), a="vec_int8(?)")
 
 
+def test_vec_scale():
+    vec_scale = lambda *args, a="?": db.execute(
+        f"select vec_scale({a}, ?)", args
+    ).fetchone()[0]
+    assert vec_scale("[1, -2, 0.5]", 2) == _f32([2, -4, 1])
+    assert vec_scale("[1, 2]", -0.5) == _f32([-0.5, -1])
+    assert vec_scale("[1, 2]", "3") == _f32([3, 6])
+    assert vec_scale(_int8([1, -2, 100, -100]), 1.5, a="vec_int8(?)") == _int8(
+        [2, -3, 127, -128]
+    )
+    assert tuple(
+        db.execute(
+            "select vec_to_json(vec_scale(vec_f16('[1, 2]'), 0.5)), "
+            "vec_type(vec_scale(vec_bf16('[1, 2]'), 2))"
+        ).fetchone()
+    ) == ("[0.500000,1.000000]", "bfloat16")
+    assert db.execute(
+        "select vec_to_json(vec_add('[1, 1]', vec_scale('[2, 4]', 0.25)))"
+    ).fetchone()[0] == "[1.500000,2.000000]"
+
+    with pytest.raises(sqlite3.OperationalError, match="Cannot scale a bitvector."):
+        vec_scale(b"\xff", 2, a="vec_bit(?)")
+    for s in ["two", None, b"\x02"]:
+        with pytest.raises(
+            sqlite3.OperationalError, match="vec_scale\\(\\) scale must be a number"
+        ):
+            vec_scale("[1]", s)
+
+
 def test_vec_to_json():
     vec_to_json = lambda *args, input="?": db.execute(
         f"select vec_to_json({input})", args
@@ -776,6 +1271,45 @@ def test_vec_to_json():
     assert vec_to_json(b"\x0f", input="vec_bit(?)") == "[1,1,1,1,0,0,0,0]"
 
 
+def test_vec_to_pgvector():
+    vec_to_pgvector = lambda *args, input="?": db.execute(
+        f"select vec_to_pgvector({input}{', ?' * (len(args) - 1)})", args
+    ).fetchone()[0]
+    assert vec_to_pgvector("[0.1, 1e-5, 2.5, -3, 123456789]") == "[0.1,1e-05,2.5,-3,1.2345679e+08]"
+    assert vec_to_pgvector("[1, -2]", input="vec_int8(?)") == "[1,-2]"
+    assert vec_to_pgvector("[0.5, 0.25]", input="vec_f16(?)") == "[0.5,0.25]"
+    assert vec_to_pgvector(b"\x05\x01", input="vec_bit(?)") == "1010000010000000"
+
+    assert vec_to_pgvector("[1, 2.5]", "binary") == struct.pack(">hh2f", 2, 0, 1, 2.5)
+    assert vec_to_pgvector(b"\x05\x01", "binary", input="vec_bit(?)") == bytes.fromhex(
+        "00000010a080"
+    )
+
+    with _raises("vec_to_pgvector() format must be 'text' or 'binary'."):
+        vec_to_pgvector("[1]", "csv")
+    with _raises("vec_to_pgvector() takes 1 or 2 arguments."):
+        db.execute("select vec_to_pgvector()")
+    with _raises("vec_to_pgvector() NaN and infinite values are not allowed in pgvector vectors."):
+        vec_to_pgvector(_f32([float("inf")]))
+
+
+def test_vec_from_pgvector():
+    vec_from_pgvector = lambda *args: db.execute(
+        "select vec_from_pgvector(?)", args
+    ).fetchone()[0]
+    assert vec_from_pgvector("[1,2.5,-3]") == _f32([1, 2.5, -3])
+    assert vec_from_pgvector(struct.pack(">hh2f", 2, 0, 1, 2.5)) == _f32([1, 2.5])
+    if SUPPORTS_SUBTYPE:
+        assert db.execute(
+            "select subtype(vec_from_pgvector(vec_to_pgvector('[1]', 'binary')))"
+        ).fetchone()[0] == 223
+
+    with _raises("vec_from_pgvector() binary pgvector value is too short."):
+        vec_from_pgvector(b"\x00")
+    with _raises("vec_from_pgvector() binary pgvector value is invalid."):
+        vec_from_pgvector(struct.pack(">hh1f", 2, 0, 1))
+
+
 @pytest.mark.skip(reason="TODO")
 def test_vec_quantize_int8():
     vec_quantize_int8 = lambda *args: db.execute(
@@ -784,6 +1318,46 @@ def test_vec_quantize_int8():
     assert vec_quantize_int8() == 111
 
 
+def test_vec_project():
+    vec_project = lambda *args, a="?": db.execute(
+        f"select vec_project({a}, ?, ?)", args
+    ).fetchone()[0]
+    projected = vec_project("[1, 2, 3, 4]", 42, 3)
+    assert len(projected) == 3 * 4
+    # deterministic for a seed, and int8, float16 and bfloat16 vectors project
+    # like their float32 values
+    assert vec_project("[1, 2, 3, 4]", 42, 3) == projected
+    assert vec_project("[1, 2, 3, 4]", 43, 3) != projected
+    assert vec_project(_int8([1, 2, 3, 4]), 42, 3, a="vec_int8(?)") == projected
+    assert vec_project("[1, 2, 3, 4]", 42, 3, a="vec_f16(?)") == projected
+    assert vec_project("[1, 2, 3, 4]", 42, 3, a="vec_bf16(?)") == projected
+    assert db.execute("select vec_type(vec_project(vec_int8('[1]'), 0, 2))").fetchone()[0] == "float32"
+
+    # distances are roughly preserved
+    a = [math.sin(i * 1.7) for i in range(512)]
+    b = [math.cos(i * 0.3) for i in range(512)]
+    (before, after) = db.execute(
+        "select vec_distance_l2(:a, :b), "
+        "vec_distance_l2(vec_project(:a, 1, 128), vec_project(:b, 1, 128))",
+        {"a": _f32(a), "b": _f32(b)},
+    ).fetchone()
+    assert after == pytest.approx(before, rel=0.25)
+
+    with pytest.raises(sqlite3.OperationalError, match="Cannot project a bitvector."):
+        vec_project(b"\xff", 1, 2, a="vec_bit(?)")
+    for seed in [1.5, "1", None]:
+        with pytest.raises(
+            sqlite3.OperationalError, match="vec_project\\(\\) seed must be an integer"
+        ):
+            vec_project("[1]", seed, 2)
+    for out_dims in [0, -1, 16385, 2.0]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="vec_project\\(\\) out_dims must be an integer between 1 and 16384",
+        ):
+            vec_project("[1]", 1, out_dims)
+
+
 def test_vec_quantize_binary():
     vec_quantize_binary = lambda *args, input="?": db.execute(
         f"select vec_quantize_binary({input})", args
@@ -791,6 +1365,196 @@ def test_vec_quantize_binary():
     assert vec_quantize_binary("[-1, -1, -1, -1, 1, 1, 1, 1]") == b"\xf0"
 
 
+def test_vec_query():
+    db = connect(EXT_PATH)
+    db.execute("select vec_register_query('q8', vec_int8('[4, 4]'))")
+    # the parsed vector, with its type
+    assert db.execute("select vec_query('q8')").fetchone()[0] == _int8([4, 4])
+    assert db.execute("select vec_type(vec_query('q8'))").fetchone()[0] == "int8"
+    # queries are per connection
+    other = connect(EXT_PATH)
+    with _raises(
+        "vec_query() no query vector is registered as q8, register one with "
+        "vec_register_query() first"
+    ):
+        other.execute("select vec_query('q8')").fetchall()
+
+
+def test_vec_register_query():
+    db = connect(EXT_PATH)
+    db.execute("create virtual table v using vec0(a float[2], b int8[2], c bit[8])")
+    db.execute(
+        "insert into v(rowid, a, b, c) values "
+        "(1, '[1, 1]', vec_int8('[1, 1]'), vec_bit(x'ff')), "
+        "(2, '[5, 5]', vec_int8('[5, 5]'), vec_bit(x'0f'))"
+    )
+    assert db.execute("select vec_register_query('q', '[4, 4]')").fetchone()[0] is None
+    db.execute("select vec_register_query('q8', vec_int8('[4, 4]'))")
+    db.execute("select vec_register_query('qb', vec_bit(x'0f'))")
+    assert db.execute("select vec_query('q')").fetchone()[0] == _f32([4, 4])
+    knn = "select rowid from v where {} match vec_query(?) and k = 1"
+    assert db.execute(knn.format("a"), ["q"]).fetchone()[0] == 2
+    assert db.execute(knn.format("b"), ["q8"]).fetchone()[0] == 2
+    assert db.execute(knn.format("c"), ["qb"]).fetchone()[0] == 2
+
+    # registering a name again replaces it, and NULL forgets it
+    db.execute("select vec_register_query('q', '[1, 1.5]')")
+    assert db.execute(knn.format("a"), ["q"]).fetchone()[0] == 1
+    db.execute("select vec_register_query('q', null)")
+    with _raises("no query vector is registered as q,"):
+        db.execute(knn.format("a"), ["q"]).fetchall()
+
+    with _raises("vec_register_query() name must be TEXT"):
+        db.execute("select vec_register_query(1, '[1]')")
+    with _raises("JSON array parsing error"):
+        db.execute("select vec_register_query('bad', 'x')")
+
+
+def test_vec_rrf():
+    vec_rrf = lambda *args: db.execute(
+        f"select vec_rrf({', '.join('?' * len(args))})", args
+    ).fetchone()[0]
+    assert vec_rrf(1, 60) == pytest.approx(1 / 61)
+    assert vec_rrf(1, 3, 60) == pytest.approx(1 / 61 + 1 / 63)
+    assert vec_rrf(None, 2, 0) == pytest.approx(1 / 2)
+    assert vec_rrf(None, None, 60) == 0.0
+    assert vec_rrf(2.0, 60.0) == pytest.approx(1 / 62)
+
+    with _raises("vec_rrf() requires at least one rank and the k constant."):
+        vec_rrf(60)
+    with _raises("vec_rrf() k must be a non-negative number."):
+        vec_rrf(1, None)
+    with _raises("vec_rrf() k must be a non-negative number."):
+        vec_rrf(1, -1)
+    with _raises("vec_rrf() ranks must be NULL or numbers starting at 1, argument 2 isn't."):
+        vec_rrf(1, 0, 60)
+    with _raises("vec_rrf() ranks must be NULL or numbers starting at 1, argument 1 isn't."):
+        vec_rrf("1", 60)
+
+
+def test_vec_optimize_remaining():
+    db = connect(EXT_PATH)
+    vec_optimize_remaining = lambda table: db.execute(
+        "select vec_optimize_remaining(?)", [table]
+    ).fetchone()[0]
+    db.execute("create virtual table v using vec0(a float[1], chunk_size=8)")
+    assert vec_optimize_remaining("v") == 0
+    db.executemany(
+        "insert into v(rowid, a) values (?, ?)", [(i, _f32([i])) for i in range(1, 25)]
+    )
+    assert vec_optimize_remaining("v") == 0
+    db.execute("delete from v where rowid % 2 = 0")
+    assert vec_optimize_remaining("v") == 1
+    db.execute("insert into v(v) values ('optimize')")
+    assert vec_optimize_remaining("v") == 0
+
+    with _raises("missing is not a vec0 table"):
+        vec_optimize_remaining("missing")
+    with _raises("table name must be TEXT"):
+        vec_optimize_remaining(None)
+
+
+def test_vec_rebuild_remaining():
+    db = connect(EXT_PATH)
+    vec_rebuild_remaining = lambda table: db.execute(
+        "select vec_rebuild_remaining(?)", [table]
+    ).fetchone()[0]
+    db.execute(
+        "create virtual table v using vec0(a float[1] index=hnsw(m=4), chunk_size=8)"
+    )
+    assert vec_rebuild_remaining("v") == 0
+    db.executemany(
+        "insert into v(rowid, a) values (?, ?)", [(i, _f32([i])) for i in range(1, 21)]
+    )
+    db.execute("insert into v(v) values ('rebuild=0')")
+    assert vec_rebuild_remaining("v") == 13
+    db.execute("insert into v(v) values ('rebuild')")
+    assert vec_rebuild_remaining("v") == 0
+
+    with _raises("missing is not a vec0 table"):
+        vec_rebuild_remaining("missing")
+    with _raises("table name must be TEXT"):
+        vec_rebuild_remaining(None)
+
+
+def test_vec_weighted_score():
+    vec_weighted_score = lambda *args: db.execute(
+        f"select vec_weighted_score({', '.join('?' * len(args))})", args
+    ).fetchone()[0]
+    assert vec_weighted_score(0.5, 2) == 1.0
+    assert vec_weighted_score(0.5, 0.7, 0.25, 0.3) == pytest.approx(0.425)
+    assert vec_weighted_score(None, 0.7, 1, 0.3) == pytest.approx(0.3)
+    assert vec_weighted_score(None, 1) == 0.0
+
+    with _raises("vec_weighted_score() requires pairs of scores and weights."):
+        vec_weighted_score(1, 2, 3)
+    with _raises("vec_weighted_score() requires pairs of scores and weights."):
+        db.execute("select vec_weighted_score()").fetchone()
+    with _raises("vec_weighted_score() weights must be numbers, argument 4 isn't."):
+        vec_weighted_score(1, 1, 1, None)
+    with _raises("vec_weighted_score() scores must be NULL or numbers, argument 1 isn't."):
+        vec_weighted_score(b"x", 1)
+
+
+def test_vec_similarity_cosine():
+    vec_similarity_cosine = lambda *args: db.execute(
+        "select vec_similarity_cosine(?)", args
+    ).fetchone()[0]
+    assert vec_similarity_cosine(0) == 1.0
+    assert vec_similarity_cosine(1) == 0.5
+    assert vec_similarity_cosine(2) == 0.0
+    assert vec_similarity_cosine(0.5) == 0.75
+    assert vec_similarity_cosine(-0.0000001) == 1.0
+    assert vec_similarity_cosine(2.0000001) == 0.0
+    assert vec_similarity_cosine(None) is None
+    distance = db.execute(
+        "select vec_distance_cosine('[1, 0]', '[1, 1]')"
+    ).fetchone()[0]
+    assert vec_similarity_cosine(distance) == pytest.approx((1 + 2**-0.5) / 2)
+
+    with _raises("vec_similarity_cosine() distance must be NULL or a number."):
+        vec_similarity_cosine("0.5")
+
+
+def test_vec_score_l2():
+    vec_score_l2 = lambda *args: db.execute(
+        "select vec_score_l2(?, ?)", args
+    ).fetchone()[0]
+    assert vec_score_l2(0, 1) == 1.0
+    assert vec_score_l2(1, 1) == pytest.approx(math.exp(-0.5))
+    assert vec_score_l2(2, 2) == pytest.approx(math.exp(-0.5))
+    assert vec_score_l2(3, 0.5) == pytest.approx(math.exp(-18))
+    assert vec_score_l2(None, 1) is None
+
+    with _raises("vec_score_l2() sigma must be a positive number."):
+        vec_score_l2(1, 0)
+    with _raises("vec_score_l2() sigma must be a positive number."):
+        vec_score_l2(None, None)
+    with _raises("vec_score_l2() distance must be NULL or a non-negative number."):
+        vec_score_l2(-1, 1)
+    with _raises("vec_score_l2() distance must be NULL or a non-negative number."):
+        vec_score_l2(b"1", 1)
+
+
+def test_vec_json_contains():
+    vec_json_contains = lambda *args: db.execute(
+        "select vec_json_contains(?, ?)", args
+    ).fetchone()[0]
+    assert vec_json_contains('{"a": 1, "b": {"c": "x"}}', '{"b": {"c": "x"}}') == 1
+    assert vec_json_contains('{"a": 1.0}', '{"a": 1}') == 1
+    assert vec_json_contains('{"a": [1, 2, 3]}', '{"a": [3, 1]}') == 1
+    assert vec_json_contains('{"a": [1, 2, 3]}', '{"a": [4]}') == 0
+    assert vec_json_contains('{"a": "1"}', '{"a": 1}') == 0
+    assert vec_json_contains('{"a": 1}', '{"b": null}') == 0
+    assert vec_json_contains('{"a": 1}', "{}") == 1
+    assert vec_json_contains(None, "{}") is None
+
+    with _raises("vec_json_contains() pattern must be a JSON object"):
+        vec_json_contains("{}", "[]")
+    with _raises("malformed JSON"):
+        vec_json_contains("{", '{"a": 1}')
+
+
 @pytest.mark.skip(reason="TODO")
 def test_vec0():
     pass
@@ -1422,6 +2186,12 @@ def test_vec0_updates():
     # EVIDENCE-OF: V25739_09810 vec0 UPDATE validates dimension length
     with _raises(
         'Dimension mismatch for new updated vector for the "aaa" column. Expected 8 dimensions but received 1.'
+    ):
+        db.execute("UPDATE t3 SET aaa = X'AABBCCDD' WHERE rowid = 1")
+    # a point UPDATE runs in one pass, so the vec_bit() subtype reaches vec0
+    # and the bit vector is rejected by type before its dimensions are checked
+    with _raises(
+        'Updated vector for the "aaa" column is expected to be of type float32, but a bit vector was provided.'
     ):
         db.execute("UPDATE t3 SET aaa = vec_bit(X'AABBCCDD') WHERE rowid = 1")
 
@@ -1702,65 +2472,1293 @@ def test_vec_each():
         db, "select rowid, * from vec_each(vec_f32(?))", args
     )
     assert vec_each_f32(_f32([1.0, 2.0, 3.0])) == [
-        {"rowid": 0, "value": 1.0},
-        {"rowid": 1, "value": 2.0},
-        {"rowid": 2, "value": 3.0},
+        {"rowid": 0, "idx": 0, "value": 1.0},
+        {"rowid": 1, "idx": 1, "value": 2.0},
+        {"rowid": 2, "idx": 2, "value": 3.0},
     ]
 
     with _raises("Input must have type BLOB (compact format) or TEXT (JSON), found NULL"):
       vec_each_f32(None)
 
-
-import io
-
-
-def to_npy(arr):
-    buf = io.BytesIO()
-    np.save(buf, arr)
-    buf.seek(0)
-    return buf.read()
+    # per-dimension statistics over a table's vectors
+    stats = connect(EXT_PATH)
+    stats.execute("create table t(embedding)")
+    stats.executemany(
+        "insert into t values (?)",
+        [[_f32([1, 0, 2])], [_f32([3, 0, 2])], [_f32([5, 0, 2])]],
+    )
+    assert execute_all(
+        stats,
+        """
+        select idx, avg(value) as mean, avg(value * value) - avg(value) * avg(value) as variance
+        from t, vec_each(t.embedding)
+        group by idx
+        """,
+    ) == [
+        {"idx": 0, "mean": 3.0, "variance": pytest.approx(8 / 3)},
+        {"idx": 1, "mean": 0.0, "variance": 0.0},
+        {"idx": 2, "mean": 2.0, "variance": 0.0},
+    ]
+    assert execute_all(
+        stats,
+        "select idx from t, vec_each(t.embedding) group by idx having max(abs(value)) = 0",
+    ) == [{"idx": 1}]
 
 
-def test_vec_npy_each():
-    db = connect(EXT_PATH, extra_entrypoint="sqlite3_vec_numpy_init")
-    vec_npy_each = lambda *args: execute_all(
-        db, "select rowid, * from vec_npy_each(?)", args
+def test_vec0_info():
+    db = connect(EXT_PATH)
+    db.execute(
+        """
+        create virtual table v using vec0(
+          user_id integer partition key,
+          a float[2] distance_metric=cosine,
+          b int8[4] index=hnsw,
+          tag text,
+          chunk_size=8
+        )
+        """
     )
-    assert vec_npy_each(to_npy(np.array([1.1, 2.2, 3.3], dtype=np.float32))) == [
-        {
-            "rowid": 0,
-            "vector": _f32([1.1, 2.2, 3.3]),
-        },
+    db.executemany(
+        "insert into v(rowid, user_id, a, b, tag) values (?, ?, ?, vec_int8(?), 'x')",
+        [(i, i % 2, _f32([i, -i]), _int8([1, 2, 3, 4])) for i in range(1, 31)],
+    )
+    db.execute("delete from v where rowid < 10")
+    info = lambda: [
+        tuple(row)
+        for row in db.execute(
+            "select key, name, value from vec0_info('v') where key != 'shadow_bytes'"
+        )
     ]
-    assert vec_npy_each(to_npy(np.array([[1.1, 2.2, 3.3]], dtype=np.float32))) == [
-        {
-            "rowid": 0,
-            "vector": _f32([1.1, 2.2, 3.3]),
-        },
+    assert info() == [
+        ("rows", None, 21),
+        ("chunks", None, 4),
+        ("chunk_size", None, 8),
+        ("fill_factor", None, 0.65625),
+        ("deleted_slots", None, 9),
+        ("chunk_fill", 1, 0.375),
+        ("chunk_fill", 2, 0.5),
+        ("chunk_fill", 3, 0.875),
+        ("chunk_fill", 4, 0.875),
+        ("element_type", "a", "float32"),
+        ("dimensions", "a", 2),
+        ("distance_metric", "a", "cosine"),
+        ("index", "a", "flat"),
+        ("quantize", "a", "none"),
+        ("element_type", "b", "int8"),
+        ("dimensions", "b", 4),
+        ("distance_metric", "b", "l2"),
+        ("index", "b", "hnsw"),
+        ("quantize", "b", "none"),
     ]
-    assert vec_npy_each(
-        to_npy(np.array([[1.1, 2.2, 3.3], [9.9, 8.8, 7.7]], dtype=np.float32))
-    ) == [
-        {
-            "rowid": 0,
-            "vector": _f32([1.1, 2.2, 3.3]),
-        },
-        {
-            "rowid": 1,
-            "vector": _f32([9.9, 8.8, 7.7]),
-        },
+    # only available when SQLite is compiled with the dbstat virtual table
+    shadow_tables = [
+        row[0]
+        for row in db.execute(
+            "select name from vec0_info('v') where key = 'shadow_bytes' and value > 0"
+        )
     ]
+    if shadow_tables:
+        assert shadow_tables == [
+            "v_chunks",
+            "v_hnsw01",
+            "v_info",
+            "v_metadatachunks00",
+            "v_metadatatext00",
+            "v_rowids",
+            "v_vector_chunks00",
+            "v_vector_chunks01",
+        ]
 
-    assert vec_npy_each(to_npy(np.array([], dtype=np.float32))) == []
+    db.execute("delete from v where rowid < 20")
+    db.execute("insert into v(v) values ('optimize')")
+    assert [row for row in info() if row[0] in ("rows", "chunks", "deleted_slots")] == [
+        ("rows", None, 11),
+        ("chunks", None, 2),
+        ("deleted_slots", None, 0),
+    ]
 
+    db.execute("create table plain(x)")
+    with _raises("plain is not a vec0 table"):
+        db.execute("select * from vec0_info('plain')")
+    with _raises("missing is not a vec0 table"):
+        db.execute("select * from vec0_info('missing')")
+    with _raises("vec0_info() requires the name of a vec0 table"):
+        db.execute("select * from vec0_info()")
+    db.execute("drop table v")
+    with _raises("v is not a vec0 table"):
+        db.execute("select * from vec0_info('v')")
 
-def test_vec_npy_each_errors():
-    db = connect(EXT_PATH, extra_entrypoint="sqlite3_vec_numpy_init")
-    vec_npy_each = lambda *args: execute_all(
-        db, "select rowid, * from vec_npy_each(?)", args
-    )
 
-    full = b"\x93NUMPY\x01\x00v\x00{'descr': '<f4', 'fortran_order': False, 'shape': (2, 4), }                                                          \n\xcd\xcc\x8c?\xcd\xcc\x0c@33S@\xcd\xcc\x8c@ff\x1eA\xcd\xcc\x0cAff\xf6@33\xd3@"
+def test_vec0_integrity_check():
+    db = connect(EXT_PATH)
+    db.execute(
+        "create virtual table v using vec0(a float[2], tag text, +note text, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, tag, note) values (?, ?, 'x', 'y')",
+        [(i, _f32([i, -i])) for i in range(1, 21)],
+    )
+    db.execute("delete from v where rowid < 5")
+    check = lambda: [
+        tuple(row) for row in db.execute("select * from vec0_integrity_check('v')")
+    ]
+    assert check() == []
+
+    db.execute("delete from v_auxiliary where rowid = 10")
+    db.execute("update v_rowids set chunk_offset = 9 where rowid = 12")
+    assert check() == [
+        ("v_rowids", 12, "rowid 12 points to slot 9 of chunk 2, outside of its 8 slots"),
+        (
+            "v_chunks",
+            2,
+            "slot 3 of chunk 2 is marked valid for rowid 12, which _rowids puts in slot 9 of chunk 2",
+        ),
+        ("v_auxiliary", 10, "rowid 10 has no _auxiliary row"),
+    ]
+
+    db.execute("create table plain(x)")
+    with _raises("plain is not a vec0 table"):
+        db.execute("select * from vec0_integrity_check('plain')")
+    with _raises("vec0_integrity_check() requires the name of a vec0 table"):
+        db.execute("select * from vec0_integrity_check()")
+
+
+def test_vec0_repair():
+    db = connect(EXT_PATH)
+    db.execute("create virtual table v using vec0(a float[2], +note text, chunk_size=8)")
+    db.executemany(
+        "insert into v(rowid, a, note) values (?, ?, 'x')",
+        [(i, _f32([i, -i])) for i in range(1, 11)],
+    )
+    repair = lambda: [tuple(row) for row in db.execute("select * from vec0_repair('v')")]
+    assert repair() == []
+
+    db.execute("update v_chunks set validity = x'00' where chunk_id = 1")
+    db.execute("delete from v_rowids where rowid = 10")
+    assert repair() == [
+        *[("v_chunks", 1, f"marked slot {i} of chunk 1 valid for rowid {i + 1}") for i in range(8)],
+        ("v_rowids", 10, "added rowid 10 from slot 1 of chunk 2"),
+    ]
+    assert [tuple(row) for row in db.execute("select * from vec0_integrity_check('v')")] == []
+    assert db.execute("select count(*) from v").fetchone()[0] == 10
+
+    db.execute("create table plain(x)")
+    with _raises("plain is not a vec0 table"):
+        db.execute("select * from vec0_repair('plain')")
+    with _raises("vec0_repair() requires the name of a vec0 table"):
+        db.execute("select * from vec0_repair()")
+
+
+def test_vec0_kmeans():
+    db = connect(EXT_PATH)
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[2],
+          b float[2] quantize=int8,
+          c int8[2],
+          cluster integer
+        )
+        """
+    )
+    points = {
+        1: [0, 0],
+        2: [0, 1],
+        3: [1, 0],
+        4: [10, 10],
+        5: [10, 11],
+        6: [11, 10],
+    }
+    db.executemany(
+        "insert into v(rowid, a, b, c, cluster) values (?, ?, ?, vec_int8(?), -1)",
+        [
+            (rowid, _f32(p), _f32([x / 11 for x in p]), _int8(p))
+            for rowid, p in points.items()
+        ],
+    )
+
+    # cluster ids are arbitrary, so only the groups of rows are compared
+    def groups(k, *args, column="a"):
+        clusters = {}
+        for rowid, cluster_id in db.execute(
+            "select rowid, cluster_id from vec0_kmeans(?, ?, ?"
+            + ", ?" * len(args)
+            + ") order by rowid",
+            ["v", column, k, *args],
+        ):
+            clusters.setdefault(cluster_id, []).append(rowid)
+        return sorted(clusters.values())
+
+    assert groups(2) == [[1, 2, 3], [4, 5, 6]]
+    assert groups(2, 100) == [[1, 2, 3], [4, 5, 6]]
+    assert groups(1) == [[1, 2, 3, 4, 5, 6]]
+    # k is capped at the number of vectors
+    assert groups(100) == [[1], [2], [3], [4], [5], [6]]
+
+    assert [
+        round(row[0], 4)
+        for row in db.execute(
+            "select distance from vec0_kmeans('v', 'a', 2) order by rowid"
+        )
+    ] == [0.4714, 0.7454, 0.7454, 0.4714, 0.7454, 0.7454]
+
+    # quantized columns are clustered on their float32 values
+    assert groups(2, column="b") == [[1, 2, 3], [4, 5, 6]]
+
+    # assignments can be written back into a metadata column
+    db.execute(
+        """
+        update v set cluster = m.cluster_id
+        from vec0_kmeans('v', 'a', 2) as m
+        where v.rowid = m.rowid
+        """
+    )
+    assert (
+        db.execute(
+            "select count(distinct cluster) from v where cluster >= 0"
+        ).fetchone()[0]
+        == 2
+    )
+    assert [
+        row[0]
+        for row in db.execute(
+            "select rowid from v where cluster = (select cluster from v where rowid = 1)"
+        )
+    ] == [1, 2, 3]
+
+    db.execute("create virtual table empty using vec0(a float[2])")
+    assert db.execute("select * from vec0_kmeans('empty', 'a', 2)").fetchall() == []
+
+    db.execute("create table plain(x)")
+    with _raises("plain is not a vec0 table"):
+        db.execute("select * from vec0_kmeans('plain', 'x', 2)")
+    with _raises("v has no vector column named x"):
+        db.execute("select * from vec0_kmeans('v', 'x', 2)")
+    with _raises(
+        'vec0_kmeans() only supports float32 vector columns, "c" has int8 vectors'
+    ):
+        db.execute("select * from vec0_kmeans('v', 'c', 2)")
+    for k in [0, -1, 1.5, 65537]:
+        with _raises("k in vec0_kmeans() must be an integer between 1 and 65536"):
+            db.execute("select * from vec0_kmeans('v', 'a', ?)", [k])
+    with _raises("max_iters in vec0_kmeans() must be a positive integer"):
+        db.execute("select * from vec0_kmeans('v', 'a', 2, 0)")
+    with _raises(
+        "vec0_kmeans() requires the name of a vec0 table, a vector column, and k"
+    ):
+        db.execute("select * from vec0_kmeans('v', 'a')")
+
+
+def test_vec_pairwise():
+    db = connect(EXT_PATH)
+    db.execute(
+        "create virtual table v using vec0(a float[2], b float[2] quantize=int8, "
+        "c int8[2] distance_metric=l1)"
+    )
+    points = {1: [0, 0], 2: [0, 1], 3: [3, 4], 4: [0, 0.5]}
+    db.executemany(
+        "insert into v(rowid, a, b, c) values (?, ?, ?, vec_int8(?))",
+        [(rowid, _f32(p), _f32(p), _int8([int(x) for x in p])) for rowid, p in points.items()],
+    )
+    db.execute("insert into v(rowid) values (5)")
+
+    def pairs(column, threshold):
+        return execute_all(
+            db,
+            "select rowid_a, rowid_b, round(distance, 2) as d "
+            "from vec_pairwise('v', ?, ?) order by rowid_a, rowid_b",
+            [column, threshold],
+        )
+
+    assert pairs("a", 1) == [
+        {"rowid_a": 1, "rowid_b": 2, "d": 1.0},
+        {"rowid_a": 1, "rowid_b": 4, "d": 0.5},
+        {"rowid_a": 2, "rowid_b": 4, "d": 0.5},
+    ]
+    assert len(pairs("a", 100)) == 6
+    assert pairs("a", -1) == []
+    # quantized columns are compared on the vectors they return
+    assert [(r["rowid_a"], r["rowid_b"]) for r in pairs("b", 0.6)] == [(1, 4), (2, 4)]
+    # with the column's distance metric
+    assert pairs("c", 1) == [
+        {"rowid_a": 1, "rowid_b": 2, "d": 1.0},
+        {"rowid_a": 1, "rowid_b": 4, "d": 0.0},
+        {"rowid_a": 2, "rowid_b": 4, "d": 1.0},
+    ]
+
+    # more rows than fit in one tile
+    db.execute("create virtual table many using vec0(a float[1])")
+    db.executemany(
+        "insert into many(rowid, a) values (?, ?)",
+        [(i, _f32([i])) for i in range(1, 201)],
+    )
+    assert execute_all(
+        db, "select count(*) as n from vec_pairwise('many', 'a', 1.5)"
+    ) == [{"n": 199}]
+
+    db.execute("create table plain(x)")
+    with _raises("plain is not a vec0 table"):
+        db.execute("select * from vec_pairwise('plain', 'x', 1)")
+    with _raises("v has no vector column named x"):
+        db.execute("select * from vec_pairwise('v', 'x', 1)")
+    with _raises("threshold in vec_pairwise() must be a number"):
+        db.execute("select * from vec_pairwise('v', 'a', '1')")
+    with _raises(
+        "vec_pairwise() requires the name of a vec0 table, a vector column, "
+        "and a threshold"
+    ):
+        db.execute("select * from vec_pairwise('v', 'a')")
+
+
+def test_vec0_export(tmp_path):
+    db = connect(EXT_PATH)
+    db.execute(
+        "create virtual table v using vec0(user_id integer partition key, a float[2], b int8[3], +note text, genre text)"
+    )
+    for i in [3, 1, 2]:
+        db.execute(
+            "insert into v(rowid, user_id, a, b, note, genre) values (?, ?, ?, vec_int8(?), ?, 'x')",
+            [i, i % 2, _f32([i, i / 2]), f"[{i}, -{i}, 0]", f"note {i}"],
+        )
+    export = lambda *args: db.execute(
+        f"select vec0_export({spread_args(args)})", args
+    ).fetchone()[0]
+
+    def read_npy(path):
+        data = open(path, "rb").read()
+        header_length = struct.unpack("<H", data[8:10])[0]
+        return data[10 : 10 + header_length].decode().strip(), data[10 + header_length :]
+
+    # rows are exported in primary key order
+    path = str(tmp_path / "a.npy")
+    assert export("v", path, "npy") == 3
+    assert read_npy(path) == (
+        "{'descr': '<f4', 'fortran_order': False, 'shape': (3, 2), }",
+        _f32([1, 0.5, 2, 1, 3, 1.5]),
+    )
+    assert export("v", path, "npy", "b") == 3
+    assert read_npy(path) == (
+        "{'descr': '|i1', 'fortran_order': False, 'shape': (3, 3), }",
+        _int8([1, -1, 0, 2, -2, 0, 3, -3, 0]),
+    )
+
+    path = str(tmp_path / "v.jsonl")
+    assert export("v", path, "jsonl") == 3
+    assert [json.loads(line) for line in open(path)] == [
+        {"rowid": i, "user_id": i % 2, "a": [i, i / 2], "b": [i, -i, 0], "note": f"note {i}", "genre": "x"}
+        for i in [1, 2, 3]
+    ]
+
+    db.execute("create virtual table empty using vec0(a float[4])")
+    path = str(tmp_path / "empty.npy")
+    assert export("empty", path, "npy") == 0
+    assert read_npy(path) == (
+        "{'descr': '<f4', 'fortran_order': False, 'shape': (0, 4), }",
+        b"",
+    )
+
+    with _raises("missing is not a vec0 table"):
+        export("missing", path, "npy")
+    with _raises("vec0_export() format must be 'npy' or 'jsonl'"):
+        export("v", path, "csv")
+    with _raises("v has no vector column named note"):
+        export("v", path, "npy", "note")
+    with _raises("vec0_export() column can only be given for 'npy' exports"):
+        export("v", path, "jsonl", "a")
+    with _raises(f"vec0_export() could not open {tmp_path}"):
+        export("v", str(tmp_path), "npy")
+    with _raises("vec0_export() takes 3 or 4 arguments"):
+        export("v", path)
+
+
+def faiss_header(d, n):
+    return struct.pack("<iqqqBi", d, n, 1 << 20, 1 << 20, 1, 1)
+
+
+def to_faiss_flat(vectors, ids=None):
+    # faiss::write_index() of an IndexFlatL2, optionally in an IndexIDMap2
+    header = lambda n: faiss_header(len(vectors[0]), n)
+
+    values = [x for vector in vectors for x in vector]
+    flat = b"IxF2" + header(len(vectors)) + struct.pack("<q", len(values)) + _f32(values)
+    if ids is None:
+        return flat
+    return (
+        b"IxM2"
+        + header(len(vectors))
+        + flat
+        + struct.pack("<q%dq" % len(ids), len(ids), *ids)
+    )
+
+
+def to_faiss_ivf_flat(centroids, lists):
+    # faiss::write_index() of an IndexIVFFlat, lists are [(id, vector), ...]
+    d = len(centroids[0])
+    n = sum(len(items) for items in lists)
+    sizes = [len(items) for items in lists]
+    index = (
+        b"IwFl"
+        + faiss_header(d, n)
+        + struct.pack("<qq", len(centroids), 1)
+        + to_faiss_flat(centroids)
+        # no direct map
+        + b"\x00"
+        + struct.pack("<q", 0)
+        + b"ilar"
+        + struct.pack("<qq", len(lists), d * 4)
+        + b"full"
+        + struct.pack("<q%dq" % len(sizes), len(sizes), *sizes)
+    )
+    for items in lists:
+        if items:
+            index += b"".join(_f32(vector) for _, vector in items)
+            index += struct.pack("<%dq" % len(items), *[id for id, _ in items])
+    return index
+
+
+def test_vec0_set_key():
+    db = connect(EXT_PATH)
+    key = bytes(range(32))
+    assert db.execute("select vec0_set_key('t', ?)", [key]).fetchone()[0] is None
+    db.execute("create virtual table t using vec0(a float[2], encrypt='aes-gcm')")
+    db.execute("insert into t(rowid, a) values (1, '[1, 2]')")
+    db.execute("select vec0_set_key('main.t', null)")
+    with _raises("t is encrypted, set its key with vec0_set_key() first"):
+        db.execute("select a from t")
+    with _raises("vec0_set_key() key isn't the key t was created with"):
+        db.execute("select vec0_set_key('t', ?)", [b"\x01" * 32])
+    db.execute("select vec0_set_key('t', ?)", [key])
+    assert db.execute("select a from t").fetchone()[0] == _f32([1, 2])
+
+    with _raises("vec0_set_key() key must be a 32 byte BLOB or NULL"):
+        db.execute("select vec0_set_key('t', ?)", [b"short"])
+    with _raises("vec0_set_key() table must be a table name"):
+        db.execute("select vec0_set_key(1, ?)", [key])
+    db.execute("create virtual table plain using vec0(a float[2])")
+    with _raises("vec0_set_key() plain isn't an encrypt='aes-gcm' table"):
+        db.execute("select vec0_set_key('plain', ?)", [key])
+    db.execute("create table regular(x)")
+    with _raises("vec0_set_key() regular is not a vec0 table"):
+        db.execute("select vec0_set_key('regular', ?)", [key])
+
+
+def test_vec0_set_distance_handler():
+    with _raises("vec0_set_distance_handler() can only be called through its C API"):
+        db.execute("select vec0_set_distance_handler(?)", [None])
+
+
+def test_vec0_set_key_handler():
+    # like vec0_set_progress_handler(), only its C API can set a handler
+    with _raises("vec0_set_key_handler() can only be called through its C API"):
+        db.execute("select vec0_set_key_handler(?)", [None])
+
+
+def test_vec0_set_progress_handler():
+    # handlers are C function pointers, so SQL can only reach the error path
+    for arg in [None, 1, "handler", b"\x00" * 8]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=r"^vec0_set_progress_handler\(\) can only be called through its C API$",
+        ):
+            db.execute("select vec0_set_progress_handler(?)", [arg])
+
+
+def test_vec0_set_slow_query_handler():
+    with _raises("vec0_set_slow_query_handler() can only be called through its C API"):
+        db.execute("select vec0_set_slow_query_handler(?)", [None])
+
+
+def test_vec0_stats(tmp_path):
+    db = connect(EXT_PATH, str(tmp_path / "stats.db"))
+    db.isolation_level = None
+    stats = lambda *args: json.loads(
+        db.execute(f"select vec0_stats({spread_args(args)})", args).fetchone()[0]
+    )
+    assert stats() == {
+        "queries": 0,
+        "chunks_scanned": 0,
+        "rows_compared": 0,
+        "bytes_read": 0,
+    }
+
+    db.execute(
+        "create virtual table v using vec0(a float[2], genre text, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, genre) values (?, ?, ?)",
+        [(i, _f32([i, i]), "odd" if i % 2 else "even") for i in range(1, 41)],
+    )
+    db.execute("delete from v where rowid = 40")
+    # only KNN queries are counted
+    db.execute("select * from v where rowid = 1").fetchall()
+    db.execute("select count(*) from v").fetchall()
+    assert stats()["queries"] == 0
+
+    # 5 chunks of 8 float[2] vectors, 64 bytes each
+    knn = "select rowid from v where a match '[3.1, 3.1]' and k = 3"
+    db.execute(knn).fetchall()
+    assert stats() == {
+        "queries": 1,
+        "chunks_scanned": 5,
+        "rows_compared": 39,
+        "bytes_read": 320,
+    }
+    db.execute(knn + " and genre = 'odd'").fetchall()
+    assert stats("reset") == {
+        "queries": 2,
+        "chunks_scanned": 10,
+        "rows_compared": 59,
+        "bytes_read": 640,
+    }
+    assert stats()["queries"] == 0
+
+    # cached chunks are scanned, but not read again
+    db.execute("select vec_cache_size(1024)")
+    db.execute(knn).fetchall()
+    db.execute(knn).fetchall()
+    assert stats() == {
+        "queries": 2,
+        "chunks_scanned": 10,
+        "rows_compared": 78,
+        "bytes_read": 320,
+    }
+
+    with _raises("vec0_stats() only takes 'reset' as an argument"):
+        stats("clear")
+    with _raises("vec0_stats() takes at most 1 argument"):
+        stats("reset", "reset")
+
+
+def test_vec0_migrate_from_vss():
+    db = connect(EXT_PATH)
+    # the shadow tables of a sqlite-vss "vss0(a(2), b(3))" table
+    db.execute("create table old_index(idx)")
+    db.execute("create table old_data(x)")
+    db.execute(
+        "insert into old_index(rowid, idx) values (0, ?), (1, ?)",
+        [
+            to_faiss_flat([[1, 2], [3, 4]], ids=[5, 9]),
+            to_faiss_flat([[30, 40, 50], [10, 20, 30]], ids=[9, 5]),
+        ],
+    )
+    db.execute("create virtual table v using vec0(a float[2], b float[3])")
+    assert execute_all(db, "select vec0_migrate_from_vss('old', 'v') as rows") == [
+        {"rows": 2}
+    ]
+    assert execute_all(
+        db, "select rowid, vec_to_json(a) as a, vec_to_json(b) as b from v"
+    ) == [
+        {"rowid": 5, "a": "[1.000000,2.000000]", "b": "[10.000000,20.000000,30.000000]"},
+        {"rowid": 9, "a": "[3.000000,4.000000]", "b": "[30.000000,40.000000,50.000000]"},
+    ]
+
+    with _raises("could not insert rowid 5 into v: UNIQUE constraint failed on v primary key"):
+        db.execute("select vec0_migrate_from_vss('old', 'v')")
+    with _raises("nope is not a sqlite-vss table, it has no nope_index shadow table"):
+        db.execute("select vec0_migrate_from_vss('nope', 'v')")
+    with _raises("nope is not a vec0 table"):
+        db.execute("select vec0_migrate_from_vss('old', 'nope')")
+    db.execute("create virtual table w using vec0(a float[2])")
+    with _raises("old has more vector columns than w"):
+        db.execute("select vec0_migrate_from_vss('old', 'w')")
+    db.execute("create virtual table w2 using vec0(a float[3], b float[3])")
+    with _raises("w2 column a must be float[2] to hold the vectors of old"):
+        db.execute("select vec0_migrate_from_vss('old', 'w2')")
+
+    db.execute("create table bad_index(idx)")
+    db.execute("insert into bad_index values (?)", [b"IwPQ" + bytes(32)])
+    with _raises(
+        "could not read the a index of bad: FAISS index type 'IwPQ' is not supported, only Flat and IVFFlat indexes can be read"
+    ):
+        db.execute("select vec0_migrate_from_vss('bad', 'w')")
+    db.execute("update bad_index set idx = ?", [to_faiss_flat([[1, 2]], ids=[1])[:-4]])
+    with _raises("could not read the a index of bad: FAISS index is truncated"):
+        db.execute("select vec0_migrate_from_vss('bad', 'w')")
+
+    # flat indexes without an id map number their rows from 0
+    db.execute("update bad_index set idx = ?", [to_faiss_flat([[1, 2], [3, 4]])])
+    db.execute("select vec0_migrate_from_vss('bad', 'w')")
+    assert execute_all(db, "select rowid, vec_to_json(a) as a from w") == [
+        {"rowid": 0, "a": "[1.000000,2.000000]"},
+        {"rowid": 1, "a": "[3.000000,4.000000]"},
+    ]
+
+    # IVFFlat indexes keep their vectors in their inverted lists
+    db.execute(
+        "update bad_index set idx = ?",
+        [to_faiss_ivf_flat([[0, 0], [5, 5]], [[(3, [1, 0])], [(4, [5, 6])]])],
+    )
+    db.execute("create virtual table w3 using vec0(a float[2])")
+    db.execute("select vec0_migrate_from_vss('bad', 'w3')")
+    assert execute_all(db, "select rowid, vec_to_json(a) as a from w3") == [
+        {"rowid": 3, "a": "[1.000000,0.000000]"},
+        {"rowid": 4, "a": "[5.000000,6.000000]"},
+    ]
+
+
+def test_vec0_copy():
+    db = connect(EXT_PATH)
+    db.execute("attach database ':memory:' as backup")
+    db.execute(
+        "create virtual table docs using vec0(a float[2] index=hnsw, tag text, +note text, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into docs(rowid, a, tag, note) values (?, ?, ?, ?)",
+        [(i, _f32([i, 1]), f"tag{i % 2}", f"note {i}") for i in range(1, 21)],
+    )
+    assert execute_all(db, "select vec0_copy('main.docs', 'backup.docs') as rows") == [
+        {"rows": 20}
+    ]
+    knn = "select rowid, distance, tag, note from {} where a match ? and k = 3 and tag = 'tag1'"
+    assert execute_all(db, knn.format("backup.docs"), [_f32([4, 1])]) == execute_all(
+        db, knn.format("main.docs"), [_f32([4, 1])]
+    )
+    assert [
+        row[0]
+        for row in db.execute(
+            "select name from backup.sqlite_master where type = 'table' and name like 'docs%' order by 1"
+        )
+    ] == [
+        "docs",
+        "docs_auxiliary",
+        "docs_chunks",
+        "docs_hnsw00",
+        "docs_info",
+        "docs_metadatachunks00",
+        "docs_metadatatext00",
+        "docs_rowids",
+        "docs_vector_chunks00",
+    ]
+    # a schema defaults to main
+    assert db.execute("select vec0_copy('docs', 'docs_copy')").fetchone()[0] == 20
+    assert db.execute("select count(*) from main.docs_copy").fetchone()[0] == 20
+
+    with _raises("nope is not a vec0 table"):
+        db.execute("select vec0_copy('nope', 'x')")
+    with _raises('vec0_copy() could not create docs_copy: table "docs_copy" already exists'):
+        db.execute("select vec0_copy('docs', 'docs_copy')")
+    with _raises('vec0_copy() could not create nodb.x: unknown database "nodb"'):
+        db.execute("select vec0_copy('docs', 'nodb.x')")
+    with _raises("vec0_copy() table names must be TEXT"):
+        db.execute("select vec0_copy('docs', null)")
+
+    # shadow tables that don't match this version's fail, and leave nothing behind
+    db.execute("alter table docs_chunks add column extra")
+    with _raises("vec0_copy() could not copy main.docs_chunks into main.x_chunks: "):
+        db.execute("select vec0_copy('docs', 'x')")
+    assert db.execute("select count(*) from sqlite_master where name like 'x%'").fetchone()[0] == 0
+    db.execute(
+        "update docs_info set value = 9 where key in ('CREATE_VERSION_MAJOR', 'CREATE_VERSION_MINOR', 'CREATE_VERSION_PATCH')"
+    )
+    with _raises(
+        "vec0_copy() main.docs was created by sqlite-vec v9.9.9, which is newer than this version"
+    ):
+        db.execute("select vec0_copy('docs', 'x')")
+
+
+def test_vec0_serialize():
+    db = connect(EXT_PATH)
+    db.execute("create virtual table v using vec0(a float[2], tag text, +note text)")
+    db.execute(
+        "insert into v(rowid, a, tag, note) values (1, ?, 'x', 'one'), (2, ?, 'y', null)",
+        [_f32([1, 2]), _f32([3, 4])],
+    )
+    snapshot = db.execute("select vec0_serialize('v')").fetchone()[0]
+    assert snapshot[:8] == b"VEC0SNAP"
+    assert db.execute("select vec0_serialize('v')").fetchone()[0] == snapshot
+
+    with _raises("nope is not a vec0 table"):
+        db.execute("select vec0_serialize('nope')")
+    with _raises("vec0_serialize() table name must be TEXT"):
+        db.execute("select vec0_serialize(null)")
+
+
+def test_vec0_deserialize():
+    db = connect(EXT_PATH)
+    db.execute("create virtual table v using vec0(a float[2], tag text, +note text)")
+    db.execute(
+        "insert into v(rowid, a, tag, note) values (1, ?, 'x', 'one'), (2, ?, 'y', null)",
+        [_f32([1, 2]), _f32([3, 4])],
+    )
+    snapshot = db.execute("select vec0_serialize('v')").fetchone()[0]
+
+    other = connect(EXT_PATH)
+    assert execute_all(other, "select vec0_deserialize(?) as name", [snapshot]) == [
+        {"name": "v"}
+    ]
+    assert execute_all(
+        other, "select rowid, vec_to_json(a) as a, tag, note from v"
+    ) == [
+        {"rowid": 1, "a": "[1.000000,2.000000]", "tag": "x", "note": "one"},
+        {"rowid": 2, "a": "[3.000000,4.000000]", "tag": "y", "note": None},
+    ]
+    assert other.execute("select vec0_serialize('v')").fetchone()[0] == snapshot
+    assert execute_all(
+        other, "select vec0_deserialize(?, 'copy') as name", [snapshot]
+    ) == [{"name": "copy"}]
+    assert other.execute("select count(*) from copy").fetchone()[0] == 2
+
+    with _raises('vec0_deserialize() could not create v: table "v" already exists'):
+        other.execute("select vec0_deserialize(?)", [snapshot])
+    with _raises("vec0_deserialize() argument isn't a vec0 snapshot"):
+        other.execute("select vec0_deserialize(?, 'x')", [b"VEC0"])
+    with _raises("vec0_deserialize() snapshot is truncated or corrupt"):
+        other.execute("select vec0_deserialize(?, 'x')", [snapshot[:-1]])
+    with _raises(
+        "vec0_deserialize() snapshot version 2 isn't supported, this build of sqlite-vec reads version 1"
+    ):
+        other.execute(
+            "select vec0_deserialize(?, 'x')",
+            [snapshot[:8] + struct.pack("<I", 2) + snapshot[12:]],
+        )
+    with _raises("vec0_deserialize() snapshot must be a BLOB and table a TEXT"):
+        other.execute("select vec0_deserialize('v')")
+    with _raises("vec0_deserialize() takes 1 or 2 arguments"):
+        other.execute("select vec0_deserialize(?, 'x', 'y')", [snapshot])
+    # failed snapshots leave no table behind
+    assert (
+        other.execute("select count(*) from sqlite_master where name like 'x%'").fetchone()[0]
+        == 0
+    )
+
+
+def test_vec0_changeset():
+    db = connect(EXT_PATH)
+    db.execute("create virtual table v using vec0(a float[2], +note text, changelog=on)")
+    db.execute(
+        "insert into v(rowid, a, note) values (1, ?, 'one'), (2, ?, 'two')",
+        [_f32([1, 2]), _f32([3, 4])],
+    )
+    changeset = db.execute("select vec0_changeset('v')").fetchone()[0]
+    assert changeset[:8] == b"VEC0CSET"
+    assert db.execute("select vec0_changeset('v', 1)").fetchone()[0] != changeset
+
+    with _raises("nope is not a vec0 table"):
+        db.execute("select vec0_changeset('nope')")
+    with _raises("vec0_changeset() table name must be TEXT"):
+        db.execute("select vec0_changeset(null)")
+    with _raises("vec0_changeset() takes 1 or 2 arguments"):
+        db.execute("select vec0_changeset('v', 1, 2)")
+
+
+def test_vec0_apply_changeset():
+    db = connect(EXT_PATH)
+    db.execute("create virtual table v using vec0(a float[2], +note text, changelog=on)")
+    db.execute("insert into v(rowid, a, note) values (1, ?, 'one')", [_f32([1, 2])])
+    changeset = db.execute("select vec0_changeset('v')").fetchone()[0]
+
+    other = connect(EXT_PATH)
+    other.execute("create virtual table v using vec0(a float[2], +note text)")
+    assert other.execute("select vec0_apply_changeset('v', ?)", [changeset]).fetchone()[0] == 1
+    assert execute_all(other, "select rowid, vec_to_json(a) as a, note from v") == [
+        {"rowid": 1, "a": "[1.000000,2.000000]", "note": "one"}
+    ]
+    assert other.execute("select vec0_apply_changeset('v', ?)", [changeset]).fetchone()[0] == 0
+
+    with _raises("vec0_apply_changeset() table must be TEXT and changeset a BLOB"):
+        other.execute("select vec0_apply_changeset('v', 'x')")
+    with _raises(
+        "vec0_apply_changeset() changeset version 2 isn't supported, this build of sqlite-vec reads version 1"
+    ):
+        other.execute(
+            "select vec0_apply_changeset('v', ?)",
+            [changeset[:8] + struct.pack("<I", 2) + changeset[12:]],
+        )
+
+
+def test_vec_topk():
+    db = connect(EXT_PATH)
+    db.execute("create table documents(id integer primary key, embedding blob)")
+    db.executemany(
+        "insert into documents values (?, ?)",
+        [(i, _f32([i, -i])) for i in range(1, 11)] + [(11, None)],
+    )
+    vec_topk = lambda *args: execute_all(
+        db, "select rowid, distance from vec_topk(?, ?, ?, ?)", args
+    )
+    assert vec_topk("[3.2, -3.2]", 3, "documents", "embedding") == [
+        {"rowid": 3, "distance": pytest.approx(0.2828427)},
+        {"rowid": 4, "distance": pytest.approx(1.1313708)},
+        {"rowid": 2, "distance": pytest.approx(1.6970563)},
+    ]
+    assert vec_topk("[3.2, -3.2]", 0, "documents", "embedding") == []
+    assert len(vec_topk("[3.2, -3.2]", 100, "documents", "embedding")) == 10
+    assert execute_all(
+        db,
+        "select rowid from vec_topk('[1, 0]', 2, 'documents', 'embedding', 'dot')",
+    ) == [{"rowid": 10}, {"rowid": 9}]
+
+    # the query vector can come from another table in the join
+    db.execute("create table queries(q)")
+    db.execute("insert into queries values ('[1, -1]'), ('[9.9, -9.9]')")
+    assert execute_all(
+        db,
+        """
+        select queries.q, t.rowid
+        from queries, vec_topk(queries.q, 2, 'documents', 'embedding') as t
+        order by 1, t.distance
+        """,
+    ) == [
+        {"q": "[1, -1]", "rowid": 1},
+        {"q": "[1, -1]", "rowid": 2},
+        {"q": "[9.9, -9.9]", "rowid": 10},
+        {"q": "[9.9, -9.9]", "rowid": 9},
+    ]
+
+    db.execute("create table fingerprints(fp blob)")
+    db.execute("insert into fingerprints values (x'f0'), (x'0f'), (x'f1')")
+    assert execute_all(
+        db,
+        "select rowid, distance from vec_topk(vec_bit(x'f0'), 2, 'fingerprints', 'fp', 'hamming')",
+    ) == [{"rowid": 1, "distance": 0.0}, {"rowid": 3, "distance": 1.0}]
+
+    with _raises(
+        "vec_topk() requires a query vector, k, a table name, and a column name"
+    ):
+        db.execute("select * from vec_topk('[1, 1]', 2, 'documents')")
+    with _raises("k in vec_topk() must be an integer greater than or equal to 0."):
+        vec_topk("[1, 1]", -1, "documents", "embedding")
+    with _raises("k in vec_topk() too large, provided 4097 and the limit is 4096"):
+        vec_topk("[1, 1]", 4097, "documents", "embedding")
+    with _raises("vec_topk() error on missing.embedding: no such table: missing"):
+        vec_topk("[1, 1]", 2, "missing", "embedding")
+    with _raises("vec_topk() error on documents.embedding: Vector dimension mistmatch"):
+        vec_topk("[1, 1, 1]", 2, "documents", "embedding")
+    with _raises("Unknown distance metric for vec_topk(): manhattan"):
+        db.execute(
+            "select * from vec_topk('[1, 1]', 2, 'documents', 'embedding', 'manhattan')"
+        )
+
+
+def test_vec_rerank_topk():
+    db = connect(EXT_PATH)
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[2],
+          b float[2] distance_metric=cosine,
+          c bit[8],
+          d float[2] quantize=int8
+        )
+        """
+    )
+    for rowid, vector in [(1, [1, 0]), (2, [0, 1]), (3, [1, 1]), (4, [-1, 0])]:
+        db.execute(
+            "insert into v(rowid, a, b, c, d) values (?, ?, ?, vec_bit(?), ?)",
+            [rowid, _f32(vector), _f32(vector), bytes([rowid]), _f32(vector)],
+        )
+    db.execute("create virtual table f using fts5(body)")
+    db.executemany(
+        "insert into f(rowid, body) values (?, ?)",
+        [(1, "apple"), (2, "pear"), (3, "apple pie"), (4, "apple tart"), (9, "apple")],
+    )
+    rerank = lambda *args: execute_all(
+        db,
+        f"select rowid, distance from vec_rerank_topk(?, ?, ?, ?{', ?' * (len(args) - 4)})",
+        args,
+    )
+
+    # candidates from full-text search, rowid 9 has no vector and is skipped
+    assert execute_all(
+        db,
+        """
+        select rowid, distance
+        from vec_rerank_topk(
+          '[1, 0.1]',
+          (select json_group_array(rowid) from f where f match 'apple'),
+          'v',
+          'a'
+        )
+        """,
+    ) == [
+        {"rowid": 1, "distance": pytest.approx(0.1)},
+        {"rowid": 3, "distance": pytest.approx(0.9)},
+        {"rowid": 4, "distance": pytest.approx(2.0024984)},
+    ]
+    assert rerank("[1, 0.1]", "[4, 3, 1]", "v", "a", 2) == [
+        {"rowid": 1, "distance": pytest.approx(0.1)},
+        {"rowid": 3, "distance": pytest.approx(0.9)},
+    ]
+    assert rerank("[1, 0.1]", "[4, 3, 1]", "v", "a", 0) == []
+    assert rerank("[1, 0.1]", "[]", "v", "a") == []
+
+    # the column's distance metric is used
+    assert rerank("[1, 0]", "[2, 3]", "v", "b") == [
+        {"rowid": 3, "distance": pytest.approx(0.2928932)},
+        {"rowid": 2, "distance": pytest.approx(1.0)},
+    ]
+    assert execute_all(
+        db,
+        "select rowid, distance from vec_rerank_topk(vec_bit(x'01'), '[1, 2, 3]', 'v', 'c')",
+    ) == [
+        {"rowid": 1, "distance": 0.0},
+        {"rowid": 3, "distance": 1.0},
+        {"rowid": 2, "distance": 2.0},
+    ]
+    assert [row["rowid"] for row in rerank("[-1, 0]", "[1, 4]", "v", "d")] == [4, 1]
+
+    with _raises("vec_rerank_topk() candidates must be a JSON array of integer rowids"):
+        rerank("[1, 0]", '[1, "two"]', "v", "a")
+    with _raises("vec_rerank_topk() candidates must be a JSON array of integer rowids"):
+        rerank("[1, 0]", "not json", "v", "a")
+    with _raises(
+        'vec_rerank_topk() query vector has 3 dimensions, but the "a" column has 2 dimensions'
+    ):
+        rerank("[1, 0, 0]", "[1]", "v", "a")
+    with _raises(
+        'vec_rerank_topk() query vector has type float32, but the "c" column stores bit vectors'
+    ):
+        rerank("[1, 0]", "[1]", "v", "c")
+    with _raises("f is not a vec0 table"):
+        rerank("[1, 0]", "[1]", "f", "body")
+    with _raises("v has no vector column named e"):
+        rerank("[1, 0]", "[1]", "v", "e")
+    with _raises("k in vec_rerank_topk() must be an integer greater than or equal to 0."):
+        rerank("[1, 0]", "[1]", "v", "a", -1)
+    with _raises(
+        "vec_rerank_topk() requires a query vector, a list of candidate rowids, a table name, and a column name"
+    ):
+        db.execute("select * from vec_rerank_topk('[1, 1]', '[1]', 'v')")
+
+
+def test_vec_arrow_each():
+    pa = pytest.importorskip("pyarrow")
+    db = connect(EXT_PATH)
+
+    def to_arrow(table):
+        sink = pa.BufferOutputStream()
+        with pa.ipc.new_stream(sink, table.schema) as writer:
+            for batch in table.to_batches(max_chunksize=2):
+                writer.write_batch(batch)
+        return sink.getvalue().to_pybytes()
+
+    table = pa.table(
+        {
+            "id": pa.array([1, 2, 3], pa.int64()),
+            "embedding": pa.array(
+                [[1, 2], [3, 4], [5, 6]], pa.list_(pa.float32(), 2)
+            ),
+            "label": ["a", None, 'c"d'],
+            "score": [0.5, 1.25, None],
+            "seen": [True, False, True],
+        }
+    )
+    stream = to_arrow(table)
+    assert execute_all(
+        db, "select rowid, id, vector, metadata from vec_arrow_each(?)", [stream]
+    ) == [
+        {
+            "rowid": 0,
+            "id": 1,
+            "vector": _f32([1, 2]),
+            "metadata": '{"label":"a","score":0.5,"seen":true}',
+        },
+        {
+            "rowid": 1,
+            "id": 2,
+            "vector": _f32([3, 4]),
+            "metadata": '{"label":null,"score":1.25,"seen":false}',
+        },
+        {
+            "rowid": 2,
+            "id": 3,
+            "vector": _f32([5, 6]),
+            "metadata": '{"label":"c\\"d","score":null,"seen":true}',
+        },
+    ]
+
+    # the vector and id columns can be picked by name
+    assert execute_all(
+        db,
+        "select id, vec_to_json(vector) as vector from vec_arrow_each(?, 'embedding', 'label')",
+        [stream],
+    ) == [
+        {"id": "a", "vector": "[1.000000,2.000000]"},
+        {"id": None, "vector": "[3.000000,4.000000]"},
+        {"id": 'c"d', "vector": "[5.000000,6.000000]"},
+    ]
+
+    # float64 lists become float32 vectors, int8 lists int8 vectors
+    table = pa.table(
+        {
+            "a": pa.array([[1.5, 2.25]], pa.list_(pa.float64(), 2)),
+            "b": pa.array([[-128, 127]], pa.list_(pa.int8(), 2)),
+        }
+    )
+    assert execute_all(
+        db,
+        "select id, vec_type(vector) as type, vec_to_json(vector) as json, metadata from vec_arrow_each(?)",
+        [to_arrow(table)],
+    ) == [{"id": None, "type": "float32", "json": "[1.500000,2.250000]", "metadata": "{}"}]
+    assert execute_all(
+        db,
+        "select vec_type(vector) as type, vec_to_json(vector) as json from vec_arrow_each(?, 'b')",
+        [to_arrow(table)],
+    ) == [{"type": "int8", "json": "[-128,127]"}]
+
+    with _raises("Arrow stream has no vector column named c"):
+        execute_all(db, "select * from vec_arrow_each(?, 'c')", [to_arrow(table)])
+    with _raises("Arrow column id must be a FixedSizeList of float32, float64 or int8 values"):
+        execute_all(
+            db,
+            "select * from vec_arrow_each(?)",
+            [to_arrow(pa.table({"id": pa.array([["x"]], pa.list_(pa.string(), 1))}))],
+        )
+
+
+def test_vec_arrow_each_errors():
+    db = connect(EXT_PATH)
+    vec_arrow_each = lambda *args: execute_all(
+        db, "select * from vec_arrow_each(?)", args
+    )
+    with _raises("Arrow stream must start with a schema message"):
+        vec_arrow_each(b"")
+    with _raises("Arrow stream must start with a schema message"):
+        vec_arrow_each(b"\xff\xff\xff\xff\x00\x00\x00\x00")
+    with _raises("Arrow stream is truncated"):
+        vec_arrow_each(b"\xff\xff\xff\xff\x10")
+    with _raises("Arrow stream is truncated"):
+        vec_arrow_each(b"\xff\xff\xff\xff\x10\x00\x00\x00")
+    with _raises("Arrow stream has an invalid message"):
+        vec_arrow_each(b"\xff\xff\xff\xff\x04\x00\x00\x00\xff\xff\x00\x00")
+    with _raises("input argument is required"):
+        db.execute("select * from vec_arrow_each")
+
+
+def test_vec_faiss_each(tmp_path):
+    db = connect(EXT_PATH, extra_entrypoint="sqlite3_vec_numpy_init")
+    vec_faiss_each = lambda *args: execute_all(
+        db,
+        "select rowid, id, vec_to_json(vector) as vector, list from vec_faiss_each(%s)"
+        % ", ".join("?" * len(args)),
+        args,
+    )
+    assert vec_faiss_each(to_faiss_flat([[1, 2], [3, 4]])) == [
+        {"rowid": 0, "id": 0, "vector": "[1.000000,2.000000]", "list": None},
+        {"rowid": 1, "id": 1, "vector": "[3.000000,4.000000]", "list": None},
+    ]
+    assert vec_faiss_each(to_faiss_flat([[1, 2]], ids=[42])) == [
+        {"rowid": 0, "id": 42, "vector": "[1.000000,2.000000]", "list": None},
+    ]
+
+    ivf = to_faiss_ivf_flat(
+        [[0, 0], [10, 10], [20, 20]],
+        [[(7, [1, 1]), (8, [0, 1])], [], [(3, [20, 21])]],
+    )
+    assert vec_faiss_each(ivf) == [
+        {"rowid": 0, "id": 7, "vector": "[1.000000,1.000000]", "list": 0},
+        {"rowid": 1, "id": 8, "vector": "[0.000000,1.000000]", "list": 0},
+        {"rowid": 2, "id": 3, "vector": "[20.000000,21.000000]", "list": 2},
+    ]
+    assert vec_faiss_each(ivf, "centroids") == [
+        {"rowid": 0, "id": 0, "vector": "[0.000000,0.000000]", "list": 0},
+        {"rowid": 1, "id": 1, "vector": "[10.000000,10.000000]", "list": 1},
+        {"rowid": 2, "id": 2, "vector": "[20.000000,20.000000]", "list": 2},
+    ]
+
+    path = tmp_path / "index.faiss"
+    path.write_bytes(ivf)
+    assert execute_all(
+        db, "select id from vec_faiss_each(vec_npy_file(?))", [str(path)]
+    ) == [{"id": 7}, {"id": 8}, {"id": 3}]
+
+    with _raises("FAISS index has no centroids, only IVF indexes have them"):
+        vec_faiss_each(to_faiss_flat([[1, 2]]), "centroids")
+    with _raises("vec_faiss_each() contents must be 'vectors' or 'centroids'"):
+        vec_faiss_each(ivf, "lists")
+    with _raises("FAISS index is truncated"):
+        vec_faiss_each(ivf[:-1])
+    with _raises("FAISS index type 'IHNf' is not supported, only Flat and IVFFlat indexes can be read"):
+        vec_faiss_each(b"IHNf" + faiss_header(2, 0))
+    with _raises("input argument is required"):
+        db.execute("select * from vec_faiss_each")
+
+
+def to_safetensors(tensors):
+    header = {"__metadata__": {"format": "pt"}}
+    data = b""
+    for name, (dtype, shape, buffer) in tensors.items():
+        header[name] = {
+            "dtype": dtype,
+            "shape": shape,
+            "data_offsets": [len(data), len(data) + len(buffer)],
+        }
+        data += buffer
+    header = json.dumps(header).encode()
+    return struct.pack("<Q", len(header)) + header + data
+
+
+def test_vec_safetensors_each(tmp_path):
+    db = connect(EXT_PATH, extra_entrypoint="sqlite3_vec_numpy_init")
+    vec_safetensors_each = lambda *args: execute_all(
+        db,
+        "select rowid, vec_type(vector) as type, vec_to_json(vector) as json from vec_safetensors_each(%s)"
+        % ", ".join("?" * len(args)),
+        args,
+    )
+    file = to_safetensors(
+        {
+            "embeddings": ("F32", [2, 3], _f32([1, 2, 3, 4, 5, 6])),
+            "bias": ("F64", [2], struct.pack("<2d", 1.5, 2.25)),
+            "codes": ("I8", [1, 2], _int8([-128, 127])),
+            "half": ("F16", [1, 2], struct.pack("<2H", 0x3C00, 0xC000)),
+        }
+    )
+    assert vec_safetensors_each(file) == [
+        {"rowid": 0, "type": "float32", "json": "[1.000000,2.000000,3.000000]"},
+        {"rowid": 1, "type": "float32", "json": "[4.000000,5.000000,6.000000]"},
+    ]
+    # 1D tensors are a single vector, F64 tensors become float32 vectors
+    assert vec_safetensors_each(file, "bias") == [
+        {"rowid": 0, "type": "float32", "json": "[1.500000,2.250000]"},
+    ]
+    assert vec_safetensors_each(file, "codes") == [
+        {"rowid": 0, "type": "int8", "json": "[-128,127]"},
+    ]
+    assert vec_safetensors_each(file, "half") == [
+        {"rowid": 0, "type": "float16", "json": "[1.000000,-2.000000]"},
+    ]
+
+    path = tmp_path / "model.safetensors"
+    path.write_bytes(file)
+    assert execute_all(
+        db,
+        "select rowid, vector from vec_safetensors_each(vec_npy_file(?), 'embeddings')",
+        [str(path)],
+    ) == [
+        {"rowid": 0, "vector": _f32([1, 2, 3])},
+        {"rowid": 1, "vector": _f32([4, 5, 6])},
+    ]
+
+    with _raises("safetensors file has no tensor named 'weights'"):
+        vec_safetensors_each(file, "weights")
+    with _raises("safetensors file does not contain any tensors"):
+        vec_safetensors_each(to_safetensors({}))
+
+
+def test_vec_safetensors_each_errors():
+    db = connect(EXT_PATH)
+    vec_safetensors_each = lambda *args: execute_all(
+        db, "select * from vec_safetensors_each(?)", args
+    )
+    with _raises("safetensors file is too short"):
+        vec_safetensors_each(b"")
+    with _raises("safetensors header size is invalid"):
+        vec_safetensors_each(struct.pack("<Q", 100) + b"{}")
+    with _raises("safetensors header did not start with '{'"):
+        vec_safetensors_each(struct.pack("<Q", 2) + b"[]")
+    with _raises("safetensors header is not valid JSON"):
+        vec_safetensors_each(struct.pack("<Q", 2) + b"{x")
+    with _raises("safetensors tensor a must have a F32, F64, F16, BF16 or I8 dtype"):
+        vec_safetensors_each(to_safetensors({"a": ("I32", [1], b"\x00" * 4)}))
+    with _raises("safetensors tensor a must be 1 or 2 dimensional"):
+        vec_safetensors_each(to_safetensors({"a": ("F32", [1, 1, 1], _f32([1]))}))
+    with _raises("safetensors tensor a has data_offsets that don't match its shape"):
+        vec_safetensors_each(to_safetensors({"a": ("F32", [2], _f32([1]))}))
+    with _raises("safetensors tensor a has empty rows"):
+        vec_safetensors_each(to_safetensors({"a": ("F32", [0], b"")}))
+    with _raises("input argument is required"):
+        db.execute("select * from vec_safetensors_each")
+
+
+def test_vec_static_file(tmp_path):
+    db = connect(EXT_PATH)
+    path = tmp_path / "vectors.f32"
+    path.write_bytes(_f32([1, 1, 2, 2, 3, 3, 4, 4]))
+    db.execute(
+        f"create virtual table v using vec_static_file(a float[2], path='{path}')"
+    )
+    assert execute_all(
+        db, "select rowid, distance from v where a match '[2.9, 2.9]' and k = 2"
+    ) == [
+        {"rowid": 2, "distance": pytest.approx(0.1414213, rel=1e-5)},
+        {"rowid": 1, "distance": pytest.approx(1.2727922, rel=1e-5)},
+    ]
+    assert execute_all(db, "select vec_to_json(a) as a from v where rowid = 3") == [
+        {"a": "[4.000000,4.000000]"}
+    ]
+    with _raises("table v may not be modified"):
+        db.execute("delete from v")
+
+
+import io
+import zipfile
+
+
+def to_npy(arr):
+    buf = io.BytesIO()
+    np.save(buf, arr)
+    buf.seek(0)
+    return buf.read()
+
+
+def test_vec_npy_each():
+    db = connect(EXT_PATH, extra_entrypoint="sqlite3_vec_numpy_init")
+    vec_npy_each = lambda *args: execute_all(
+        db, "select rowid, * from vec_npy_each(?)", args
+    )
+    assert vec_npy_each(to_npy(np.array([1.1, 2.2, 3.3], dtype=np.float32))) == [
+        {
+            "rowid": 0,
+            "vector": _f32([1.1, 2.2, 3.3]),
+        },
+    ]
+    assert vec_npy_each(to_npy(np.array([[1.1, 2.2, 3.3]], dtype=np.float32))) == [
+        {
+            "rowid": 0,
+            "vector": _f32([1.1, 2.2, 3.3]),
+        },
+    ]
+    assert vec_npy_each(
+        to_npy(np.array([[1.1, 2.2, 3.3], [9.9, 8.8, 7.7]], dtype=np.float32))
+    ) == [
+        {
+            "rowid": 0,
+            "vector": _f32([1.1, 2.2, 3.3]),
+        },
+        {
+            "rowid": 1,
+            "vector": _f32([9.9, 8.8, 7.7]),
+        },
+    ]
+
+    assert vec_npy_each(to_npy(np.array([], dtype=np.float32))) == []
+
+    # float64 arrays are converted to float32 vectors
+    assert vec_npy_each(to_npy(np.array([[1.5, 2.25], [3, 4]], dtype=np.float64))) == [
+        {"rowid": 0, "vector": _f32([1.5, 2.25])},
+        {"rowid": 1, "vector": _f32([3, 4])},
+    ]
+    assert execute_all(
+        db,
+        "select vec_type(vector) as type, vec_to_json(vector) as json from vec_npy_each(?)",
+        [to_npy(np.array([[-128, 0, 127]], dtype=np.int8))],
+    ) == [{"type": "int8", "json": "[-128,0,127]"}]
+
+    # npz archives yield their first array, or the one named in the 2nd argument
+    buf = io.BytesIO()
+    np.savez(
+        buf,
+        a=np.array([[1, 2]], dtype=np.float32),
+        b=np.array([[3, 4], [5, 6]], dtype=np.float32),
+    )
+    npz = buf.getvalue()
+    assert vec_npy_each(npz) == [{"rowid": 0, "vector": _f32([1, 2])}]
+    assert execute_all(
+        db, "select rowid, * from vec_npy_each(?, 'b')", [npz]
+    ) == [
+        {"rowid": 0, "vector": _f32([3, 4])},
+        {"rowid": 1, "vector": _f32([5, 6])},
+    ]
+
+
+def test_vec_npy_each_errors():
+    db = connect(EXT_PATH, extra_entrypoint="sqlite3_vec_numpy_init")
+    vec_npy_each = lambda *args: execute_all(
+        db, "select rowid, * from vec_npy_each(?)", args
+    )
+
+    full = b"\x93NUMPY\x01\x00v\x00{'descr': '<f4', 'fortran_order': False, 'shape': (2, 4), }                                                          \n\xcd\xcc\x8c?\xcd\xcc\x0c@33S@\xcd\xcc\x8c@ff\x1eA\xcd\xcc\x0cAff\xf6@33\xd3@"
 
     # EVIDENCE-OF: V03312_20150 numpy validation too short
     with _raises("numpy array too short"):
@@ -1801,7 +3799,9 @@ def test_vec_npy_each_errors():
             b"\x93NUMPY\x01\x00v\x00{'descr':                                                                                                  \n\xcd\xcc\x8c?\xcd\xcc\x0c@33S@\xcd\xcc\x8c@ff\x1eA\xcd\xcc\x0cAff\xf6@33\xd3@"
         )
 
-    with _raises("Only '<f4' values are supported in sqlite-vec numpy functions"):
+    with _raises(
+        "Only '<f4', '<f8' and '|i1' values are supported in sqlite-vec numpy functions"
+    ):
         vec_npy_each(
             b"\x93NUMPY\x01\x00v\x00{'descr': '=f4', 'fortran_order': False, 'shape': (2, 4), }                                                          \n\xcd\xcc\x8c?\xcd\xcc\x0c@33S@\xcd\xcc\x8c@ff\x1eA\xcd\xcc\x0cAff\xf6@33\xd3@"
         )
@@ -1856,6 +3856,24 @@ def test_vec_npy_each_errors():
             b"\x93NUMPY\x01\x00v\x00{'no': '<f4', 'fortran_order': False, 'shape': (2, 4), }                                                          \n\xcd\xcc\x8c?\xcd\xcc\x0c@33S@\xcd\xcc\x8c@ff\x1eA\xcd\xcc\x0cAff\xf6@33\xd3@"
         )
 
+    def npz(compression=zipfile.ZIP_STORED):
+        buf = io.BytesIO()
+        with zipfile.ZipFile(buf, "w", compression) as archive:
+            archive.writestr("a.npy", full)
+        return buf.getvalue()
+
+    assert len(vec_npy_each(npz())) == 2
+    with _raises("npz archive does not contain an array named 'b'"):
+        execute_all(db, "select * from vec_npy_each(?, 'b')", [npz()])
+    with _raises(
+        "compressed npz archives are not supported, use numpy.savez() instead of numpy.savez_compressed()"
+    ):
+        vec_npy_each(npz(zipfile.ZIP_DEFLATED))
+    with _raises("npz archive is missing its central directory"):
+        vec_npy_each(npz()[:-22])
+    with _raises("array names can only be given for npz archives"):
+        execute_all(db, "select * from vec_npy_each(?, 'a')", [full])
+
     with _raises("Error parsing numpy array: unknown extra token after value"):
         vec_npy_each(
             b"\x93NUMPY\x01\x00v\x00{'descr': '<f4' 'asdf', 'fortran_order': False, 'shape': (2, 4), }                                                          \n\xcd\xcc\x8c?\xcd\xcc\x0c@33S@\xcd\xcc\x8c@ff\x1eA\xcd\xcc\x0cAff\xf6@33\xd3@"
@@ -2173,8 +4191,8 @@ def test_vec0_knn():
         db, "select rowid from v where aaa match vec_f32(?) and k = 9", [qaaa]
     ) == [
         {"rowid": 1},
-        {"rowid": 2},  # ordering of 2 and 0 here depends on if min_idx uses < or <=
-        {"rowid": 0},  #
+        {"rowid": 0},  # 0 and 2 are the same distance away, ordered by rowid
+        {"rowid": 2},  #
         {"rowid": 3},
         {"rowid": 4},
         {"rowid": 5},
@@ -2524,6 +4542,14 @@ def test_vec0_distance_metric():
     db.execute("create virtual table v4 using vec0( a float[2] distance_metric=cosine)")
     db.execute(f"insert into v4(a) values {base}")
 
+    db.execute("create virtual table v5 using vec0( a float[2] distance_metric=dot)")
+    db.execute(f"insert into v5(a) values {base}")
+
+    db.execute("create virtual table v6 using vec0( a int8[2] distance_metric=l1)")
+    db.execute(
+        "insert into v6(a) values (vec_int8('[1, 2]')), (vec_int8('[3, 4]')), (vec_int8('[5, 6]'))"
+    )
+
     # default (L2)
     assert execute_all(
         db, "select rowid, distance from v1 where a match ? and k = 3", [q]
@@ -2557,6 +4583,76 @@ def test_vec0_distance_metric():
         {"rowid": 2, "distance": 1.9838699102401733},
         {"rowid": 1, "distance": 2},
     ]
+    # dot
+    assert execute_all(
+        db, "select rowid, distance from v5 where a match ? and k = 3", [q]
+    ) == [
+        {"rowid": 1, "distance": 5},
+        {"rowid": 2, "distance": 11},
+        {"rowid": 3, "distance": 17},
+    ]
+    assert execute_all(
+        db, "select rowid, distance from v5 where a match ? and k = 1", ["[1, 1]"]
+    ) == [
+        {"rowid": 3, "distance": -11},
+    ]
+    # l1 on int8 vectors
+    assert execute_all(
+        db,
+        "select rowid, distance from v6 where a match vec_int8(?) and k = 3",
+        [q],
+    ) == [
+        {"rowid": 1, "distance": 6},
+        {"rowid": 2, "distance": 10},
+        {"rowid": 3, "distance": 14},
+    ]
+    # wide enough for the SIMD l1 kernels, with leftover elements past the last
+    # full block of 8
+    wide = [[(7 * i * j) % 41 - 20 for j in range(11)] for i in range(1, 4)]
+    wide_q = [(5 * j) % 23 - 11 for j in range(11)]
+    for sql_type, pack, scale in [("int8", "11b", 1), ("float", "11f", 0.25)]:
+        vectors = [[x * scale for x in v] for v in wide]
+        query = [x * scale for x in wide_q]
+        transform = "vec_int8(?)" if sql_type == "int8" else "?"
+        db.execute(
+            f"create virtual table l1_{sql_type} using vec0(a {sql_type}[11] distance_metric=l1)"
+        )
+        for rowid, v in enumerate(vectors, 1):
+            db.execute(
+                f"insert into l1_{sql_type}(rowid, a) values (?, {transform})",
+                [rowid, struct.pack(pack, *v)],
+            )
+        expected = sorted(
+            (sum(abs(x - y) for x, y in zip(v, query)), rowid)
+            for rowid, v in enumerate(vectors, 1)
+        )
+        assert execute_all(
+            db,
+            f"select rowid, distance from l1_{sql_type} where a match {transform} and k = 3",
+            [struct.pack(pack, *query)],
+        ) == [{"rowid": rowid, "distance": distance} for distance, rowid in expected]
+
+    db.execute("create virtual table v7 using vec0( a bit[8] distance_metric=jaccard)")
+    db.execute(
+        "insert into v7(rowid, a) values (1, vec_bit(X'FF')), (2, vec_bit(X'0F')), (3, vec_bit(X'F1'))"
+    )
+    assert execute_all(
+        db, "select rowid, distance from v7 where a match vec_bit(X'03') and k = 3"
+    ) == [
+        {"rowid": 2, "distance": 0.5},
+        {"rowid": 1, "distance": 0.75},
+        {"rowid": 3, "distance": 0.8333333134651184},
+    ]
+
+    db.execute("create virtual table v8 using vec0( a bit[8] distance_metric=tanimoto)")
+    for column in ["a bit[8] distance_metric=l2", "a float[8] distance_metric=jaccard"]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v9 using vec0({column})")
+    with _raises(
+        "vec0 constructor error: Unknown distance_metric in vector column "
+        "'a float[8] distance_metric=manhattan', must be l2, l1, cosine, dot or jaccard"
+    ):
+        db.execute("create virtual table v9 using vec0(a float[8] distance_metric=manhattan)")
 
 
 def test_vec0_vacuum():
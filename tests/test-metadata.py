@@ -1117,9 +1117,18 @@ def test_vtab_in(db, snapshot):
         db, "select *  from v where vector match '[0]' and k = 8 and b in (1, 0)"
     ) == snapshot(name="block-bool")
 
-    assert exec(
-        db, "select *  from v where vector match '[0]' and k = 8 and f in (1.1, 0.0)"
-    ) == snapshot(name="block-float")
+    assert [
+        row[0]
+        for row in db.execute(
+            "select rowid from v where vector match '[0]' and k = 8 and f in (1.1, 0.0)"
+        )
+    ] == [1, 2, 3, 4, 5, 6, 7, 8]
+    assert (
+        db.execute(
+            "select count(*) from v where vector match '[0]' and k = 8 and f in (1.0, 1.2)"
+        ).fetchone()[0]
+        == 0
+    )
 
     assert exec(
         db,
@@ -1140,6 +1149,198 @@ def test_vtab_in(db, snapshot):
     ) == snapshot(name="allow-text-superfluous")
 
 
+def test_integer_metadata_real_operands(db):
+    db.execute("create virtual table v using vec0(vector float[1], n int, chunk_size=8)")
+    db.executemany(
+        "insert into v(rowid, vector, n) values (?, ?, ?)",
+        [(i, f"[{i}]", i) for i in range(1, 11)],
+    )
+
+    def knn(where):
+        return [
+            row[0]
+            for row in db.execute(
+                f"select rowid from v where vector match '[0]' and k = 3 and {where}"
+            )
+        ]
+
+    # REAL operands aren't truncated to integers
+    assert knn("n > 2.5") == [3, 4, 5]
+    assert knn("n >= 2.5") == [3, 4, 5]
+    assert knn("n < 2.5") == [1, 2]
+    assert knn("n = 2.5") == []
+    assert knn("n != 2.5") == [1, 2, 3]
+    assert knn("n = 2.0") == [2]
+    assert knn("n < 1e30") == [1, 2, 3]
+    assert knn("n > -1e30 and n > 1e19") == []
+
+
+
+def test_text_metadata_patterns_and_collations(db):
+    db.execute(
+        "create virtual table v using vec0(vector float[1], t text, u text collate nocase, chunk_size=8)"
+    )
+    data = ["apple", "Apple", "banana", "abcdefghijkl", "ABCDEFGHIJKL", "abcdefghijklmnop"]
+    db.executemany(
+        "insert into v(rowid, vector, t, u) values (?, ?, ?, ?)",
+        [(i, f"[{i}]", t, t) for i, t in enumerate(data, start=1)],
+    )
+
+    def knn(where):
+        return [
+            row[0]
+            for row in db.execute(
+                f"select rowid from v where vector match '[0]' and k = 10 and {where}"
+            )
+        ]
+
+    # character classes and values that fill the whole 12-byte cache
+    assert knn("t glob '[ab]*'") == [1, 3, 4, 6]
+    assert knn("t like '%l'") == [4, 5]
+    assert knn("t glob '*l'") == [4]
+    assert knn("t >= 'abcdefghijkl' and t < 'abcdefghijklm'") == [4]
+
+    # query-level collations
+    assert knn("t = 'APPLE' collate nocase") == [1, 2]
+    assert knn("t > 'abcdefghijklm' collate nocase") == [1, 2, 3, 6]
+    db.create_collation("reverse", lambda a, b: (a < b) - (a > b))
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Only BINARY, NOCASE and RTRIM collations are supported on TEXT metadata columns",
+    ):
+        knn("t = 'apple' collate reverse")
+
+    # columns declared `collate nocase` default to it
+    assert knn("u = 'APPLE'") == [1, 2]
+    assert knn("u in ('apple', 'abcdefghijklMNOP')") == [1, 2, 6]
+    assert knn("u != 'abcdefghijkl'") == [1, 2, 3, 6]
+    assert knn("u = 'APPLE' collate binary") == []
+
+    for definition in ["x integer collate nocase", "x text collate", "x text collate reverse"]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse metadata column"):
+            db.execute(f"create virtual table e using vec0(vector float[1], {definition})")
+
+
+def test_text_metadata_rtrim(db):
+    db.execute(
+        "create virtual table v using vec0(vector float[1], t text, r text collate rtrim, chunk_size=8)"
+    )
+    data = [
+        "apple",
+        "apple  ",
+        "apple\t",
+        "abcdefghijklmnop",
+        "abcdefghijklmnop   ",
+        "abcdefghijkl ",
+        "abcdefghijk",
+        "",
+        "   ",
+    ]
+    db.executemany(
+        "insert into v(rowid, vector, t, r) values (?, ?, ?, ?)",
+        [(i, f"[{i}]", t, t) for i, t in enumerate(data, start=1)],
+    )
+
+    def knn(where):
+        return [
+            row[0]
+            for row in db.execute(
+                f"select rowid from v where vector match '[0]' and k = 10 and {where}"
+            )
+        ]
+
+    def expected(where):
+        # what SQLite itself returns, without the constraint pushed down
+        return [
+            row[0]
+            for row in db.execute(
+                f"select rowid from (select rowid, t, r from v) where {where} order by rowid"
+            )
+        ]
+
+    for where in [
+        "r = 'apple'",
+        "r = 'abcdefghijklmnop '",
+        "r != 'abcdefghijkl'",
+        "r is ''",
+        "r > 'abcdefghijklmnop'",
+        "r <= 'abcdefghijkl'",
+        "r < 'apple'",
+        "r in ('apple ', 'abcdefghijklmnop', ' ')",
+        "t = 'apple   ' collate rtrim",
+        "t >= 'abcdefghijklmnop  ' collate rtrim",
+        "r = 'APPLE' collate nocase",
+        "r = 'apple  ' collate binary",
+    ]:
+        assert knn(where) == expected(where), where
+    assert knn("r = 'apple'") == [1, 2]
+    assert knn("r in ('abcdefghijkl', '')") == [6, 8, 9]
+    assert knn("t = 'apple'") == [1]
+    assert knn("r like 'apple_'") == [3]
+
+
+def test_json_metadata(db):
+    db.execute("create virtual table v using vec0(vector float[1], meta json, chunk_size=8)")
+    data = [
+        '{"lang": "en"}',
+        '{"lang": "fr", "year": 2024}',
+        '{"lang": "en", "year": 2024.0, "tags": ["news", "tech"]}',
+        '{"lang": "en", "author": {"country": "NZ", "name": "a long author name"}}',
+        '{"lang": null, "draft": true}',
+        '{"tags": ["tech"]}',
+    ]
+    db.executemany(
+        "insert into v(rowid, vector, meta) values (?, ?, ?)",
+        [(i, f"[{i}]", meta) for i, meta in enumerate(data, start=1)],
+    )
+    assert db.execute("select meta from v where rowid = 4").fetchone()[0] == data[3]
+
+    def knn(pattern, k=3):
+        return [
+            row[0]
+            for row in db.execute(
+                "select rowid from v where vector match '[0]' and k = ? and vec_json_contains(meta, ?)",
+                [k, pattern],
+            )
+        ]
+
+    # constraints apply before the k closest rows are picked
+    assert knn('{"lang": "en"}') == [1, 3, 4]
+    assert knn('{"year": 2024}') == [2, 3]
+    assert knn('{"author": {"country": "NZ"}}') == [4]
+    assert knn('{"tags": ["tech"]}') == [3, 6]
+    assert knn('{"tags": ["tech", "news"]}') == [3]
+    assert knn('{"lang": null}') == [5]
+    assert knn('{"draft": true}') == [5]
+    assert knn('{"draft": 1}') == []
+    assert knn('{"year": "2024"}') == []
+    assert knn("{}", k=10) == [1, 2, 3, 4, 5, 6]
+
+    # outside of KNN queries SQLite calls the function itself
+    assert [
+        row[0]
+        for row in db.execute(
+            "select rowid from v where vec_json_contains(meta, '{\"lang\": \"fr\"}')"
+        )
+    ] == [2]
+
+    for pattern in ["[1]", "not json"]:
+        with pytest.raises(sqlite3.OperationalError, match="pattern must be a JSON object"):
+            knn(pattern)
+    with pytest.raises(sqlite3.OperationalError, match="can't have objects or arrays inside arrays"):
+        knn('{"a": [{"b": 1}]}')
+
+    db.execute("create virtual table w using vec0(vector float[1], meta json, t text)")
+    for value in ["{not json", 1]:
+        with pytest.raises(sqlite3.OperationalError, match="Expected valid JSON text for JSON metadata column meta"):
+            db.execute("insert into w(vector, meta, t) values ('[1]', ?, 'x')", [value])
+    with pytest.raises(sqlite3.OperationalError, match="only available on JSON metadata columns"):
+        db.execute(
+            "select * from w where vector match '[0]' and k = 1 and vec_json_contains(t, '{}')"
+        ).fetchall()
+    with pytest.raises(sqlite3.OperationalError, match="could not parse metadata column"):
+        db.execute("create virtual table e using vec0(vector float[1], meta json collate nocase)")
+
 def test_vtab_in_long_text(db, snapshot):
     db.execute(
         "create virtual table v using vec0(vector float[1], t text, chunk_size=8)"
@@ -0,0 +1,132 @@
+import re
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_multivector_columns(db):
+    db.execute(
+        "create virtual table v using vec0(embedding float[2], tokens multivector float[2], +label text)"
+    )
+    db.execute(
+        """
+        insert into v(rowid, embedding, tokens, label) values
+          (1, '[1, 1]', '[[1, 0], [0, 1]]', 'a'),
+          (2, '[1, 2]', '[[2, 0]]', 'b'),
+          (3, '[0, 0]', null, 'c'),
+          (4, '[2, 1]', ?, 'd')
+        """,
+        [_f32([0.5, 0.5, 0, 0.25, 0.25, 0])],
+    )
+
+    # JSON is stored as the float32 sub-vectors back to back
+    assert rows(db, "select rowid, tokens from v order by rowid") == [
+        (1, _f32([1, 0, 0, 1])),
+        (2, _f32([2, 0])),
+        (3, None),
+        (4, _f32([0.5, 0.5, 0, 0.25, 0.25, 0])),
+    ]
+
+    # every query vector adds its best inner product with the row's vectors
+    knn = "select rowid, distance from v where tokens match ? and k = ?"
+    assert rows(db, knn, ["[[1, 0], [0, 1]]", 5]) == [
+        (1, -2.0),
+        (2, -2.0),
+        (4, -1.0),
+    ]
+    assert rows(db, knn, ["[[0, 1]]", 1]) == [(1, -1.0)]
+    assert rows(db, knn, [_f32([1, 0, 1, 0]), 1]) == [(2, -4.0)]
+    assert rows(db, knn, ["[[0, 1]]", 0]) == []
+    if sqlite3.sqlite_version_info >= (3, 41, 0):
+        assert rows(
+            db,
+            "select rowid, label from v where tokens match ? order by distance limit 1 offset 1",
+            ["[[1, 0]]"],
+        ) == [(1, "a")]
+    assert rows(
+        db,
+        "select rowid from v where tokens match ? and k = 5 and rowid in (1, 3, 4)",
+        ["[[1, 0]]"],
+    ) == [(1,), (4,)]
+
+    # dense columns are unaffected
+    assert rows(db, "select rowid from v where embedding match '[1, 1]' and k = 1") == [
+        (1,)
+    ]
+
+    db.execute("update v set tokens = '[[0, 3], [0, -1]]' where rowid = 2")
+    db.execute("update v set tokens = null where rowid = 1")
+    assert rows(db, knn, ["[[0, 1]]", 5]) == [(2, -3.0), (4, -0.5)]
+
+    db.execute("delete from v where rowid = 2")
+    assert rows(db, knn, ["[[0, 1]]", 5]) == [(4, -0.5)]
+
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("knn on v.tokens via maxsim scan",)
+    ]
+
+
+def test_multivector_columns_errors(db):
+    db.execute(
+        "create virtual table v using vec0(embedding float[1], tokens multivector float[2], genre text)"
+    )
+
+    for value, message in [
+        ("[[1, 2, 3]]", "multivector vectors must have 2 dimensions, found 3."),
+        ("[]", "multivectors must have at least one vector."),
+        ("[1, 2]", "JSON multivector parsing error"),
+        (_f32([1, 2, 3]), "multivector blobs must be a series of 2-dimension float32 vectors, found 12 bytes."),
+        (3, "multivectors must be a BLOB or JSON TEXT, found INTEGER."),
+    ]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=re.escape('Invalid multivector for the "tokens" column: ' + message),
+        ):
+            db.execute(
+                "insert into v(rowid, embedding, tokens, genre) values (1, '[1]', ?, 'x')",
+                [value],
+            )
+    # nothing was written by the failed inserts
+    assert rows(db, "select count(*) from v_rowids") == [(0,)]
+
+    db.execute(
+        "insert into v(rowid, embedding, tokens, genre) values (1, '[1]', '[[1, 2]]', 'x')"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError, match='Invalid multivector for the "tokens" column'
+    ):
+        db.execute("update v set tokens = '[[1]]' where rowid = 1")
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match=r'Query vector on the "tokens" column is invalid: multivector vectors must have 2 dimensions',
+    ):
+        db.execute("select * from v where tokens match '[[1, 2, 3]]' and k = 1")
+
+    for constraint in ["genre = 'x'", "distance < 0"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=r'KNN queries on the multivector column "tokens" only support k, LIMIT, OFFSET and rowid in \(...\) constraints',
+        ):
+            db.execute(
+                f"select * from v where tokens match '[[1, 2]]' and k = 1 and {constraint}"
+            )
+
+    for definition in [
+        "tokens multivector float[0]",
+        "tokens multivector int8[4]",
+        "tokens multivector float",
+        "tokens multivector float[4] distance_metric=l2",
+    ]:
+        with pytest.raises(
+            sqlite3.OperationalError, match="could not parse multivector column"
+        ):
+            db.execute(f"create virtual table w using vec0(a float[1], {definition})")
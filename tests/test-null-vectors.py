@@ -0,0 +1,108 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_null_vectors(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], b float[2] index=hnsw, "
+        "+title text, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, b, title) values (?, ?, ?, ?)",
+        [
+            (1, "[1, 1]", "[1, 1]", "one"),
+            (2, None, None, "two"),
+            (3, "[3, 3]", "[3, 3]", "three"),
+            (4, None, None, "four"),
+        ],
+    )
+
+    # rows without vectors don't take a chunk slot
+    assert rows(db, "select rowid, chunk_id from v_rowids order by rowid") == [
+        (1, 1),
+        (2, None),
+        (3, 1),
+        (4, None),
+    ]
+    assert rows(db, "select rowid, title from v where a is null") == [
+        (2, "two"),
+        (4, "four"),
+    ]
+    assert rows(db, "select rowid from v where b is not null") == [(1,), (3,)]
+    assert rows(db, "select a, b from v where rowid = 2") == [(None, None)]
+    assert rows(db, "select rowid, a is null from v order by rowid") == [
+        (1, 0),
+        (2, 1),
+        (3, 0),
+        (4, 1),
+    ]
+    assert rows(db, "select rowid from v where a match '[4, 4]' and k = 10") == [
+        (3,),
+        (1,),
+    ]
+    assert rows(db, "select rowid from v where b match '[4, 4]' and k = 10") == [
+        (3,),
+        (1,),
+    ]
+
+    # updating the other columns keeps them without vectors
+    db.execute("update v set title = 'TWO' where rowid = 2")
+    assert rows(db, "select rowid, title from v where a is null") == [
+        (2, "TWO"),
+        (4, "four"),
+    ]
+
+    # setting the vectors gives them a chunk slot
+    db.execute("update v set a = '[2, 2]', b = '[2, 2]' where rowid = 2")
+    assert rows(db, "select rowid from v where a is null") == [(4,)]
+    assert rows(db, "select rowid from v where b match '[2.1, 2.1]' and k = 2") == [
+        (2,),
+        (3,),
+    ]
+    assert rows(db, "select vec_to_json(a), title from v where rowid = 2") == [
+        ("[2.000000,2.000000]", "TWO")
+    ]
+
+    db.execute("delete from v where rowid = 4")
+    assert rows(db, "select rowid from v where a is null") == []
+    assert rows(db, "select count(*) from v_rowids") == [(3,)]
+    assert rows(db, "select count(*) from v_auxiliary") == [(3,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+    db.execute("insert into v(rowid, title) values (5, 'five')")
+    db.execute("insert into v(v) values ('optimize')")
+    assert rows(db, "select rowid from v where a is null") == [(5,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_null_vectors_errors(db):
+    db.execute("create virtual table v using vec0(a float[2], b float[2])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='Inserted vector for the "b" column is NULL, but all or none of '
+        "a row's vectors must be NULL",
+    ):
+        db.execute("insert into v(rowid, a, b) values (1, '[1, 1]', NULL)")
+
+    db.execute("insert into v(rowid, a, b) values (1, NULL, NULL)")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='Updated vector for the "b" column is NULL, but all or none of '
+        "a row's vectors must be NULL",
+    ):
+        db.execute("update v set a = '[1, 1]' where rowid = 1")
+    assert rows(db, "select rowid from v where a is null") == [(1,)]
+
+    db.execute(
+        "create virtual table m using vec0(user_id integer partition key, a float[2])"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Vectors can't be NULL in vec0 tables with metadata or partition "
+        "key columns",
+    ):
+        db.execute("insert into m(rowid, user_id, a) values (1, 1, NULL)")
@@ -121,3 +121,68 @@ def test_optimize_then_vacuum_allows_future_writes(tmp_path):
     # File/page count should not grow; should shrink when pages are freed
     assert size_after_vacuum <= size_before_vacuum
     assert disk_bytes_after <= disk_bytes_before
+
+
+def test_optimize_skips_compact_tables(db):
+    db.execute(
+        "create virtual table v using vec0(user_id integer partition key, vector float[1], chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, user_id, vector) values(?, ?, ?)",
+        ((i, i % 2, b"\x11\x11\x11\x11") for i in range(1, 25)),
+    )
+
+    # one partial chunk per partition is already as small as it gets
+    chunks = db.execute("select chunk_id from v_chunks order by 1").fetchall()
+    db.execute("delete from v where rowid = 1")
+    db.execute("insert into v(v) values('optimize')")
+    assert db.execute("select chunk_id from v_chunks order by 1").fetchall() == chunks
+
+    # emptying a chunk's worth of rows in one partition lets it shrink
+    db.execute("delete from v where user_id = 0 and rowid <= 16")
+    db.execute("insert into v(v) values('optimize')")
+    assert db.execute("select count(*) from v_chunks").fetchone()[0] == 3
+    assert [tuple(row) for row in db.execute(
+        "select user_id, count(*) from v group by 1 order by 1"
+    )] == [(0, 4), (1, 11)]
+
+
+def test_optimize_incrementally(db):
+    db.execute(
+        "create virtual table v using vec0(user_id integer partition key, vector float[1], label text, chunk_size=8)"
+    )
+    db.executemany(
+        "insert into v(rowid, user_id, vector, label) values(?, ?, ?, ?)",
+        ((i, i % 2, b"\x11\x11\x11\x11", f"label number {i}") for i in range(1, 65)),
+    )
+    db.execute("delete from v where rowid % 4 = 0 or rowid <= 20")
+    rows = [tuple(row) for row in db.execute("select rowid, user_id, label from v order by 1")]
+    remaining = db.execute("select vec_optimize_remaining('v')").fetchone()[0]
+    # the delete already dropped the two chunks it emptied
+    assert db.execute("select count(*) from v_chunks").fetchone()[0] == 6
+    assert remaining == 1
+
+    # a 0ms budget still compacts one chunk per call
+    calls = 0
+    while db.execute("select vec_optimize_remaining('v')").fetchone()[0] > 0:
+        db.execute("insert into v(v) values('optimize=0')")
+        calls += 1
+        assert calls <= 8
+    assert calls > 1
+    assert db.execute("select count(*) from v_chunks").fetchone()[0] == 5
+    assert [tuple(row) for row in db.execute("select rowid, user_id, label from v order by 1")] == rows
+
+    # every row is still reachable through KNN queries of its partition
+    assert db.execute(
+        "select count(*) from v where vector match ? and k = 100 and user_id = 1",
+        [b"\x11\x11\x11\x11"],
+    ).fetchone()[0] == 22
+
+    # partitions without rows lose their chunks entirely
+    db.execute("delete from v where user_id = 0")
+    db.execute("insert into v(v) values('optimize=1000')")
+    assert db.execute("select vec_optimize_remaining('v')").fetchone()[0] == 0
+    assert db.execute("select count(*) from v_chunks").fetchone()[0] == 3
+
+    with pytest.raises(Exception, match="optimize budget must be an integer between 0 and 3600000 milliseconds"):
+        db.execute("insert into v(v) values('optimize=-1')")
@@ -0,0 +1,87 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_partition_delete(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          tenant_id integer partition key,
+          a float[1],
+          +label text,
+          chunk_size=8
+        )
+        """
+    )
+    for tenant_id in [1, 2, 3]:
+        for i in range(20):
+            db.execute(
+                "insert into v(tenant_id, a, label) values (?, ?, ?)",
+                [tenant_id, f"[{i}]", f"{tenant_id}-{i}"],
+            )
+    assert rows(db, "select count(*) from v_chunks") == [(9,)]
+
+    # scans only visit the chunks of matching partitions
+    assert rows(db, "select count(*), min(label) from v where tenant_id = 2") == [
+        (20, "2-0")
+    ]
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("fullscan partition-pruned on v",)
+    ]
+    assert rows(db, "select count(*) from v where tenant_id in (1, 3)") == [(40,)]
+    assert rows(db, "select count(*) from v where tenant_id > 1") == [(40,)]
+
+    # deleting a whole partition drops its chunks
+    db.execute("delete from v where tenant_id = 2")
+    assert rows(db, "select distinct partition00 from v_chunks") == [(1,), (3,)]
+    assert rows(db, "select count(*) from v_chunks") == [(6,)]
+    assert rows(db, "select count(*) from v_vector_chunks00") == [(6,)]
+    assert rows(db, "select count(*) from v_rowids") == [(40,)]
+    assert rows(db, "select count(*) from v_auxiliary") == [(40,)]
+
+    # partly emptied chunks are kept
+    db.execute("delete from v where tenant_id = 1 and label in ('1-0', '1-1')")
+    assert rows(db, "select count(*) from v_chunks where partition00 = 1") == [(3,)]
+
+    assert rows(
+        db,
+        "select label, distance from v where a match '[4.25]' and k = 2 and tenant_id = 3",
+    ) == [("3-4", 0.25), ("3-5", 0.75)]
+    assert rows(
+        db, "select label from v where a match '[4]' and k = 2 and tenant_id = 2"
+    ) == []
+
+    # the partition can be filled again
+    db.execute("insert into v(tenant_id, a, label) values (2, '[1]', 'again')")
+    assert rows(db, "select rowid, label from v where tenant_id = 2") == [
+        (61, "again")
+    ]
+
+
+def test_partition_delete_metadata(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          tenant_id text partition key,
+          a float[1],
+          kind text,
+          chunk_size=8
+        )
+        """
+    )
+    for tenant_id in ["x", "y"]:
+        for i in range(10):
+            db.execute(
+                "insert into v(tenant_id, a, kind) values (?, ?, ?)",
+                [tenant_id, f"[{i}]", "even" if i % 2 == 0 else "odd"],
+            )
+    db.execute("delete from v where tenant_id = 'x' and kind = 'odd'")
+    assert rows(db, "select count(*) from v_chunks") == [(4,)]
+    db.execute("delete from v where tenant_id = 'x'")
+    assert rows(db, "select partition00 from v_chunks") == [("y",), ("y",)]
+    assert rows(db, "select count(*) from v_metadatachunks00") == [(2,)]
+    assert rows(db, "select count(*) from v where kind = 'even'") == [(5,)]
@@ -1,4 +1,5 @@
 import sqlite3
+import pytest
 from collections import OrderedDict
 
 
@@ -72,6 +73,10 @@ def test_updates(db, snapshot):
     assert exec(db, "update v set p = ? where rowid = ?", ["new", 1]) == snapshot(
         name="2. update #1"
     )
+    assert exec(db, "select * from v") == snapshot(name="3. after update #1")
+    assert exec(db, "select * from v where p = 'new'") == snapshot(
+        name="4. filter on new partition"
+    )
 
 
 def test_vacuum(db, snapshot):
@@ -141,3 +146,97 @@ def vec0_shadow_table_contents(db, v):
             continue
         o[shadow_table] = exec(db, f"select * from {shadow_table}")
     return o
+
+
+def test_update_moves_row_between_partitions(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          user_id integer partition key,
+          a float[1] index=hnsw,
+          genre text,
+          +note text,
+          chunk_size=8
+        )
+        """
+    )
+    for i in range(1, 11):
+        db.execute(
+            "insert into v(rowid, user_id, a, genre, note) values (?, ?, ?, ?, ?)",
+            [i, 1, f"[{i}]", f"genre{i}", f"note{i}"],
+        )
+
+    # partition key, vector, metadata and auxiliary in a single statement
+    db.execute(
+        "update v set user_id = 2, a = '[0.5]', genre = 'jazz', note = 'moved' where rowid = 3"
+    )
+    assert [tuple(r) for r in db.execute(
+        "select rowid, user_id, vec_to_json(a), genre, note from v where rowid = 3"
+    )] == [(3, 2, "[0.500000]", "jazz", "moved")]
+    assert [tuple(r) for r in db.execute(
+        "select rowid, distance from v where a match '[0]' and k = 5 and user_id = 2"
+    )] == [(3, 0.5)]
+    assert sorted(r[0] for r in db.execute(
+        "select rowid from v where a match '[3]' and k = 2 and user_id = 1"
+    )) == [2, 4]
+    assert [r[0] for r in db.execute(
+        "select rowid from v where a match '[0]' and k = 1 and genre = 'jazz'"
+    )] == [3]
+
+    # moving back reuses the freed slot instead of growing the table
+    chunks = db.execute("select count(*) from v_chunks").fetchone()[0]
+    db.execute("update v set user_id = 1 where rowid = 3")
+    assert db.execute("select count(*) from v_chunks").fetchone()[0] == chunks
+    assert db.execute("select count(*) from v where user_id = 1").fetchone()[0] == 10
+
+    with pytest.raises(sqlite3.OperationalError, match="Parition key type mismatch"):
+        db.execute("update v set user_id = 'x' where rowid = 3")
+    with pytest.raises(
+        sqlite3.OperationalError, match="UPDATEs on vec0 primary key values are not allowed"
+    ):
+        db.execute("update v set rowid = 100 where rowid = 3")
+
+
+@pytest.mark.skipif(
+    sqlite3.sqlite_version_info[1] < 38,
+    reason="requires vtab `x in (...)` support in SQLite >=3.38",
+)
+def test_partition_key_in(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          user_id integer partition key,
+          region text partition key,
+          a float[1],
+          chunk_size=8
+        )
+        """
+    )
+    db.executemany(
+        "insert into v(rowid, user_id, region, a) values (?, ?, ?, ?)",
+        [(i, i % 4, "eu" if i % 2 else "us", f"[{i}]") for i in range(1, 41)],
+    )
+
+    def knn(where, params=[]):
+        return [
+            tuple(r)
+            for r in db.execute(
+                f"select rowid, distance from v where a match '[10]' and k = 3 and {where}",
+                params,
+            )
+        ]
+
+    # a single top k across all listed partitions, not k rows per partition
+    assert knn("user_id in (1, 2)") == [(10, 0.0), (9, 1.0), (13, 3.0)]
+    assert knn("user_id in (select value from json_each(?))", ["[3]"]) == [
+        (11, 1.0),
+        (7, 3.0),
+        (15, 5.0),
+    ]
+    assert knn("user_id in (7, 8)") == []
+    assert [
+        r[0]
+        for r in db.execute(
+            "select rowid from v where a match '[10.5]' and k = 3 and region in ('us') and user_id in (0, 1, 2)"
+        )
+    ] == [10, 12, 8]
@@ -0,0 +1,127 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def insert(db, table, rowid, tenant):
+    db.execute(
+        f"insert into {table}(rowid, a, tenant, note, n) "
+        "values (?, json_array(?, ?), ?, ?, ?)",
+        [rowid, rowid, rowid, tenant, f"row {rowid}", rowid],
+    )
+
+
+def tenant_rows(db, table):
+    return rows(db, f"select tenant, rowid, note, n from {table} order by tenant, rowid")
+
+
+def test_partition_limit_reject(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], "
+        "tenant text partition key limit 3, +note text, n integer, chunk_size=8)"
+    )
+    for i in range(1, 4):
+        insert(db, "v", i, "acme")
+    with pytest.raises(
+        sqlite3.IntegrityError,
+        match="The tenant partition 'acme' already has 3 rows, the limit of its partition key",
+    ):
+        insert(db, "v", 4, "acme")
+    # other partitions, and rows with no partition, aren't affected
+    insert(db, "v", 4, "globex")
+    for i in range(5, 10):
+        insert(db, "v", i, None)
+    assert rows(db, "select tenant, count(*) from v group by tenant") == [
+        (None, 5),
+        ("acme", 3),
+        ("globex", 1),
+    ]
+
+    # a rejected write leaves nothing behind, and a freed slot can be reused
+    assert rows(db, "select count(*) from v_rowids") == [(9,)]
+    db.execute("delete from v where rowid = 2")
+    insert(db, "v", 10, "acme")
+    with pytest.raises(sqlite3.IntegrityError, match="already has 3 rows"):
+        db.execute("update v set tenant = 'acme' where rowid = 4")
+    db.execute("update v set tenant = 'globex' where rowid = 10")
+    db.execute("update v set tenant = 'acme' where rowid = 4")
+    assert rows(db, "select rowid from v where tenant = 'acme' order by rowid") == [
+        (1,),
+        (3,),
+        (4,),
+    ]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_partition_limit_evict_oldest(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], "
+        "tenant integer partition key limit 3, +note text, n integer, "
+        "chunk_size=8, partition_limit_policy=evict_oldest)"
+    )
+    for i in range(1, 11):
+        insert(db, "v", i, i % 2)
+    # the rows with the smallest rowids go first, even though the newer rows
+    # took over their slots
+    assert tenant_rows(db, "v") == [
+        (0, 6, "row 6", 6),
+        (0, 8, "row 8", 8),
+        (0, 10, "row 10", 10),
+        (1, 5, "row 5", 5),
+        (1, 7, "row 7", 7),
+        (1, 9, "row 9", 9),
+    ]
+    assert rows(db, "select count(*) from v_rowids") == [(6,)]
+    assert rows(db, "select count(*) from v_auxiliary") == [(6,)]
+    assert rows(
+        db, "select rowid from v where a match '[1, 1]' and k = 2 and tenant = 1"
+    ) == [(5,), (7,)]
+
+    # moving a row into a full partition evicts there, not from the row itself
+    db.execute("update v set tenant = 1 where rowid = 10")
+    assert rows(db, "select tenant, rowid from v order by tenant, rowid") == [
+        (0, 6),
+        (0, 8),
+        (1, 7),
+        (1, 9),
+        (1, 10),
+    ]
+    db.execute("update v set tenant = 1 where rowid = 7")
+    assert rows(db, "select count(*) from v where tenant = 1") == [(3,)]
+
+    # an id already taken fails the insert before anything is evicted
+    with pytest.raises(sqlite3.OperationalError, match="UNIQUE constraint failed"):
+        insert(db, "v", 9, 1)
+    assert rows(db, "select rowid from v where tenant = 1") == [(7,), (9,), (10,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_partition_limit_errors(db):
+    for definition, message in [
+        ("tenant integer partition key limit", "expected `limit N` with a positive N"),
+        ("tenant integer partition key limit 0", "expected `limit N` with a positive N"),
+        ("tenant integer partition key limit ten", "expected `limit N` with a positive N"),
+        ("tenant integer partition key limit 3 4", "expected `limit N` with a positive N"),
+        (
+            "tenant integer partition key, partition_limit_policy=evict_oldest",
+            "partition_limit_policy requires a partition key column with a `limit N`",
+        ),
+        (
+            "tenant integer partition key limit 3, partition_limit_policy=drop",
+            "partition_limit_policy must be 'reject' or 'evict_oldest'",
+        ),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=message):
+            db.execute(
+                f"create virtual table x using vec0(a float[2], {definition})"
+            )
+    db.execute(
+        "create virtual table v using vec0(a float[2], "
+        "tenant integer partition key limit 1, partition_limit_policy=reject)"
+    )
+    db.execute("insert into v(rowid, a, tenant) values (1, '[1, 1]', 1)")
+    with pytest.raises(sqlite3.IntegrityError, match="already has 1 rows"):
+        db.execute("insert into v(rowid, a, tenant) values (2, '[2, 2]', 1)")
@@ -0,0 +1,88 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_primary_key_compression(db):
+    db.execute(
+        "create virtual table v using vec0(id text primary key, a float[2], +label text, primary_key_compression=prefix)"
+    )
+    for i, id in enumerate(["doc-1/a", "doc-1/b", "doc-2/a", "plain", "x:y:z"]):
+        db.execute(
+            "insert into v(id, a, label) values (?, ?, ?)", [id, f"[{i}, 0]", id.upper()]
+        )
+
+    # keys are split after their last separator, with one copy of each prefix
+    assert rows(db, "select rowid, id_prefix, id_suffix from v_rowids order by rowid") == [
+        (1, 1, "a"),
+        (2, 1, "b"),
+        (3, 2, "a"),
+        (4, 0, "plain"),
+        (5, 3, "z"),
+    ]
+    assert rows(db, "select prefix_id, prefix from v_idprefixes") == [
+        (1, "doc-1/"),
+        (2, "doc-2/"),
+        (3, "x:y:"),
+    ]
+
+    assert rows(db, "select id from v") == [
+        ("doc-1/a",),
+        ("doc-1/b",),
+        ("doc-2/a",),
+        ("plain",),
+        ("x:y:z",),
+    ]
+    assert rows(db, "select id, label from v where id = 'doc-1/b'") == [
+        ("doc-1/b", "DOC-1/B")
+    ]
+    assert rows(db, "select id from v where id = 'doc-3/a'") == []
+    assert rows(db, "select id, distance from v where a match '[1.9, 0]' and k = 2") == [
+        ("doc-2/a", pytest.approx(0.1)),
+        ("doc-1/b", pytest.approx(0.9)),
+    ]
+    assert rows(
+        db,
+        "select id from v where a match '[1.9, 0]' and k = 5 and id in ('plain', 'doc-1/a')",
+    ) == [("plain",), ("doc-1/a",)]
+
+    with pytest.raises(
+        sqlite3.OperationalError, match="UNIQUE constraint failed on v primary key"
+    ):
+        db.execute("insert into v(id, a) values ('doc-1/a', '[1, 1]')")
+
+    db.execute("update v set label = 'updated' where id = 'x:y:z'")
+    db.execute("delete from v where id = 'doc-2/a'")
+    # prefixes left without rows are purged by optimize
+    db.execute("insert into v(v) values ('optimize')")
+    assert rows(db, "select prefix from v_idprefixes") == [("doc-1/",), ("x:y:",)]
+
+    db.execute("alter table v rename to w")
+    assert rows(db, "select id, label from w") == [
+        ("doc-1/a", "DOC-1/A"),
+        ("doc-1/b", "DOC-1/B"),
+        ("plain", "PLAIN"),
+        ("x:y:z", "updated"),
+    ]
+    db.execute("drop table w")
+    assert rows(db, "select name from sqlite_master where name like 'w%'") == []
+
+
+def test_primary_key_compression_errors(db):
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="primary_key_compression requires a TEXT primary key column",
+    ):
+        db.execute(
+            "create virtual table v using vec0(a float[2], primary_key_compression=prefix)"
+        )
+    for value in ["zstd", "pre"]:
+        with pytest.raises(
+            sqlite3.OperationalError, match="primary_key_compression must be 'prefix'"
+        ):
+            db.execute(
+                f"create virtual table v using vec0(id text primary key, a float[2], primary_key_compression={value})"
+            )
@@ -0,0 +1,187 @@
+import math
+import random
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect():
+    db = sqlite3.connect(":memory:")
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+M64 = (1 << 64) - 1
+
+
+def splitmix64(x):
+    x = (x + 0x9E3779B97F4A7C15) & M64
+    x = ((x ^ (x >> 30)) * 0xBF58476D1CE4E5B9) & M64
+    x = ((x ^ (x >> 27)) * 0x94D049BB133111EB) & M64
+    return x ^ (x >> 31)
+
+
+def project(vector, seed, out_dims):
+    # the projection matrix, as documented for vec_project()
+    scale = math.sqrt(3 / out_dims)
+    out = []
+    for j in range(out_dims):
+        total = 0
+        for i, x in enumerate(vector):
+            h = splitmix64((seed + j * len(vector) + i) & M64) % 6
+            total += x if h == 0 else -x if h == 1 else 0
+        out.append(total * scale)
+    return out
+
+
+def gaussians(rng, n, dimensions):
+    return [[rng.gauss(0, 1) for _ in range(dimensions)] for _ in range(n)]
+
+
+def test_project():
+    db = connect()
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[96],
+          small float[24] project=a(seed=7),
+          b int8[8],
+          tiny float[4] project=b,
+          chunk_size=8
+        )
+        """
+    )
+    rng = random.Random(0)
+    vectors = gaussians(rng, 40, 96)
+    for i, vector in enumerate(vectors):
+        db.execute(
+            "insert into v(rowid, a, b) values (?, ?, vec_int8(?))",
+            [i + 1, _f32(vector), str([i % 5 - 2] * 8)],
+        )
+
+    (small,) = db.execute("select small from v where rowid = 3").fetchone()
+    assert struct.unpack("24f", small) == pytest.approx(project(vectors[2], 7, 24), rel=1e-5)
+    (tiny,) = db.execute("select tiny from v where rowid = 4").fetchone()
+    assert struct.unpack("4f", tiny) == pytest.approx(project([1] * 8, 0, 4), rel=1e-5)
+    assert rows(db, "select count(*) from v where small = vec_project(a, 7, 24)") == [(40,)]
+
+    # full length queries are projected like the stored vectors
+    knn = "select rowid from v where small match ? and k = 3"
+    assert rows(db, knn, [_f32(vectors[9])])[0] == (10,)
+    assert rows(db, knn, [_f32(vectors[9])]) == rows(
+        db, knn, [db.execute("select vec_project(?, 7, 24)", [_f32(vectors[9])]).fetchone()[0]]
+    )
+    # a cheap first pass over the projections, reranked with the full vectors
+    assert rows(
+        db,
+        """
+        with candidates as (
+          select rowid from v where small match :q and k = 10
+        )
+        select rowid from v where rowid in (select rowid from candidates)
+        order by vec_distance_l2(a, :q) limit 1
+        """,
+        {"q": _f32(vectors[20])},
+    ) == [(21,)]
+
+    # updating the source column updates its projections
+    db.execute("update v set a = ? where rowid = 1", [_f32(vectors[30])])
+    assert rows(db, "select small = vec_project(a, 7, 24) from v where rowid = 1") == [(1,)]
+    nearest = rows(db, "select rowid from v where small match ? and k = 2", [_f32(vectors[30])])
+    assert sorted(nearest) == [(1,), (31,)]
+    db.execute("update v set b = vec_int8('[1, 1, 1, 1, 1, 1, 1, 1]') where rowid = 2")
+    assert rows(db, "select tiny = vec_project(b, 0, 4) from v where rowid = 2") == [(1,)]
+
+    db.execute("delete from v where rowid % 2 = 0")
+    db.execute("insert into v(v) values ('optimize')")
+    assert rows(db, "select count(*) from v where small = vec_project(a, 7, 24)") == [(20,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "small" column is projected from "a", so it can\'t be inserted',
+    ):
+        db.execute(
+            "insert into v(rowid, a, small, b) values (100, ?, ?, vec_int8(?))",
+            [_f32(vectors[0]), _f32([0] * 24), str([0] * 8)],
+        )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "small" column is projected from "a", so it can\'t be updated',
+    ):
+        db.execute("update v set small = ? where rowid = 1", [_f32([0] * 24)])
+
+
+def test_project_batch():
+    db = connect()
+    db.execute(
+        "create virtual table v using vec0(a float[32], "
+        "small float[8] project=a(seed=3) distance_metric=cosine)"
+    )
+    vectors = gaussians(random.Random(1), 10, 32)
+    db.execute(
+        "insert into v(v, a) values ('batch', ?)",
+        [b"".join(_f32(vector) for vector in vectors)],
+    )
+    assert rows(db, "select count(*) from v where small = vec_project(a, 3, 8)") == [(10,)]
+    assert rows(db, "select rowid from v where small match ? and k = 1", [_f32(vectors[6])]) == [(7,)]
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "small" column is projected from "a", so it can\'t be inserted',
+    ):
+        db.execute(
+            "insert into v(v, a, small) values ('batch', ?, ?)",
+            [_f32(vectors[0]), _f32([0] * 8)],
+        )
+
+
+def test_project_changeset():
+    db = connect()
+    for t in ["v", "w"]:
+        db.execute(
+            f"create virtual table {t} using vec0(a float[16], "
+            "small float[4] project=a(seed=9), changelog=on)"
+        )
+    db.execute("insert into v(rowid, a) values (1, ?)", [_f32(range(16))])
+    db.execute("select vec0_apply_changeset('w', vec0_changeset('v'))")
+    assert rows(db, "select rowid, small from w") == rows(db, "select rowid, small from v")
+
+
+def test_project_errors():
+    db = connect()
+    for definition, message in [
+        (
+            "a float[8], b float[4] project=c",
+            'Vector column "b" is projected from "c", which isn\'t a vector column '
+            "of the table",
+        ),
+        ("a float[8], b float[4] project=b", 'Vector column "b" can\'t be projected from "b"'),
+        (
+            "a bit[8], b float[4] project=a",
+            'Vector column "b" can\'t be projected from "a", only from a float32, '
+            "float16, bfloat16 or int8 column that isn't projected itself",
+        ),
+        (
+            "a float[8], b float[4] project=a, c float[2] project=b",
+            'Vector column "c" can\'t be projected from "b"',
+        ),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=message):
+            db.execute(f"create virtual table v using vec0({definition})")
+    for column in [
+        "b int8[4] project=a",
+        "b float[4] project=a(m=2)",
+        "b float[4] project=a(seed=x)",
+        "b float[4] project",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0(a float[8], {column})")
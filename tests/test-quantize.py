@@ -0,0 +1,350 @@
+import sqlite3
+import random
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def _i64(list):
+    return struct.pack("%sq" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def info(db, table):
+    return dict(
+        rows(db, f"select key, value from {table}_info where key like 'quantize%'")
+    )
+
+
+def test_quantize_int8_storage(db):
+    db.execute(
+        "create virtual table v using vec0(a float[4] quantize=int8, chunk_size=8)"
+    )
+    db.execute("insert into v(rowid, a) values (1, '[-1, 0, 0.5, 1]')")
+
+    # the value range is learned from the first vector and saved in _info
+    params = info(db, "v")
+    assert params["quantize00_offset"] == -1.0
+    assert params["quantize00_scale"] == pytest.approx(2 / 255)
+    # stored as 1 byte per dimension
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 8 * 4
+
+    # reads are dequantized back to float32
+    (a,) = db.execute("select a from v where rowid = 1").fetchone()
+    assert struct.unpack("4f", a) == pytest.approx([-1, 0, 0.5, 1], abs=1 / 255)
+    assert db.execute("select vec_type(a) from v").fetchone()[0] == "float32"
+
+    # values outside the learned range are clamped
+    db.execute("insert into v(rowid, a) values (2, '[-5, 0, 0, 5]')")
+    (a,) = db.execute("select a from v where rowid = 2").fetchone()
+    assert struct.unpack("4f", a) == pytest.approx([-1, 0, 0, 1], abs=1 / 255)
+    assert info(db, "v") == params
+
+    db.execute("update v set a = '[1, 1, 1, 1]' where rowid = 2")
+    result = rows(db, "select rowid, distance from v where a match '[1, 1, 1, 1]' and k = 2")
+    assert [r[0] for r in result] == [2, 1]
+    assert result[0][1] == 0.0
+    assert result[1][1] == pytest.approx((2 ** 2 + 1 + 0.25) ** 0.5, abs=0.01)
+
+
+def test_quantize_int8_knn(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          l2 float[16] quantize=int8,
+          cosine float[16] distance_metric=cosine quantize=int8,
+          hnsw float[16] quantize=int8 index=hnsw,
+          exact float[16]
+        )
+        """
+    )
+    rng = random.Random(0)
+    vectors = [[rng.uniform(-1, 1) for _ in range(16)] for _ in range(300)]
+    db.execute(
+        "insert into v(v, rowid, l2, cosine, hnsw, exact) values ('batch', ?, ?, ?, ?, ?)",
+        [
+            _i64(range(1, 301)),
+            *([b"".join(_f32(v) for v in vectors)] * 4),
+        ],
+    )
+    # learned from the whole batch
+    params = info(db, "v")
+    assert params["quantize00_offset"] == pytest.approx(min(min(v) for v in vectors))
+
+    hits = 0
+    for _ in range(10):
+        q = _f32([rng.uniform(-1, 1) for _ in range(16)])
+        exact = rows(db, "select rowid, distance from v where exact match ? and k = 10", [q])
+        approx = rows(db, "select rowid, distance from v where l2 match ? and k = 10", [q])
+        for (_, x), (_, y) in zip(exact, approx):
+            assert x == pytest.approx(y, rel=0.05)
+        hits += len(set(r[0] for r in exact) & set(r[0] for r in approx))
+        cosine = rows(db, "select rowid from v where cosine match ? and k = 10", [q])
+        hnsw = rows(db, "select rowid from v where hnsw match ? and k = 10", [q])
+        assert len(cosine) == 10
+        assert len(hnsw) == 10
+    assert hits >= 80
+
+
+def test_quantize_int8_empty_table_query(db):
+    db.execute("create virtual table v using vec0(a float[2] quantize=int8)")
+    assert rows(db, "select rowid from v where a match '[1, 1]' and k = 1") == []
+    # queries don't learn the range
+    assert info(db, "v") == {}
+
+    # nor do rolled back inserts
+    db.commit()
+    db.execute("begin")
+    db.execute("insert into v(rowid, a) values (1, '[0, 10]')")
+    db.execute("rollback")
+    db.execute("insert into v(rowid, a) values (1, '[0, 1]')")
+    assert info(db, "v")["quantize00_scale"] == pytest.approx(1 / 255)
+
+
+def test_quantize_constructor_errors(db):
+    for column in [
+        "a int8[2] quantize=int8",
+        "a bit[8] quantize=int8",
+        "a float[2] quantize=int4",
+        "a float[2] quantize=int8 index=ivf",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0({column})")
+
+
+def test_quantize_pq_train(db):
+    db.execute(
+        "create virtual table v using vec0(a float[4] quantize=pq(m=2, nbits=2), chunk_size=8)"
+    )
+    rng = random.Random(0)
+    vectors = [[rng.uniform(-1, 1) for _ in range(4)] for _ in range(20)]
+    for i, vector in enumerate(vectors):
+        db.execute("insert into v(rowid, a) values (?, ?)", [i + 1, _f32(vector)])
+
+    # float32 until trained, and queries are exact
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 8 * 16
+    q = _f32([0.5, 0.5, -0.5, -0.5])
+    exact = rows(db, "select rowid, distance from v where a match ? and k = 3", [q])
+    assert rows(db, "select count(*) from v_pqcodebooks00") == [(0,)]
+
+    db.execute("insert into v(v) values ('train')")
+    # 2 subvectors of 2^2 centroids, stored as 1 byte codes
+    assert rows(db, "select subvector, length(centroids) from v_pqcodebooks00") == [
+        (0, 4 * 2 * 4),
+        (1, 4 * 2 * 4),
+    ]
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 8 * 2
+
+    # reads are decoded from the codebooks
+    codebooks = [
+        struct.unpack("8f", blob)
+        for (blob,) in rows(db, "select centroids from v_pqcodebooks00 order by subvector")
+    ]
+    (a,) = db.execute("select a from v where rowid = 1").fetchone()
+    a = struct.unpack("4f", a)
+    assert tuple(a[:2]) in [codebooks[0][i : i + 2] for i in range(0, 8, 2)]
+    assert tuple(a[2:]) in [codebooks[1][i : i + 2] for i in range(0, 8, 2)]
+    assert db.execute("select vec_type(a) from v").fetchone()[0] == "float32"
+
+    # distances are computed against the decoded vectors
+    result = rows(db, "select rowid, distance, vec_distance_l2(a, ?) from v where a match ? and k = 20", [q, q])
+    assert len(result) == 20
+    for _, distance, decoded in result:
+        assert distance == pytest.approx(decoded, abs=1e-5)
+    assert [r[1] for r in result] == sorted(r[1] for r in result)
+
+    # new rows are encoded right away
+    db.execute("insert into v(rowid, a) values (21, ?)", [_f32([0.5, 0.5, -0.5, -0.5])])
+    db.execute("update v set a = ? where rowid = 2", [_f32([0, 0, 0, 0])])
+    db.execute(
+        "insert into v(v, rowid, a) values ('batch', ?, ?)",
+        [_i64([22, 23]), _f32([1, 1, 1, 1, -1, -1, -1, -1])],
+    )
+    assert db.execute("select count(*) from v").fetchone()[0] == 23
+    assert len(rows(db, "select rowid from v where a match ? and k = 30", [q])) == 23
+
+    with pytest.raises(sqlite3.OperationalError, match='PQ codebooks for "a" are already trained'):
+        db.execute("insert into v(v) values ('train')")
+
+
+def test_quantize_pq_knn(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          l2 float[16] quantize=pq(m=8),
+          cosine float[16] distance_metric=cosine quantize=pq(m=8),
+          dot float[16] distance_metric=dot quantize=pq(m=8, nbits=8),
+          l1 float[16] distance_metric=l1 quantize=pq(m=8),
+          exact float[16]
+        )
+        """
+    )
+    rng = random.Random(0)
+    vectors = [[rng.uniform(-1, 1) for _ in range(16)] for _ in range(500)]
+    db.execute(
+        "insert into v(v, rowid, l2, cosine, dot, l1, exact) values ('batch', ?, ?, ?, ?, ?, ?)",
+        [_i64(range(1, 501)), *([b"".join(_f32(v) for v in vectors)] * 5)],
+    )
+    db.execute("insert into v(v) values ('train')")
+    # 16 bytes of floats down to 8 codes
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 1024 * 8
+
+    for column, function in [
+        ("l2", "vec_distance_l2"),
+        ("cosine", "vec_distance_cosine"),
+        ("dot", "vec_distance_dot"),
+        ("l1", "vec_distance_l1"),
+    ]:
+        hits = 0
+        for _ in range(10):
+            q = _f32([rng.uniform(-1, 1) for _ in range(16)])
+            exact = rows(
+                db,
+                f"select rowid from v order by {function}(exact, ?) limit 10",
+                [q],
+            )
+            approx = rows(db, f"select rowid from v where {column} match ? and k = 10", [q])
+            hits += len(set(exact) & set(approx))
+        assert hits >= 60, column
+
+
+def test_quantize_pq_untrained(db):
+    db.execute("create virtual table v using vec0(a float[2] quantize=pq(m=1))")
+    # nothing to learn from, stays untrained
+    db.execute("insert into v(v) values ('train')")
+    assert rows(db, "select count(*) from v_pqcodebooks00") == [(0,)]
+
+    db.execute("insert into v(rowid, a) values (1, '[1, 2]'), (2, '[3, 4]')")
+    db.commit()
+    db.execute("begin")
+    db.execute("insert into v(v) values ('train')")
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 1024
+    db.execute("rollback")
+
+    # the rolled back codebooks are forgotten
+    assert db.execute("select length(vectors) from v_vector_chunks00").fetchone()[0] == 1024 * 8
+    assert rows(db, "select rowid, vec_to_json(a) from v") == [
+        (1, "[1.000000,2.000000]"),
+        (2, "[3.000000,4.000000]"),
+    ]
+    db.execute("insert into v(rowid, a) values (3, '[5, 6]')")
+    db.execute("insert into v(v) values ('train')")
+    # 3 rows for 256 centroids, so every vector is its own centroid
+    assert rows(db, "select rowid, distance from v where a match '[3, 4]' and k = 1") == [(2, 0.0)]
+
+
+def test_quantize_pq_constructor_errors(db):
+    for column in [
+        "a int8[8] quantize=pq",
+        "a float[4] quantize=pq",
+        "a float[8] quantize=pq(m=3)",
+        "a float[8] quantize=pq(nbits=9)",
+        "a float[8] quantize=pq(m=2, k=4)",
+        "a float[8] quantize=pq index=hnsw",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0({column})")
+    # one subvector of 8 dimensions by default
+    db.execute("create virtual table v using vec0(a float[16] quantize=pq)")
+    db.execute("insert into v(rowid, a) values (1, ?)", [_f32([1] * 16)])
+    db.execute("insert into v(v) values ('train')")
+    assert db.execute("select count(*) from v_pqcodebooks00").fetchone()[0] == 2
+
+
+def test_quantize_binary_storage(db):
+    db.execute(
+        "create virtual table v using vec0(a float[8] quantize=binary(rescore=2), chunk_size=8)"
+    )
+    db.execute("insert into v(rowid, a) values (1, '[1, -1, 2, -2, 0, 3, -3, 0.5]')")
+    # sign bits of the whole chunk, then the float vectors
+    (vectors,) = db.execute("select vectors from v_vector_chunks00").fetchone()
+    assert len(vectors) == 8 * 1 + 8 * 32
+    assert vectors[0] == 0b10100101
+    assert vectors[8:40] == _f32([1, -1, 2, -2, 0, 3, -3, 0.5])
+
+    # reads and distances are full precision
+    assert rows(db, "select vec_to_json(a) from v") == [
+        ("[1.000000,-1.000000,2.000000,-2.000000,0.000000,3.000000,-3.000000,0.500000]",)
+    ]
+    db.execute("update v set a = ? where rowid = 1", [_f32([1] * 8)])
+    db.execute("insert into v(rowid, a) values (2, ?)", [_f32([-1] * 8)])
+    db.execute(
+        "insert into v(v, rowid, a) values ('batch', ?, ?)",
+        [_i64([3]), _f32([1, 1, 1, 1, -1, -1, -1, -1])],
+    )
+    (vectors,) = db.execute("select vectors from v_vector_chunks00").fetchone()
+    assert vectors[:3] == bytes([0xFF, 0x00, 0x0F])
+    assert rows(db, "select rowid, distance from v where a match ? and k = 3", [_f32([1] * 8)]) == [
+        (1, 0.0),
+        (3, 4.0),
+        (2, pytest.approx(32 ** 0.5)),
+    ]
+    db.execute("delete from v where rowid = 1")
+    assert rows(db, "select rowid from v where a match ? and k = 3", [_f32([1] * 8)]) == [(3,), (2,)]
+
+
+def test_quantize_binary_knn(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[64] quantize=binary,
+          cosine float[64] distance_metric=cosine quantize=binary(rescore=16)
+        )
+        """
+    )
+    rng = random.Random(0)
+    vectors = [[rng.gauss(0, 1) for _ in range(64)] for _ in range(1000)]
+    db.execute(
+        "insert into v(v, rowid, a, cosine) values ('batch', ?, ?, ?)",
+        [_i64(range(1, 1001)), *([b"".join(_f32(v) for v in vectors)] * 2)],
+    )
+    hits = 0
+    for _ in range(10):
+        q = _f32([rng.gauss(0, 1) for _ in range(64)])
+        exact = rows(db, "select rowid, vec_distance_l2(a, ?) as d from v order by d limit 10", [q])
+        approx = rows(db, "select rowid, distance from v where a match ? and k = 10", [q])
+        # candidates are rescored with exact distances
+        assert [d for _, d in approx] == sorted(d for _, d in approx)
+        for rowid, distance in approx:
+            assert distance == pytest.approx(dict(rows(db, "select rowid, vec_distance_l2(a, ?) from v", [q]))[rowid], rel=1e-5)
+        hits += len(set(r[0] for r in exact) & set(r[0] for r in approx))
+
+        exact = rows(db, "select rowid from v order by vec_distance_cosine(cosine, ?) limit 10", [q])
+        approx = rows(db, "select rowid from v where cosine match ? and k = 10", [q])
+        hits += len(set(exact) & set(approx))
+    assert hits >= 100
+
+    # distance constraints use the exact scan
+    q = _f32([0] * 64)
+    first = rows(db, "select rowid, distance from v where a match ? and k = 5", [q])
+    after = rows(
+        db,
+        "select rowid, distance from v where a match ? and k = 5 and distance > ?",
+        [q, first[0][1]],
+    )
+    assert after[0][1] > first[0][1]
+    assert len(after) == 5
+
+    # and rowid filters are applied before rescoring
+    assert rows(db, "select rowid from v where a match ? and k = 5 and rowid in (3, 4)", [q]) == sorted(
+        rows(db, "select rowid from v where rowid in (3, 4)"),
+        key=lambda r: sum(x * x for x in vectors[r[0] - 1]),
+    )
+
+
+def test_quantize_binary_constructor_errors(db):
+    for column in [
+        "a float[12] quantize=binary",
+        "a int8[8] quantize=binary",
+        "a float[8] quantize=binary(rescore=0)",
+        "a float[8] quantize=binary(m=2)",
+        "a float[8] quantize=binary index=hnsw",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="could not parse vector column"):
+            db.execute(f"create virtual table v using vec0({column})")
@@ -0,0 +1,61 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_radius(db):
+    db.execute(
+        "create virtual table v using vec0(a float[1], label text, chunk_size=8, threads=2)"
+    )
+    db.executemany(
+        "insert into v(rowid, a, label) values (?, ?, ?)",
+        [(i, f"[{i}]", "even" if i % 2 == 0 else "odd") for i in range(1, 5001)],
+    )
+
+    assert rows(db, "select rowid, distance from v where a match '[50]' and distance < 2.5") == [
+        (50, 0.0),
+        (49, 1.0),
+        (51, 1.0),
+        (48, 2.0),
+        (52, 2.0),
+    ]
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("knn on v.a via chunk scan within distance",)
+    ]
+    assert rows(
+        db,
+        "select rowid from v where a match '[50]' and distance <= 2 and label = 'odd'",
+    ) == [(49,), (51,)]
+    assert rows(
+        db, "select rowid from v where a match '[50]' and distance > 1 and distance < 3"
+    ) == [(48,), (52,)]
+
+    # not capped at the k limit of top-k queries
+    (count,) = db.execute(
+        "select count(*) from v where a match '[0]' and distance <= 5000"
+    ).fetchone()
+    assert count == 5000
+
+    # a LIMIT still finds the closest rows
+    assert rows(
+        db, "select rowid from v where a match '[50.2]' and distance < 100 limit 2"
+    ) == [(50,), (51,)]
+
+
+def test_radius_errors(db):
+    db.execute("create virtual table v using vec0(a float[1])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="A LIMIT or 'k = \\?' constraint is required on vec0 knn queries.",
+    ):
+        db.execute("select rowid from v where a match '[1]' and distance > 1")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="mmr_lambda in knn query needs a LIMIT or 'k = \\?' constraint",
+    ):
+        db.execute(
+            "select rowid from v where a match '[1]' and distance < 1 and mmr_lambda = 0.5"
+        )
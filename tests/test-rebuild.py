@@ -0,0 +1,147 @@
+import sqlite3
+import random
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def recall(db, queries, k=5):
+    hits = 0
+    for q in queries:
+        approx = rows(db, "select rowid from v where embedding match ? and k = ?", [q, k])
+        exact = rows(db, "select rowid from v where exact match ? and k = ?", [q, k])
+        hits += len(set(approx) & set(exact))
+    return hits / (len(queries) * k)
+
+
+def fill(db, n, seed=0):
+    rng = random.Random(seed)
+    for i in range(1, n + 1):
+        v = _f32([rng.random() for _ in range(4)])
+        db.execute("insert into v(rowid, embedding, exact) values (?, ?, ?)", [i, v, v])
+    return [_f32([rng.random() for _ in range(4)]) for _ in range(10)]
+
+
+def test_rebuild(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[4] index=hnsw(m=8, ef_construction=40),
+          exact float[4],
+          chunk_size=8
+        )
+        """
+    )
+    queries = fill(db, 100)
+    db.execute("delete from v where rowid % 3 = 0")
+    assert rows(db, "select count(*) from v_chunks") == [(13,)]
+
+    db.execute("insert into v(v) values ('rebuild')")
+    assert rows(db, "select count(*) from v_chunks") == [(9,)]
+    assert rows(db, "select count(*) from v_hnsw00") == [(67,)]
+    assert rows(db, "select count(*) from v_hnswrebuild00") == [(0,)]
+    assert rows(db, "select key from v_info where key like '%rebuild%'") == []
+    assert rows(db, "select vec_rebuild_remaining('v')") == [(0,)]
+    assert recall(db, queries) >= 0.9
+
+
+def test_rebuild_incremental(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          embedding float[4] index=hnsw(m=8, ef_construction=40),
+          exact float[4],
+          chunk_size=8
+        )
+        """
+    )
+    queries = fill(db, 40)
+    db.execute("delete from v where rowid between 9 and 16")
+    assert rows(db, "select vec_rebuild_remaining('v')") == [(0,)]
+
+    # the first step only compacts a chunk
+    db.execute("insert into v(v) values ('rebuild=0')")
+    assert rows(db, "select vec_rebuild_remaining('v')") == [(33,)]
+    assert rows(db, "select count(*) from v_hnswrebuild00") == [(0,)]
+
+    # then each step adds chunk_size rows to the new graph
+    db.execute("insert into v(v) values ('rebuild=0')")
+    assert rows(db, "select vec_rebuild_remaining('v')") == [(25,)]
+    assert rows(db, "select rowid from v_hnswrebuild00") == [
+        (1,), (2,), (3,), (4,), (5,), (6,), (7,), (8,)
+    ]
+
+    # writes in the meantime go to the old graph, which queries still use
+    db.execute("delete from v where rowid = 2")
+    db.execute("update v set embedding = ?, exact = ? where rowid = 3", [_f32([9, 9, 9, 9])] * 2)
+    db.execute("insert into v(rowid, embedding, exact) values (5000, ?, ?)", [_f32([8, 8, 8, 8])] * 2)
+    assert rows(db, "select rowid from v where embedding match '[9, 9, 9, 9]' and k = 2") == [
+        (3,),
+        (5000,),
+    ]
+
+    steps = 0
+    while rows(db, "select vec_rebuild_remaining('v')")[0][0] > 0:
+        db.execute("insert into v(v) values ('rebuild=0')")
+        steps += 1
+    # 25 rows in 4 steps, one more for the chunk the delete left behind, then
+    # the swap
+    assert steps == 6
+    assert rows(db, "select count(*) from v_chunks") == [(4,)]
+
+    assert rows(db, "select count(*) from v_hnsw00") == [(32,)]
+    assert rows(db, "select count(*) from v_hnsw00 where rowid = 2") == [(0,)]
+    assert rows(db, "select vector from v_hnsw00 where rowid = 3") == [
+        (_f32([9, 9, 9, 9]),)
+    ]
+    assert rows(db, "select count(*) from v_hnswrebuild00") == [(0,)]
+    assert rows(db, "select rowid from v where embedding match '[9, 9, 9, 9]' and k = 2") == [
+        (3,),
+        (5000,),
+    ]
+    assert recall(db, queries) >= 0.9
+
+    # renaming or dropping the table mid-rebuild handles the new graph too
+    db.execute("insert into v(v) values ('rebuild=0')")
+    db.execute("alter table v rename to w")
+    assert rows(db, "select count(*) from w_hnswrebuild00") == [(8,)]
+    db.execute("insert into w(w) values ('rebuild')")
+    assert rows(db, "select count(*) from w_hnsw00") == [(32,)]
+    db.execute("insert into w(w) values ('rebuild=0')")
+    db.execute("drop table w")
+    assert rows(db, "select name from sqlite_master where name like 'w%'") == []
+
+
+def test_rebuild_ivf(db):
+    db.execute(
+        "create virtual table v using vec0(embedding float[4] index=ivf(nlist=4, nprobe=4), exact float[4])"
+    )
+    # tables without a trained IVF index are left untrained
+    queries = fill(db, 50)
+    db.execute("insert into v(v) values ('rebuild')")
+    assert rows(db, "select count(*) from v_ivfcentroids00") == [(0,)]
+
+    db.execute("insert into v(v) values ('train')")
+    db.execute("update v_ivflists00 set centroid_id = -1")
+    db.execute("insert into v(v) values ('rebuild')")
+    assert rows(db, "select count(*) from v_ivfcentroids00") == [(4,)]
+    assert rows(db, "select count(*) from v_ivflists00 where centroid_id = -1") == [(0,)]
+    assert recall(db, queries) == 1.0
+
+
+def test_rebuild_errors(db):
+    db.execute("create virtual table v using vec0(a float[1])")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="rebuild budget must be an integer between 0 and 3600000 milliseconds",
+    ):
+        db.execute("insert into v(v) values ('rebuild=soon')")
+    # tables without ANN indexes only have their chunks compacted
+    db.execute("insert into v(v) values ('rebuild')")
@@ -0,0 +1,87 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect(path):
+    db = sqlite3.connect(path)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    db.enable_load_extension(False)
+    return db
+
+
+def test_rechunk(tmp_path):
+    path = str(tmp_path / "rechunk.db")
+    db = connect(path)
+    db.execute(
+        """
+        create virtual table v using vec0(
+          user_id integer partition key,
+          a float[8],
+          b float[8] quantize=binary,
+          genre text,
+          +note text,
+          chunk_size=8
+        )
+        """
+    )
+    for i in range(1, 41):
+        vector = _f32([(i % 5) - 2 + j / 10 for j in range(8)])
+        db.execute(
+            "insert into v(rowid, user_id, a, b, genre, note) values (?, ?, ?, ?, ?, ?)",
+            [i, i % 2, vector, vector, f"a longer genre label {i}", f"note {i}"],
+        )
+    db.execute("delete from v where rowid % 7 = 0")
+    select = "select rowid, user_id, vec_to_json(a), vec_to_json(b), genre, note from v order by 1"
+    knn = "select rowid, distance from v where b match ? and k = 5 and user_id = 1"
+    query = _f32([0.5] * 8)
+    expected = rows(db, select)
+    expected_knn = rows(db, knn, [query])
+    assert rows(db, "select count(*) from v_chunks") == [(6,)]
+
+    db.execute("insert into v(v) values ('rechunk=32')")
+    assert rows(db, "select count(*), min(size), max(length(validity)) from v_chunks") == [
+        (2, 32, 4)
+    ]
+    assert rows(db, select) == expected
+    assert rows(db, knn, [query]) == expected_knn
+    assert rows(db, "select value from vec0_info('v') where key = 'chunk_size'") == [(32,)]
+    db.commit()
+    db.close()
+
+    # the new size outlives the declared chunk_size=8
+    db = connect(path)
+    db.execute(
+        "insert into v(rowid, user_id, a, b, genre, note) values (100, 1, ?, ?, 'x', 'y')",
+        [query, query],
+    )
+    assert rows(db, "select count(*) from v_chunks") == [(2,)]
+    assert rows(db, select)[:-1] == expected
+
+    db.execute("insert into v(v) values ('rechunk=8')")
+    assert rows(db, "select count(*) from v_chunks") == [(6,)]
+    assert rows(db, select)[:-1] == expected
+
+
+def test_rechunk_errors(db):
+    db.execute("create virtual table v using vec0(a float[1])")
+    for command in ["rechunk=0", "rechunk=12", "rechunk=8192", "rechunk=x"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="chunk_size must be a positive integer divisible by 8 and at most 4096",
+        ):
+            db.execute("insert into v(v) values (?)", [command])
+
+    # tables without any chunks only change their size
+    db.execute("insert into v(v) values ('rechunk=16')")
+    db.execute("insert into v(rowid, a) values (1, '[1]')")
+    assert rows(db, "select length(validity) from v_chunks") == [(2,)]
@@ -0,0 +1,191 @@
+import json
+import struct
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def last_plan(db):
+    return db.execute("select vec_debug_last_plan()").fetchone()[0]
+
+
+def fill(db, table, n=100):
+    db.execute(
+        f"insert into {table}(rowid, a) "
+        "select value, json_array(value, value) from json_each(?)",
+        [json.dumps(list(range(1, n + 1)))],
+    )
+
+
+KNN = "select rowid, distance from {} where a match '[3.1, 3.1]' and k = 3"
+
+
+def test_scan_sequential(db):
+    db.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    db.execute(
+        "create virtual table s using vec0(a float[2], chunk_size=8, scan=sequential)"
+    )
+    # more chunks than one batch of _chunks rows
+    fill(db, "v", 3000)
+    fill(db, "s", 3000)
+    db.execute("delete from s where rowid % 7 = 0")
+    db.execute("delete from v where rowid % 7 = 0")
+    for q in ["[3.1, 3.1]", "[2047, 2047]", "[2999, 2999]"]:
+        sql = "select rowid, distance from {} where a match ? and k = 10"
+        assert rows(db, sql.format("s"), [q]) == rows(db, sql.format("v"), [q])
+    assert last_plan(db) == "knn on v.a via chunk scan"
+
+    with pytest.raises(sqlite3.OperationalError, match="scan must be 'sequential'"):
+        db.execute("create virtual table x using vec0(a float[2], scan=random)")
+
+
+def test_summarize(db):
+    db.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    fill(db, "v")
+    expected = rows(db, KNN.format("v"))
+    db.execute("insert into v(v) values ('summarize')")
+    assert rows(db, "select count(*) from v_chunksummaries") == [(13,)]
+    assert rows(db, KNN.format("v")) == expected
+    assert last_plan(db) == "knn on v.a via chunk scan, 12 of 13 chunks skipped"
+    assert rows(
+        db, "select rowid from v where a match '[93, 93]' and k = 3"
+    ) == [(93,), (92,), (94,)]
+
+    # any write drops the summaries, until 'summarize' runs again
+    db.execute("delete from v where rowid = 4")
+    assert rows(db, "select count(*) from v_chunksummaries") == [(0,)]
+    assert rows(db, KNN.format("v"))[:2] == expected[:1] + expected[2:]
+    assert last_plan(db) == "knn on v.a via chunk scan"
+    db.execute("insert into v(v) values ('summarize')")
+    assert rows(db, KNN.format("v"))[0] == expected[0]
+    assert last_plan(db) == "knn on v.a via chunk scan, 12 of 13 chunks skipped"
+
+    # ignored by scans that can't use them
+    db.execute("insert into v(v) values ('flush')")
+    assert rows(db, "select count(*) from v_chunksummaries") == [(13,)]
+    rows(db, "select rowid from v where a match '[3.1, 3.1]' and distance < 2")
+    assert last_plan(db) == "knn on v.a via chunk scan within distance"
+
+
+def test_summarize_l1_metadata(db):
+    db.execute(
+        "create virtual table v using vec0("
+        "a float[2] distance_metric=l1, kind text, chunk_size=8, scan=sequential)"
+    )
+    db.execute(
+        "insert into v(rowid, a, kind) "
+        "select value, json_array(value, -value), iif(value % 2, 'odd', 'even') "
+        "from json_each(?)",
+        [json.dumps(list(range(1, 101)))],
+    )
+    db.execute("insert into v(v) values ('summarize')")
+    assert rows(
+        db,
+        "select rowid, distance from v "
+        "where a match '[10, -10]' and k = 2 and kind = 'odd'",
+    ) == [(9, 2.0), (11, 2.0)]
+    assert last_plan(db).endswith("11 of 13 chunks skipped")
+
+
+def test_summarize_errors(db):
+    db.execute(
+        "create virtual table v using vec0("
+        "a float[2] distance_metric=cosine, b int8[2], chunk_size=8)"
+    )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="'summarize' requires a float32 vector column with distance_metric=l2 or l1",
+    ):
+        db.execute("insert into v(v) values ('summarize')")
+
+
+def test_summaries_shadow_table(db):
+    db.execute("create virtual table v using vec0(a float[2], chunk_size=8)")
+    fill(db, "v", 20)
+    db.execute("insert into v(v) values ('summarize')")
+    db.execute("alter table v rename to w")
+    assert rows(db, "select count(*) from w_chunksummaries") == [(3,)]
+    rows(db, KNN.format("w"))
+    assert last_plan(db) == "knn on w.a via chunk scan, 2 of 3 chunks skipped"
+
+    assert db.execute("select vec0_copy('w', 'c')").fetchone()[0] == 20
+    assert rows(db, "select count(*) from c_chunksummaries") == [(3,)]
+    (snapshot,) = db.execute("select vec0_serialize('w')").fetchone()
+    assert rows(db, "select vec0_deserialize(?, 'd')", [snapshot]) == [("d",)]
+    rows(db, KNN.format("d"))
+    assert last_plan(db) == "knn on d.a via chunk scan, 2 of 3 chunks skipped"
+
+    db.execute("drop table w")
+    db.execute("drop table c")
+    db.execute("drop table d")
+    assert rows(
+        db, "select name from sqlite_master where name like '%chunksummaries'"
+    ) == []
+
+
+def test_chunk_summaries_kept(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], chunk_size=8, chunk_summaries=1)"
+    )
+    db.execute("create virtual table plain using vec0(a float[2], chunk_size=8)")
+    for i in range(1, 101):
+        for table in ["v", "plain"]:
+            db.execute(
+                f"insert into {table}(rowid, a) values (?, json_array(?, ?))",
+                [i, i, i],
+            )
+    # full chunks get the mean as centroid, the last one its first vector
+    assert rows(db, "select chunk_id, radius from v_chunksummaries")[-2:] == [
+        (12, pytest.approx(4.9497475)),
+        (13, pytest.approx(4.2426407)),
+    ]
+    assert rows(db, KNN.format("v")) == rows(db, KNN.format("plain"))
+    assert last_plan(db) == "knn on plain.a via chunk scan"
+    rows(db, KNN.format("v"))
+    assert last_plan(db) == "knn on v.a via chunk scan, 12 of 13 chunks skipped"
+
+    # writes grow the summaries, so far away vectors are still found
+    for table in ["v", "plain"]:
+        db.execute(f"update {table} set a = '[1000, 1000]' where rowid = 10")
+        db.execute(f"delete from {table} where rowid = 3")
+        db.execute(
+            f"insert into {table}({table}, rowid, a) values ('batch', ?, ?)",
+            [
+                struct.pack("2q", 101, 102),
+                struct.pack("4f", 4, 4, -5, -5),
+            ],
+        )
+    for q in ["[3.1, 3.1]", "[999, 999]", "[-4, -4]", "[50, 50]"]:
+        sql = "select rowid, distance from {} where a match ? and k = 4"
+        assert rows(db, sql.format("v"), [q]) == rows(db, sql.format("plain"), [q])
+
+    # as do optimize and rechunk, which move rows to new chunks
+    for command in ["optimize", "rechunk=16"]:
+        for table in ["v", "plain"]:
+            db.execute(f"insert into {table}({table}) values (?)", [command])
+        for q in ["[3.1, 3.1]", "[999, 999]", "[-4, -4]", "[50, 50]"]:
+            sql = "select rowid, distance from {} where a match ? and k = 4"
+            assert rows(db, sql.format("v"), [q]) == rows(
+                db, sql.format("plain"), [q]
+            )
+    assert rows(
+        db,
+        "select count(*) from v_chunksummaries "
+        "where chunk_id not in (select chunk_id from v_chunks)",
+    ) == [(0,)]
+    rows(db, "select rowid from v where a match '[50, 50]' and k = 2")
+    assert "chunks skipped" in last_plan(db)
+
+    with pytest.raises(sqlite3.OperationalError, match="chunk_summaries must be 0 or 1"):
+        db.execute("create virtual table x using vec0(a float[2], chunk_summaries=2)")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="chunk_summaries=1 requires a float32 vector column with distance_metric=l2 or l1",
+    ):
+        db.execute(
+            "create virtual table x using vec0("
+            "a float[2] distance_metric=cosine, chunk_summaries=1)"
+        )
@@ -0,0 +1,92 @@
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_row_version(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          id text primary key,
+          a float[2],
+          +note text,
+          row_version=true,
+          tag text,
+          shard integer partition key
+        )
+        """
+    )
+    for i in range(1, 4):
+        db.execute(
+            "insert into v(id, a, note, tag, shard) values (?, ?, ?, 'x', 0)",
+            [f"doc{i}", f"[{i}, 0]", f"note {i}"],
+        )
+    # hidden, so select * leaves it out
+    assert rows(db, "select * from v where id = 'doc1'")[0][2:] == ("note 1", "x", 0)
+    assert rows(db, "select id, __version from v") == [
+        ("doc1", 1),
+        ("doc2", 1),
+        ("doc3", 1),
+    ]
+
+    db.execute("update v set a = '[9, 9]' where id = 'doc1'")
+    db.execute("update v set note = 'edited' where id = 'doc1'")
+    db.execute("update v set tag = 'y', shard = 1 where id = 'doc2'")
+    assert rows(db, "select id, __version from v order by id") == [
+        ("doc1", 3),
+        ("doc2", 2),
+        ("doc3", 1),
+    ]
+    assert rows(db, "select id from v where __version > 1 order by id") == [
+        ("doc1",),
+        ("doc2",),
+    ]
+    assert rows(
+        db, "select id, __version from v where a match '[9, 9]' and k = 1"
+    ) == [("doc1", 3)]
+
+    # a deleted and re-inserted row starts over
+    db.execute("delete from v where id = 'doc1'")
+    db.execute("insert into v(id, a, tag, shard) values ('doc1', '[1, 1]', 'x', 0)")
+    assert rows(db, "select __version from v where id = 'doc1'") == [(1,)]
+
+    # columns added later don't move it
+    db.execute("insert into v(v) values ('add_column=+extra text')")
+    db.execute("update v set extra = 'e' where id = 'doc3'")
+    assert rows(db, "select __version, note, extra from v where id = 'doc3'") == [
+        (2, "note 3", "e")
+    ]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_row_version_errors(db):
+    db.execute("create virtual table v using vec0(a float[2], row_version=true)")
+    db.execute("insert into v(rowid, a) values (1, '[1, 2]')")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='A value was provided for the hidden "__version" column.',
+    ):
+        db.execute("insert into v(rowid, a, __version) values (2, '[1, 2]', 5)")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='The "__version" column can\'t be updated, vec0 increments it on every UPDATE',
+    ):
+        db.execute("update v set __version = 5 where rowid = 1")
+    assert rows(db, "select __version from v") == [(1,)]
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="__version can't be dropped, it's the row_version column",
+    ):
+        db.execute("insert into v(v) values ('drop_column=__version')")
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0 constructor error: row_version must be true or false",
+    ):
+        db.execute("create virtual table w using vec0(a float[2], row_version=1)")
+    db.execute("create virtual table w using vec0(a float[2], row_version=false)")
+    with pytest.raises(sqlite3.OperationalError, match="no such column: __version"):
+        db.execute("select __version from w")
@@ -0,0 +1,127 @@
+import random
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect():
+    db = sqlite3.connect(":memory:")
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    db.enable_load_extension(False)
+    return db
+
+
+def test_snapshot_round_trip(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          id text primary key,
+          a float[8] index=hnsw(m=8, ef_construction=40),
+          b float[8] index=ivf(nlist=4),
+          c float[8] quantize=int8,
+          shard integer partition key,
+          tag text,
+          +note text,
+          chunk_size=16
+        )
+        """
+    )
+    rng = random.Random(3)
+    for i in range(200):
+        vector = _f32([rng.uniform(-1, 1) for _ in range(8)])
+        db.execute(
+            "insert into v(id, a, b, c, shard, tag, note) values (?, ?, ?, ?, ?, ?, ?)",
+            [f"doc{i}", vector, vector, vector, i % 2, f"tag{i % 5}", f"note {i}"],
+        )
+    db.execute("insert into v(v) values ('train')")
+    db.execute("delete from v where id in ('doc3', 'doc42')")
+    snapshot = db.execute("select vec0_serialize('v')").fetchone()[0]
+
+    copy = connect()
+    assert rows(copy, "select vec0_deserialize(?)", [snapshot]) == [("v",)]
+    query = _f32([0.5] * 8)
+    for column in ["a", "b", "c"]:
+        knn = (
+            f"select id, distance, tag, note from v where {column} match ? "
+            "and k = 10 and shard = 1 and tag != 'tag2'"
+        )
+        assert rows(copy, knn, [query]) == rows(db, knn, [query])
+    # the trained IVF index and the HNSW graph come along, nothing is rebuilt
+    copy.execute("select id from v where b match ? and k = 1", [query]).fetchall()
+    assert rows(copy, "select vec_debug_last_plan()") == [
+        ("knn on v.b via ivf index",)
+    ]
+    for shadow in ["v_hnsw00", "v_ivfcentroids01", "v_ivflists01"]:
+        assert rows(copy, f"select * from {shadow}") == rows(
+            db, f"select * from {shadow}"
+        )
+    assert copy.execute("select vec0_serialize('v')").fetchone()[0] == snapshot
+
+    # the copy is an ordinary table
+    copy.execute(
+        "insert into v(id, a, b, c, shard, tag) values ('new', ?, ?, ?, 0, 'tag0')",
+        [query, query, query],
+    )
+    assert rows(copy, "select id from v where a match ? and k = 1", [query]) == [
+        ("new",)
+    ]
+
+
+def test_snapshot_transactions(db):
+    db.execute("create virtual table v using vec0(a float[2])")
+    db.execute("insert into v(rowid, a) values (1, ?)", [_f32([1, 2])])
+    db.execute("create table snapshots(name, snapshot)")
+    db.execute("insert into snapshots select 'v', vec0_serialize('v')")
+
+    # deserializing from a query over another table
+    assert rows(
+        db, "select vec0_deserialize(snapshot, name || '_copy') from snapshots"
+    ) == [("v_copy",)]
+    assert rows(db, "select rowid from v_copy") == [(1,)]
+
+    # a rolled back transaction drops the new table with it
+    db.commit()
+    db.execute("begin")
+    db.execute("select vec0_deserialize(snapshot, 'w') from snapshots")
+    db.execute("rollback")
+    assert rows(db, "select name from sqlite_master where name like 'w%'") == []
+
+
+def test_snapshot_rejects_tampering(db):
+    db.execute("create virtual table v using vec0(a float[2])")
+    snapshot = db.execute("select vec0_serialize('v')").fetchone()[0]
+    name = struct.pack("<I", 1) + b"v"
+    args = struct.pack("<I", 10) + b"a float[2]"
+    assert snapshot.startswith(b"VEC0SNAP" + struct.pack("<I", 1) + name + args)
+    header, rest = snapshot[: 12 + len(name)], snapshot[12 + len(name) + len(args) :]
+
+    db.execute("create table victim(x)")
+    evil = b"a float[2]); drop table victim; --"
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_deserialize\\(\\) snapshot has invalid table arguments",
+    ):
+        db.execute(
+            "select vec0_deserialize(?, 'w')",
+            [header + struct.pack("<I", len(evil)) + evil + rest],
+        )
+    assert rows(db, "select count(*) from victim") == [(0,)]
+
+    # only vec0's own shadow tables are written to
+    evil = rest.replace(b"\x04\x00\x00\x00info", b"\x04\x00\x00\x00xxxx", 1)
+    assert evil != rest
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="vec0_deserialize\\(\\) snapshot has an unknown shadow table xxxx",
+    ):
+        db.execute("select vec0_deserialize(?, 'w')", [header + args + evil])
+    assert rows(db, "select name from sqlite_master where name like 'w%'") == []
@@ -0,0 +1,128 @@
+import re
+import sqlite3
+import struct
+import pytest
+
+
+def _sparse(pairs):
+    return b"".join(struct.pack("<If", i, v) for i, v in sorted(pairs.items()))
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_sparse_columns(db):
+    db.execute(
+        "create virtual table v using vec0(embedding float[2], terms sparse[1000], +label text)"
+    )
+    db.execute(
+        """
+        insert into v(rowid, embedding, terms, label) values
+          (1, '[1, 1]', '{"3": 1.5, "10": 0.5}', 'a'),
+          (2, '[1, 2]', '{"10": 2}', 'b'),
+          (3, '[0, 0]', null, 'c'),
+          (4, '[2, 1]', ?, 'd')
+        """,
+        [_sparse({999: 1.0, 3: 0.25})],
+    )
+
+    # JSON is stored in the packed format, sorted by index
+    assert rows(db, "select rowid, terms from v order by rowid") == [
+        (1, _sparse({3: 1.5, 10: 0.5})),
+        (2, _sparse({10: 2})),
+        (3, None),
+        (4, _sparse({3: 0.25, 999: 1.0})),
+    ]
+
+    # rows without a sparse vector are never returned
+    knn = "select rowid, distance from v where terms match ? and k = ?"
+    assert rows(db, knn, ['{"10": 1, "3": 1}', 5]) == [
+        (1, -2.0),
+        (2, -2.0),
+        (4, -0.25),
+    ]
+    assert rows(db, knn, ['{"3": 1}', 1]) == [(1, -1.5)]
+    assert rows(db, knn, ['{"3": 1}', 0]) == []
+    if sqlite3.sqlite_version_info >= (3, 41, 0):
+        assert rows(
+            db,
+            "select rowid, label from v where terms match ? order by distance limit 1 offset 1",
+            ['{"10": 1}'],
+        ) == [(1, "a")]
+    assert rows(
+        db,
+        "select rowid from v where terms match ? and k = 5 and rowid in (2, 3, 4)",
+        ['{"10": 1, "3": 1}'],
+    ) == [(2,), (4,)]
+    assert rows(
+        db,
+        "select rowid from v where terms match ? and k = 5 and rowid in (3)",
+        ['{"10": 1}'],
+    ) == []
+
+    # dense columns are unaffected
+    assert rows(db, "select rowid from v where embedding match '[1, 1]' and k = 1") == [
+        (1,)
+    ]
+
+    db.execute("""update v set terms = '{"3": 4}' where rowid = 2""")
+    db.execute("update v set terms = null where rowid = 1")
+    assert rows(db, knn, ['{"3": 1}', 5]) == [(2, -4.0), (4, -0.25)]
+
+    db.execute("delete from v where rowid = 2")
+    assert rows(db, knn, ['{"3": 1}', 5]) == [(4, -0.25)]
+
+    assert rows(db, "select vec_debug_last_plan()") == [
+        ("knn on v.terms via sparse scan",)
+    ]
+
+
+def test_sparse_columns_errors(db):
+    db.execute("create virtual table v using vec0(embedding float[1], terms sparse[100], genre text)")
+
+    for value, message in [
+        ('{"100": 1}', "sparse vector index 100 is out of range for 100 dimensions."),
+        ('{"1": 1, "1": 2}', "sparse vector has a duplicate index 1."),
+        ("[1, 2]", "JSON sparse vector parsing error"),
+        (3, "sparse vectors must be a BLOB or JSON TEXT, found INTEGER."),
+    ]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=re.escape('Invalid sparse vector for the "terms" column: ' + message),
+        ):
+            db.execute(
+                "insert into v(rowid, embedding, terms, genre) values (1, '[1]', ?, 'x')",
+                [value],
+            )
+    # nothing was written by the failed inserts
+    assert rows(db, "select count(*) from v_rowids") == [(0,)]
+
+    db.execute(
+        """insert into v(rowid, embedding, terms, genre) values (1, '[1]', '{"1": 1}', 'x')"""
+    )
+    with pytest.raises(
+        sqlite3.OperationalError, match='Invalid sparse vector for the "terms" column'
+    ):
+        db.execute("""update v set terms = '{"200": 1}' where rowid = 1""")
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match=r'Query vector on the "terms" column is invalid: sparse vector index 500',
+    ):
+        db.execute("""select * from v where terms match '{"500": 1}' and k = 1""")
+
+    for constraint in ["genre = 'x'", "distance < 0"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match=r'KNN queries on the sparse column "terms" only support k, LIMIT, OFFSET and rowid in \(...\) constraints',
+        ):
+            db.execute(
+                f"""select * from v where terms match '{{"1": 1}}' and k = 1 and {constraint}"""
+            )
+
+    for definition in ["terms sparse[0]", "terms sparse", "terms sparse[10] distance_metric=l2"]:
+        with pytest.raises(
+            sqlite3.OperationalError, match="could not parse sparse column"
+        ):
+            db.execute(f"create virtual table w using vec0(a float[1], {definition})")
@@ -0,0 +1,146 @@
+import math
+import random
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def write_vectors(path, vectors):
+    path.write_bytes(b"".join(_f32(v) for v in vectors))
+    return str(path)
+
+
+def test_static_file_knn(db, tmp_path):
+    rng = random.Random(0)
+    # more vectors than one block of the file, with a partial last block
+    vectors = [[rng.uniform(-1, 1) for _ in range(8)] for _ in range(2500)]
+    path = write_vectors(tmp_path / "vectors.f32", vectors)
+    db.execute(
+        f"create virtual table v using vec_static_file(embedding float[8], path='{path}')"
+    )
+    db.execute(
+        f"create virtual table c using vec_static_file("
+        f"embedding float[8] distance_metric=cosine, path='{path}')"
+    )
+    q = [rng.uniform(-1, 1) for _ in range(8)]
+
+    l2 = sorted(
+        (math.sqrt(sum((a - b) ** 2 for a, b in zip(v, q))), i)
+        for i, v in enumerate(vectors)
+    )
+    result = rows(
+        db, "select rowid, distance from v where embedding match ? and k = 5", [_f32(q)]
+    )
+    assert [r[0] for r in result] == [i for _, i in l2[:5]]
+    assert [r[1] for r in result] == pytest.approx([d for d, _ in l2[:5]], rel=1e-5)
+    if sqlite3.sqlite_version_info >= (3, 41, 0):
+        assert rows(
+            db,
+            "select rowid from v where embedding match ? order by distance limit 5",
+            [_f32(q)],
+        ) == [(i,) for _, i in l2[:5]]
+    assert rows(db, "select rowid from v where embedding match ? and k = 0", [_f32(q)]) == []
+
+    norm = lambda v: math.sqrt(sum(x * x for x in v))
+    cosine = sorted(
+        (1 - sum(a * b for a, b in zip(v, q)) / (norm(v) * norm(q)), i)
+        for i, v in enumerate(vectors)
+    )
+    assert rows(
+        db, "select rowid from c where embedding match ? and k = 3", [_f32(q)]
+    ) == [(i,) for _, i in cosine[:3]]
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="A LIMIT or 'k = \\?' constraint is required on vec_static_file knn queries.",
+    ):
+        db.execute("select rowid from v where embedding match ?", [_f32(q)])
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='Dimension mismatch for query vector for the "embedding" column. '
+        "Expected 8 float32 dimensions but received 2.",
+    ):
+        db.execute("select rowid from v where embedding match '[1, 2]' and k = 1")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="k value in knn queries must be an integer between 0 and 4096",
+    ):
+        db.execute("select rowid from v where embedding match ? and k = 5000", [_f32(q)])
+
+
+def test_static_file_lookups(db, tmp_path):
+    vectors = [[i, -i] for i in range(10)]
+    path = write_vectors(tmp_path / "vectors.f32", vectors)
+    db.execute(f"create virtual table v using vec_static_file(a float[2], path='{path}')")
+    assert rows(db, "select rowid, vec_to_json(a) from v where rowid = 3") == [
+        (3, "[3.000000,-3.000000]")
+    ]
+    assert rows(db, "select rowid from v where rowid = 10") == []
+    assert rows(db, "select rowid from v where rowid = -1") == []
+    assert rows(db, "select count(*), sum(rowid) from v") == [(10, 45)]
+    assert rows(db, "select vec_to_json(a) from v order by rowid limit 2") == [
+        ("[0.000000,0.000000]",),
+        ("[1.000000,-1.000000]",),
+    ]
+    assert rows(db, "select distance from v where rowid = 1") == [(None,)]
+
+    # metadata stays in ordinary tables
+    db.execute("create table docs(id integer primary key, title text)")
+    db.executemany("insert into docs values (?, ?)", [(i, f"doc {i}") for i in range(10)])
+    assert rows(
+        db,
+        "select docs.title, v.distance from v join docs on docs.id = v.rowid "
+        "where a match '[7.9, -7.9]' and k = 2",
+    ) == [
+        ("doc 8", pytest.approx(math.sqrt(0.02), rel=1e-5)),
+        ("doc 7", pytest.approx(math.sqrt(1.62), rel=1e-5)),
+    ]
+
+    # the file is read at every query
+    write_vectors(tmp_path / "vectors.f32", vectors[:4])
+    assert rows(db, "select count(*) from v") == [(4,)]
+
+    for sql in [
+        "insert into v(rowid, a) values (10, '[1, 1]')",
+        "update v set a = '[1, 1]' where rowid = 1",
+        "delete from v where rowid = 1",
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="may not be modified"):
+            db.execute(sql)
+    db.execute("drop table v")
+    assert (tmp_path / "vectors.f32").exists()
+
+
+def test_static_file_errors(db, tmp_path):
+    path = tmp_path / "vectors.f32"
+    path.write_bytes(_f32([1, 2, 3]))
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match=f"vector file {path} is 12 bytes, not a multiple of 8 byte vectors",
+    ):
+        db.execute(f"create virtual table v using vec_static_file(a float[2], path='{path}')")
+    with pytest.raises(sqlite3.OperationalError, match="could not open vector file missing.f32"):
+        db.execute("create virtual table v using vec_static_file(a float[2], path='missing.f32')")
+    for args, message in [
+        ("a float[2]", "a float\\[N\\] vector column and a path are required"),
+        (f"path='{path}'", "a float\\[N\\] vector column and a path are required"),
+        (f"a int8[2], path='{path}'", "the vector column must be a float\\[N\\] column"),
+        (f"a float[2] distance_metric=hamming, path='{path}'", "Unknown distance_metric"),
+        (f"a float[3], b float[3], path='{path}'", "only one vector column is supported"),
+        (f"a float[3], size=4, path='{path}'", "unknown argument 'size=4'"),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match="vec_static_file constructor error: " + message):
+            db.execute(f"create virtual table v using vec_static_file({args})")
+
+    db.execute(f"create virtual table v using vec_static_file(a float[3], path='{path}')")
+    path.unlink()
+    with pytest.raises(sqlite3.OperationalError, match="could not open vector file"):
+        db.execute("select * from v")
@@ -0,0 +1,72 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect(path=":memory:"):
+    db = sqlite3.connect(path)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+def test_strict_off():
+    db = connect()
+    db.execute(
+        "create virtual table v using vec0(a float[2], b int8[2], c float16[2], "
+        "d bit[8], strict=off)"
+    )
+    nan, inf = float("nan"), float("inf")
+    db.execute(
+        "insert into v(rowid, a, b, c, d) values (1, ?, ?, ?, vec_bit(x'0f'))",
+        [_f32([nan, inf]), "[1.6, -300]", "[1e10, 0.5]"],
+    )
+    # NaNs become 0, infinities the largest finite value, and JSON is
+    # converted to the column's element type
+    flt_max = struct.unpack("f", b"\xff\xff\x7f\x7f")[0]
+    assert rows(db, "select a, vec_to_json(b), vec_to_json(c) from v") == [
+        (_f32([0, flt_max]), "[2,-128]", "[65504.000000,0.500000]")
+    ]
+
+    # updates are coerced the same way, and int8 vectors widened for float columns
+    db.execute("update v set a = vec_int8('[3, 4]'), b = ? where rowid = 1", [_f32([-inf, 7.4])])
+    assert rows(db, "select vec_to_json(a), vec_to_json(b) from v") == [
+        ("[3.000000,4.000000]", "[-128,7]")
+    ]
+
+    # wrong dimensions and bit vectors still fail
+    with pytest.raises(sqlite3.OperationalError, match="Dimension mismatch for inserted vector"):
+        db.execute(
+            "insert into v(rowid, a, b, c, d) values (2, '[1]', '[1, 2]', '[1, 2]', vec_bit(x'0f'))"
+        )
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='Inserted vector for the "d" column is expected to be of type bit',
+    ):
+        db.execute(
+            "insert into v(rowid, a, b, c, d) values (2, '[1, 2]', '[1, 2]', '[1, 2]', '[1]')"
+        )
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_strict_on():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[2], b int8[2], strict=on)")
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match='Inserted vector for the "b" column is expected to be of type int8',
+    ):
+        db.execute("insert into v(rowid, a, b) values (1, '[1, 2]', '[1, 2]')")
+
+    with pytest.raises(
+        sqlite3.OperationalError, match="vec0 constructor error: strict must be on or off"
+    ):
+        db.execute("create virtual table u using vec0(a float[2], strict=maybe)")
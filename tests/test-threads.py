@@ -0,0 +1,77 @@
+import sqlite3
+import random
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_threads_match_single_threaded_scan(db):
+    random.seed(7)
+    for threads in [1, 4]:
+        db.execute(
+            f"""
+            create virtual table v{threads} using vec0(
+              a float[8],
+              b float[8] distance_metric=cosine,
+              c bit[64],
+              genre text,
+              chunk_size=8,
+              threads={threads}
+            )
+            """
+        )
+    for i in range(1, 101):
+        a = [random.uniform(-1, 1) for _ in range(8)]
+        b = [random.uniform(-1, 1) for _ in range(8)]
+        c = bytes(random.getrandbits(8) for _ in range(8))
+        genre = random.choice(["rock", "jazz", "pop"])
+        for t in ["v1", "v4"]:
+            db.execute(
+                f"insert into {t}(rowid, a, b, c, genre) values (?, ?, ?, vec_bit(?), ?)",
+                [i, _f32(a), _f32(b), c, genre],
+            )
+    db.execute("delete from v1 where rowid % 7 = 0")
+    db.execute("delete from v4 where rowid % 7 = 0")
+
+    q = _f32([random.uniform(-1, 1) for _ in range(8)])
+    qbits = bytes(random.getrandbits(8) for _ in range(8))
+    for where, params in [
+        ("a match ? and k = 10", [q]),
+        ("b match ? and k = 25", [q]),
+        ("c match vec_bit(?) and k = 5", [qbits]),
+        ("a match ? and k = 200", [q]),
+        ("a match ? and k = 10 and genre = 'jazz'", [q]),
+        ("a match ? and k = 10 and distance > 1.5", [q]),
+        ("a match ? and k = 10 and rowid in (3, 4, 5, 50, 51)", [q]),
+    ]:
+        expected = rows(db, f"select rowid, distance from v1 where {where}", params)
+        assert len(expected) > 0
+        assert rows(db, f"select rowid, distance from v4 where {where}", params) == expected
+
+
+def test_threads_partitions(db):
+    db.execute(
+        "create virtual table v using vec0(user_id integer partition key, a float[1], chunk_size=8, threads=3)"
+    )
+    db.executemany(
+        "insert into v(rowid, user_id, a) values (?, ?, ?)",
+        [(i, i % 2, _f32([i])) for i in range(1, 51)],
+    )
+    assert rows(db, "select rowid from v where a match ? and k = 3 and user_id = 1", [_f32([20.2])]) == [
+        (21,),
+        (19,),
+        (23,),
+    ]
+
+
+def test_threads_constructor_errors(db):
+    for option in ["threads=0", "threads=65"]:
+        with pytest.raises(sqlite3.OperationalError, match="threads must be"):
+            db.execute(f"create virtual table v using vec0(a float[1], {option})")
@@ -0,0 +1,164 @@
+import json
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def chunks_scanned(db):
+    stats = json.loads(db.execute("select vec0_stats('reset')").fetchone()[0])
+    return stats["chunks_scanned"]
+
+
+def ranges(db, table="v_metadatachunks00"):
+    return rows(db, f"select rowid, min_value, max_value from {table}")
+
+
+def fill(db, n=100):
+    db.execute(
+        "insert into v(rowid, a, published_at) "
+        "select value, json_array(value, value), 1000 + value from json_each(?)",
+        [json.dumps(list(range(1, n + 1)))],
+    )
+
+
+KNN = "select rowid from v where a match '[3, 3]' and k = 3 and "
+
+
+def test_timestamp_range_pruning(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], published_at timestamp, chunk_size=8)"
+    )
+    db.execute(
+        "create virtual table plain using vec0(a float[2], published_at integer, chunk_size=8)"
+    )
+    fill(db)
+    db.execute("insert into plain select rowid, a, published_at from v")
+    assert ranges(db)[:2] == [(1, 1001, 1008), (2, 1009, 1016)]
+    assert ranges(db)[-1] == (13, 1097, 1100)
+    chunks_scanned(db)
+
+    for condition, scanned in [
+        ("published_at > 1090", 2),
+        ("published_at >= 1097", 1),
+        ("published_at < 1009", 1),
+        ("published_at <= 1009", 2),
+        ("published_at = 1042", 1),
+        ("published_at is 1042", 1),
+        ("published_at between 1009 and 1016", 1),
+        ("published_at > 1200", 0),
+        # not ruled out by the range
+        ("published_at != 1042", 13),
+        ("published_at in (1001, 1100)", 13),
+        ("published_at > 1090.5", 13),
+    ]:
+        expected = rows(db, (KNN + condition).replace("from v", "from plain"))
+        chunks_scanned(db)
+        assert rows(db, KNN + condition) == expected, condition
+        assert chunks_scanned(db) == scanned, condition
+
+    with pytest.raises(
+        sqlite3.OperationalError,
+        match="Expected integer for TIMESTAMP metadata column published_at, received TEXT",
+    ):
+        db.execute(
+            "insert into v(rowid, a, published_at) values (101, '[1, 1]', '2024-01-01')"
+        )
+
+
+def test_timestamp_range_writes(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], published_at timestamp, chunk_size=8)"
+    )
+    fill(db, 20)
+    assert ranges(db) == [(1, 1001, 1008), (2, 1009, 1016), (3, 1017, 1020)]
+
+    # updates widen the range, and deletes leave it as it is
+    db.execute("update v set published_at = 5000 where rowid = 2")
+    db.execute("delete from v where rowid = 20")
+    assert ranges(db) == [(1, 1001, 5000), (2, 1009, 1016), (3, 1017, 1020)]
+    chunks_scanned(db)
+    assert rows(db, KNN + "published_at > 2000") == [(2,)]
+    assert chunks_scanned(db) == 1
+
+    # optimize and rechunk compute them again for the new chunks
+    db.execute("delete from v where rowid between 9 and 12")
+    db.execute("insert into v(v) values ('optimize')")
+    assert ranges(db) == [(4, 1001, 5000), (5, 1013, 1019)]
+    db.execute("insert into v(v) values ('rechunk=16')")
+    assert ranges(db) == [(6, 1001, 5000)]
+    assert rows(db, KNN + "published_at > 1016") == [(2,), (17,), (18,)]
+
+    # a chunk without any rows yet has an empty range
+    db.execute(
+        "create virtual table e using vec0(a float[2], published_at timestamp, "
+        "category integer partition key, chunk_size=8)"
+    )
+    db.execute("insert into e(rowid, a, published_at, category) values (1, '[1, 1]', 10, 1)")
+    db.execute("update e set category = 2 where rowid = 1")
+    assert ranges(db, "e_metadatachunks00") == [(1, 10, 10), (2, 10, 10)]
+    db.execute("delete from e where rowid = 1")
+    db.execute("insert into e(rowid, a, published_at, category) values (2, '[2, 2]', 20, 3)")
+    assert ranges(db, "e_metadatachunks00")[-1] == (3, 20, 20)
+    chunks_scanned(db)
+    assert rows(
+        db,
+        "select rowid from e where a match '[1, 1]' and k = 2 "
+        "and category >= 2 and published_at >= 20",
+    ) == [(2,)]
+    assert chunks_scanned(db) == 1
+
+
+def test_timestamp_schema_changes(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], n integer, chunk_size=8)"
+    )
+    db.execute(
+        "insert into v(rowid, a, n) "
+        "select value, json_array(value, value), value from json_each(?)",
+        [json.dumps(list(range(1, 21)))],
+    )
+    # a column added later is 0 in every existing row, and reuses no table
+    # left behind by 'drop_column'
+    db.execute("insert into v(v) values ('add_column=other integer')")
+    db.execute("insert into v(v) values ('drop_column=other')")
+    db.execute("insert into v(v) values ('add_column=published_at timestamp')")
+    assert ranges(db, "v_metadatachunks01") == [(1, 0, 0), (2, 0, 0), (3, 0, 0)]
+    db.execute("update v set published_at = 1000 + rowid where rowid > 16")
+    chunks_scanned(db)
+    assert rows(db, KNN + "published_at > 0") == [(17,), (18,), (19,)]
+    assert chunks_scanned(db) == 1
+
+    # later columns move down a slot along with their ranges
+    db.execute("insert into v(v) values ('drop_column=n')")
+    assert ranges(db) == [(1, 0, 0), (2, 0, 0), (3, 0, 1020)]
+    db.execute("alter table v rename to w")
+    assert ranges(db, "w_metadatachunks00") == [(1, 0, 0), (2, 0, 0), (3, 0, 1020)]
+    chunks_scanned(db)
+    assert rows(
+        db,
+        "select rowid from w where a match '[3, 3]' and k = 3 and published_at > 1018",
+    ) == [(19,), (20,)]
+    assert chunks_scanned(db) == 1
+    assert rows(db, "select * from vec0_integrity_check('w')") == []
+
+
+def test_timestamp_copy(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], published_at timestamp, chunk_size=8)"
+    )
+    fill(db, 20)
+    assert db.execute("select vec0_copy('v', 'c')").fetchone()[0] == 20
+    (snapshot,) = db.execute("select vec0_serialize('v')").fetchone()
+    assert rows(db, "select vec0_deserialize(?, 'd')", [snapshot]) == [("d",)]
+    for table in ["c", "d"]:
+        assert ranges(db, f"{table}_metadatachunks00") == ranges(db)
+        chunks_scanned(db)
+        assert rows(
+            db,
+            f"select rowid from {table} where a match '[3, 3]' and k = 1 "
+            "and published_at < 1010",
+        ) == [(3,)]
+        assert chunks_scanned(db) == 2
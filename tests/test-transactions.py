@@ -0,0 +1,121 @@
+import re
+import sqlite3
+import pytest
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def test_failed_writes_in_transaction(db):
+    db.execute(
+        "create virtual table v using vec0(a float[2], tag integer, +label text, terms sparse[10])"
+    )
+    db.execute("begin")
+    db.execute(
+        """insert into v(rowid, a, tag, label, terms) values (1, '[1, 1]', 1, 'a', '{"1": 1}')"""
+    )
+    for values, message in [
+        ("'[2, 2]', 'x', 'b', null", "Expected integer for INTEGER metadata column tag"),
+        ("'[2, 2]', 2, 'b', '{\"10\": 1}'", 'Invalid sparse vector for the "terms" column'),
+        ("'[2, 2, 2]', 2, 'b', null", 'Dimension mismatch for inserted vector for the "a" column'),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=re.escape(message)):
+            db.execute(
+                f"insert into v(rowid, a, tag, label, terms) values (2, {values})"
+            )
+
+    # also for updates: no column is changed when any new value is invalid
+    for assignments, message in [
+        ("label = 'new', a = '[1, 2, 3]'", 'Dimension mismatch for new updated vector for the "a" column'),
+        ("a = '[5, 5]', tag = 'x'", "Expected integer for INTEGER metadata column tag"),
+        ("tag = 5, terms = '[1]'", 'Invalid sparse vector for the "terms" column'),
+    ]:
+        with pytest.raises(sqlite3.OperationalError, match=re.escape(message)):
+            db.execute(f"update v set {assignments} where rowid = 1")
+
+    # multi-row statements are undone as a whole
+    with pytest.raises(
+        sqlite3.OperationalError, match="Expected integer for INTEGER metadata column tag"
+    ):
+        db.execute(
+            """
+            insert into v(rowid, a, tag)
+              select value, '[3, 3]', iif(value = 4, 'x', value) from json_each('[2, 3, 4]')
+            """
+        )
+    db.execute("insert into v(rowid, a, tag, label) values (5, '[5, 5]', 5, 'e')")
+    db.commit()
+
+    assert rows(db, "select rowid, vec_to_json(a), tag, label, terms is not null from v") == [
+        (1, "[1.000000,1.000000]", 1, "a", 1),
+        (5, "[5.000000,5.000000]", 5, "e", 0),
+    ]
+    assert rows(db, "select rowid, chunk_offset from v_rowids") == [(1, 0), (5, 1)]
+    assert rows(db, "select rowid from v_auxiliary") == [(1,), (5,)]
+    assert rows(db, "select rowid from v where a match '[2, 2]' and k = 5") == [
+        (1,),
+        (5,),
+    ]
+    assert rows(db, "select rowid from v where a match '[3, 3]' and k = 5 and tag = 5") == [
+        (5,)
+    ]
+
+
+def test_rollback_to_savepoint(db):
+    db.execute(
+        """
+        create virtual table v using vec0(
+          a float[2] quantize=int8,
+          b float[2] index=ivf(nlist=2),
+          c float[2] quantize=pq(m=1)
+        )
+        """
+    )
+    db.execute("begin")
+    db.execute("savepoint s")
+    db.execute("insert into v(rowid, a, b, c) values (1, '[0, 10]', '[1, 1]', '[1, 1]')")
+    db.execute("rollback to s")
+    assert rows(db, "select count(*) from v") == [(0,)]
+
+    # the int8 range learned from the rolled back row is forgotten
+    db.execute(
+        "insert into v(rowid, a, b, c) values (1, '[0, 1]', '[1, 1]', '[1, 1]'), (2, '[1, 0]', '[9, 9]', '[9, 9]')"
+    )
+    assert rows(
+        db, "select value from v_info where key = 'quantize00_scale'"
+    ) == [(pytest.approx(1 / 255),)]
+
+    # and so are IVF centroids and PQ codebooks trained after the savepoint
+    db.execute("savepoint s")
+    db.execute("insert into v(v) values ('train')")
+    assert rows(db, "select count(*) from v_ivfcentroids01") == [(2,)]
+    assert rows(db, "select count(*) from v_pqcodebooks02") == [(1,)]
+    db.execute("rollback to s")
+    db.execute("insert into v(rowid, a, b, c) values (3, '[1, 1]', '[8, 8]', '[8, 8]')")
+    db.commit()
+
+    assert rows(db, "select count(*) from v_ivfcentroids01") == [(0,)]
+    assert rows(db, "select centroid_id, rowid from v_ivflists01") == [
+        (-1, 1),
+        (-1, 2),
+        (-1, 3),
+    ]
+    assert rows(db, "select rowid from v where b match '[9, 9]' and k = 3") == [
+        (2,),
+        (3,),
+        (1,),
+    ]
+    assert rows(db, "select rowid, vec_to_json(c) from v") == [
+        (1, "[1.000000,1.000000]"),
+        (2, "[9.000000,9.000000]"),
+        (3, "[8.000000,8.000000]"),
+    ]
+
+    # released savepoints keep their writes
+    db.execute("begin")
+    db.execute("savepoint s")
+    db.execute("insert into v(rowid, a, b, c) values (4, '[1, 1]', '[1, 1]', '[1, 1]')")
+    db.execute("release s")
+    db.commit()
+    assert rows(db, "select count(*) from v") == [(4,)]
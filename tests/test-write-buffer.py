@@ -0,0 +1,159 @@
+import sqlite3
+import struct
+import pytest
+
+
+def _f32(list):
+    return struct.pack("%sf" % len(list), *list)
+
+
+def rows(db, sql, params=[]):
+    return [tuple(row) for row in db.execute(sql, params).fetchall()]
+
+
+def connect(path=":memory:"):
+    db = sqlite3.connect(path, isolation_level=None)
+    db.enable_load_extension(True)
+    db.load_extension("dist/vec0")
+    return db
+
+
+def shadow_rowids(db):
+    return rows(db, "select rowid from v_rowids order by rowid")
+
+
+def test_write_buffer(tmp_path):
+    path = str(tmp_path / "buffered.db")
+    db = connect(path)
+    db.execute(
+        "create virtual table v using vec0(a float[2], b int8[2], label text, "
+        "+note text, write_buffer=100)"
+    )
+    db.execute("begin")
+    for i in range(1, 4):
+        db.execute(
+            "insert into v(rowid, a, b, label, note) values (?, ?, vec_int8(?), 'x', ?)",
+            [i, _f32([i, i]), "[%d, 1]" % i, "n%d" % i],
+        )
+    # held in memory, until the table is read
+    assert shadow_rowids(db) == []
+    assert rows(db, "select rowid from v where a match '[2.1, 2.1]' and k = 1") == [(2,)]
+    assert shadow_rowids(db) == [(1,), (2,), (3,)]
+
+    # or until an explicit flush
+    db.execute("insert into v(rowid, a, b, label) values (4, '[4, 4]', vec_int8('[4, 1]'), 'y')")
+    assert shadow_rowids(db) == [(1,), (2,), (3,)]
+    db.execute("insert into v(v) values ('flush')")
+    assert shadow_rowids(db) == [(1,), (2,), (3,), (4,)]
+
+    # or until the transaction commits
+    db.execute("insert into v(rowid, a, b, label) values (5, '[5, 5]', vec_int8('[5, 1]'), 'x')")
+    # updates and deletes see the buffered rows
+    db.execute("insert into v(rowid, a, b, label) values (6, '[6, 6]', vec_int8('[6, 1]'), 'x')")
+    db.execute("update v set note = 'changed' where rowid = 6")
+    db.execute("insert into v(rowid, a, b, label) values (7, '[7, 7]', vec_int8('[7, 1]'), 'x')")
+    db.execute("delete from v where rowid = 7")
+    db.execute("insert into v(rowid, a, b, label) values (8, '[8, 8]', vec_int8('[8, 1]'), 'y')")
+    db.execute("commit")
+    db.close()
+
+    db = connect(path)
+    assert rows(db, "select rowid, vec_to_json(a), vec_to_json(b), label, note from v") == [
+        (1, "[1.000000,1.000000]", "[1,1]", "x", "n1"),
+        (2, "[2.000000,2.000000]", "[2,1]", "x", "n2"),
+        (3, "[3.000000,3.000000]", "[3,1]", "x", "n3"),
+        (4, "[4.000000,4.000000]", "[4,1]", "y", None),
+        (5, "[5.000000,5.000000]", "[5,1]", "x", None),
+        (6, "[6.000000,6.000000]", "[6,1]", "x", "changed"),
+        (8, "[8.000000,8.000000]", "[8,1]", "y", None),
+    ]
+    assert rows(db, "select rowid from v where b match vec_int8('[8, 1]') and k = 1 and label = 'x'") == [(6,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_write_buffer_rollback():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[2], write_buffer=100)")
+    db.execute("begin")
+    db.execute("insert into v(rowid, a) values (1, '[1, 1]')")
+    db.execute("rollback")
+    assert rows(db, "select rowid from v") == []
+
+    # a failed statement only discards its own rows
+    db.execute("begin")
+    db.execute("insert into v(rowid, a) values (1, '[1, 1]')")
+    with pytest.raises(sqlite3.OperationalError, match="Dimension mismatch"):
+        db.execute("insert into v(rowid, a) values (2, '[2, 2]'), (3, '[3]')")
+    db.execute("commit")
+    assert rows(db, "select rowid from v") == [(1,)]
+
+    # savepoints roll back buffered rows, flushed or not
+    db.execute("begin")
+    db.execute("insert into v(rowid, a) values (2, '[2, 2]')")
+    db.execute("savepoint s")
+    db.execute("insert into v(rowid, a) values (3, '[3, 3]')")
+    assert rows(db, "select count(*) from v") == [(3,)]
+    db.execute("insert into v(rowid, a) values (4, '[4, 4]')")
+    db.execute("rollback to s")
+    db.execute("insert into v(rowid, a) values (5, '[5, 5]')")
+    db.execute("release s")
+    db.execute("commit")
+    assert rows(db, "select rowid from v") == [(1,), (2,), (5,)]
+    assert rows(db, "select * from vec0_integrity_check('v')") == []
+
+
+def test_write_buffer_rowids():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[2], write_buffer=100)")
+    db.execute("insert into v(rowid, a) values (7, '[7, 7]')")
+    db.execute("begin")
+    # new rowids come after those of the table and of the buffered rows
+    assert db.execute("insert into v(a) values ('[8, 8]')").lastrowid == 8
+    assert db.execute("insert into v(a) values ('[9, 9]')").lastrowid == 9
+    db.execute("insert into v(rowid, a) values (20, '[20, 20]')")
+    assert db.execute("insert into v(a) values ('[21, 21]')").lastrowid == 21
+    for rowid in [7, 9]:
+        with pytest.raises(
+            sqlite3.OperationalError, match="UNIQUE constraint failed on v primary key"
+        ):
+            db.execute("insert into v(rowid, a) values (?, '[0, 0]')", [rowid])
+    with pytest.raises(sqlite3.OperationalError, match="Only integers are allows"):
+        db.execute("insert into v(rowid, a) values ('x', '[0, 0]')")
+    db.execute("commit")
+    assert rows(db, "select rowid from v") == [(7,), (8,), (9,), (20,), (21,)]
+
+
+def test_write_buffer_size():
+    db = connect()
+    db.execute("create virtual table v using vec0(a float[2], write_buffer=2)")
+    db.execute("begin")
+    db.execute("insert into v(rowid, a) values (1, '[1, 1]')")
+    assert shadow_rowids(db) == []
+    # a full buffer is flushed right away
+    db.execute("insert into v(rowid, a) values (2, '[2, 2]')")
+    assert shadow_rowids(db) == [(1,), (2,)]
+    db.execute("insert into v(rowid, a) values (3, '[3, 3]')")
+    assert shadow_rowids(db) == [(1,), (2,)]
+    db.execute("commit")
+    assert shadow_rowids(db) == [(1,), (2,), (3,)]
+
+    # flush is a no-op on tables without a write buffer
+    db.execute("create virtual table u using vec0(a float[2])")
+    db.execute("insert into u(u) values ('flush')")
+
+
+def test_write_buffer_errors():
+    db = connect()
+    for size in ["0", "65537", "x"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="vec0 constructor error: write_buffer must be an integer between 1 and 65536",
+        ):
+            db.execute(f"create virtual table v using vec0(a float[2], write_buffer={size})")
+    for key in ["id text primary key", "a integer, b text, primary key (a, b)"]:
+        with pytest.raises(
+            sqlite3.OperationalError,
+            match="vec0 constructor error: write_buffer can't be combined with a "
+            "TEXT or composite primary key",
+        ):
+            db.execute(f"create virtual table v using vec0({key}, c float[2], write_buffer=10)")